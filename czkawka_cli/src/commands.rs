@@ -1,4 +1,8 @@
+use czkawka_core::common::Common;
+use czkawka_core::common_actions::FileAction;
+use czkawka_core::common_file_type::FileType;
 use czkawka_core::duplicate::{CheckingMethod, DeleteMethod, HashType};
+use czkawka_core::quarantine::Quarantine;
 use czkawka_core::same_music::MusicSimilarity;
 use czkawka_core::similar_images::Similarity;
 use std::path::PathBuf;
@@ -7,30 +11,48 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 #[structopt(name = "czkawka", help_message = HELP_MESSAGE, template = HELP_TEMPLATE)]
 pub enum Commands {
-    #[structopt(name = "dup", about = "Finds duplicate files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka dup -d /home/rafal -e /home/rafal/Obrazy  -m 25 -x 7z rar IMAGE -s hashmb -f results.txt -D aeo")]
+    #[structopt(name = "dup", about = "Finds duplicate files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka dup -d /home/rafal -e /home/rafal/Obrazy  -m 25 -x 7z rar IMAGE -s hashmb -f results.txt -D aeo\n    czkawka dup -d /home/rafal -D aen --trash\n    czkawka dup -d /home/rafal -D aen --quarantine /home/rafal/quarantine\n    czkawka dup -d /mnt/btrfs -D dedupe")]
     Duplicates {
         #[structopt(flatten)]
         directories: Directories,
         #[structopt(flatten)]
         excluded_directories: ExcludedDirectories,
         #[structopt(flatten)]
+        reference_directories: ReferenceDirectories,
+        #[structopt(flatten)]
         excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
         #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "1024", help = "Minimum size in bytes", long_help = "Minimum size of checked files in bytes, assigning bigger value may speed up searching")]
         minimal_file_size: u64,
         #[structopt(short = "c", long, parse(try_from_str = parse_minimal_file_size), default_value = "2097152", help = "Minimum cached file size in bytes", long_help = "Minimum size of cached files in bytes, assigning bigger value may speed up will cause that lower amount of files will be cached, but loading of cache will be faster")]
         minimal_cached_file_size: u64,
         #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
+        #[structopt(flatten)]
+        content_types: ContentTypes,
         #[structopt(short, long, default_value = "HASH", parse(try_from_str = parse_checking_method), help = "Search method (NAME, SIZE, HASH, HASHMB)", long_help = "Methods to search files.\nNAME - Fast but but rarely usable,\nSIZE - Fast but not accurate, checking by the file's size,\nHASHMB - More accurate but slower, checking by the hash of the file's first mebibyte\nHASH - The slowest method, checking by the hash of the entire file")]
         search_method: CheckingMethod,
-        #[structopt(short = "D", long, default_value = "NONE", parse(try_from_str = parse_delete_method), help = "Delete method (AEN, AEO, ON, OO, HARD)", long_help = "Methods to delete the files.\nAEN - All files except the newest,\nAEO - All files except the oldest,\nON - Only 1 file, the newest,\nOO - Only 1 file, the oldest\nHARD - create hard link\nNONE - not delete files")]
+        #[structopt(short = "D", long, default_value = "NONE", parse(try_from_str = parse_delete_method), help = "Delete method (AEN, AEO, ON, OO, HARD, SYMLINK, RELSYMLINK, DEDUPE)", long_help = "Methods to delete the files.\nAEN - All files except the newest,\nAEO - All files except the oldest,\nON - Only 1 file, the newest,\nOO - Only 1 file, the oldest\nHARD - create hard link\nSYMLINK - replace files with symlinks to the oldest file\nRELSYMLINK - replace files with relative symlinks to the oldest file\nDEDUPE - share data of files with the oldest file on copy-on-write file systems(Linux only)\nNONE - not delete files")]
         delete_method: DeleteMethod,
+        #[structopt(flatten)]
+        delete_action: DeleteAction,
         #[structopt(short, long, default_value = "BLAKE3", parse(try_from_str = parse_hash_type),
             help="Hash type (BLAKE3, CRC32, XXH3)")]
         hash_type: HashType,
         #[structopt(flatten)]
+        cache_location: CacheLocation,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[structopt(flatten)]
         allow_hard_links: AllowHardLinks,
@@ -45,12 +67,22 @@ pub enum Commands {
         excluded_directories: ExcludedDirectories,
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
         #[structopt(short = "D", long, help = "Delete found folders")]
         delete_folders: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
+        #[structopt(flatten)]
+        show_progress: ShowProgress,
     },
-    #[structopt(name = "big", about = "Finds big files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka big -d /home/rafal/ /home/piszczal -e /home/rafal/Roman -n 25 -x VIDEO -f results.txt")]
+    #[structopt(name = "big", about = "Finds big files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka big -d /home/rafal/ /home/piszczal -e /home/rafal/Roman -n 25 -x VIDEO -f results.txt\n    czkawka big -d /home/rafal/ --content-types video archive")]
     BiggestFiles {
         #[structopt(flatten)]
         directories: Directories,
@@ -59,14 +91,28 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
+        #[structopt(flatten)]
+        content_types: ContentTypes,
         #[structopt(short, long, default_value = "50", help = "Number of files to be shown")]
         number_of_files: usize,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
     #[structopt(name = "empty-files", about = "Finds empty files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka empty-files -d /home/rafal /home/szczekacz -e /home/rafal/Pulpit -R -f results.txt")]
@@ -78,12 +124,24 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
     #[structopt(name = "temp", about = "Finds temporary files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka temp -d /home/rafal/ -E */.git */tmp* *Pulpit -f results.txt -D")]
@@ -94,19 +152,33 @@ pub enum Commands {
         excluded_directories: ExcludedDirectories,
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
-    #[structopt(name = "image", about = "Finds similar images", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka image -d /home/rafal/ -E */.git */tmp* *Pulpit -f results.txt")]
+    #[structopt(name = "image", about = "Finds similar images", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka image -d /home/rafal/ -E */.git */tmp* *Pulpit -f results.txt\n    czkawka image -d /media/camera --detect-by-content")]
     SimilarImages {
         #[structopt(flatten)]
         directories: Directories,
         #[structopt(flatten)]
         excluded_directories: ExcludedDirectories,
+        #[structopt(flatten)]
+        reference_directories: ReferenceDirectories,
         #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "16384", help = "Minimum size in bytes", long_help = "Minimum size of checked files in bytes, assigning bigger value may speed up searching")]
         minimal_file_size: u64,
         #[structopt(short, long, default_value = "High", parse(try_from_str = parse_similar_images_similarity), help = "Similairty level (Minimal, VerySmall, Small, Medium, High, Very High)", long_help = "Methods to choose similarity level of images which will be considered as duplicated.")]
@@ -114,8 +186,22 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
+        detect_by_content: DetectByContent,
+        #[structopt(flatten)]
+        cache_location: CacheLocation,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
     #[structopt(name = "zeroed", about = "Finds zeroed files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka zeroed -d /home/rafal -e /home/rafal/Pulpit -f results.txt")]
@@ -127,12 +213,24 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "1024", help = "Minimum size in bytes", long_help = "Minimum size of checked files in bytes, assigning bigger value may speed up searching")]
         minimal_file_size: u64,
@@ -144,7 +242,19 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_directories: ExcludedDirectories,
         #[structopt(flatten)]
+        reference_directories: ReferenceDirectories,
+        #[structopt(flatten)]
         excluded_items: ExcludedItems,
+        #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
+        detect_by_content: DetectByContent,
         // #[structopt(short = "D", long, help = "Delete found files")]
         // delete_files: bool, TODO
         #[structopt(short = "z", long, default_value = "artist,title", parse(try_from_str = parse_music_duplicate_type), help = "Search method (title, artist, album_title, album_artist, year)", long_help = "Sets which rows must be equal to set this files as duplicates(may be mixed, but must be divided by commas).")]
@@ -152,6 +262,8 @@ pub enum Commands {
         #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
         #[structopt(short, long, parse(try_from_str = parse_minimal_file_size), default_value = "1024", help = "Minimum size in bytes", long_help = "Minimum size of checked files in bytes, assigning bigger value may speed up searching")]
         minimal_file_size: u64,
@@ -165,12 +277,22 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
     #[structopt(name = "broken", about = "Finds broken files", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka broken -d /home/kicikici/ /home/szczek -e /home/kicikici/jestempsem -x jpg -f results.txt")]
@@ -182,14 +304,146 @@ pub enum Commands {
         #[structopt(flatten)]
         excluded_items: ExcludedItems,
         #[structopt(flatten)]
+        use_ignore_files: UseIgnoreFiles,
+        #[structopt(flatten)]
+        traversal_limits: TraversalLimits,
+        #[structopt(flatten)]
+        file_filters: FileFilters,
+        #[structopt(flatten)]
+        follow_symlinks: FollowSymlinks,
+        #[structopt(flatten)]
         allowed_extensions: AllowedExtensions,
+        #[structopt(flatten)]
+        detect_by_content: DetectByContent,
         #[structopt(short = "D", long, help = "Delete found files")]
         delete_files: bool,
         #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
+        cache_location: CacheLocation,
+        #[structopt(flatten)]
         file_to_save: FileToSave,
         #[structopt(flatten)]
+        show_progress: ShowProgress,
+        #[structopt(flatten)]
         not_recursive: NotRecursive,
     },
+    #[structopt(name = "import", about = "Applies actions marked in file with saved results", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka import -f results.json --dryrun")]
+    ImportResults {
+        #[structopt(
+            short = "f",
+            long,
+            parse(from_os_str),
+            required = true,
+            help = "File with saved results",
+            long_help = "File with results saved in JSON(.json) or JSON Lines(.jsonl) format, entries marked with \"action\": \"delete\" are removed. Text results are refused, because they don't contain size and modification date of files"
+        )]
+        file_to_load: PathBuf,
+        #[structopt(flatten)]
+        reference_directories: ReferenceDirectories,
+        #[structopt(flatten)]
+        delete_action: DeleteAction,
+        #[structopt(flatten)]
+        dryrun: DryRun,
+    },
+    #[structopt(name = "undo", about = "Restores files moved to quarantine", help_message = HELP_MESSAGE, after_help = "EXAMPLE:\n    czkawka undo /home/rafal/quarantine/czkawka_journal_2021-01-20_12-00-00.jsonl --dryrun")]
+    Undo {
+        #[structopt(
+            parse(from_os_str),
+            help = "Journal of quarantine",
+            long_help = "Journal saved inside quarantine folder, files which were changed in quarantine or whose original path is used by other file are not restored"
+        )]
+        journal: PathBuf,
+        #[structopt(flatten)]
+        dryrun: DryRun,
+    },
+    #[structopt(
+        name = "cache",
+        about = "Shows statistics of cache files, removes outdated entries and moves cache between computers",
+        help_message = HELP_MESSAGE,
+        after_help = "EXAMPLE:\n    czkawka cache clean --max-size 104857600\n    czkawka cache export -f cache.bin --base-path /media/rafal/disk"
+    )]
+    Cache {
+        #[structopt(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum CacheAction {
+    #[structopt(name = "stats", about = "Shows size, number of entries and hit rate during last search of each cache file", help_message = HELP_MESSAGE)]
+    Stats {
+        #[structopt(flatten)]
+        cache_dir: CacheDir,
+    },
+    #[structopt(name = "clean", about = "Removes entries of files which no longer exist or were modified", help_message = HELP_MESSAGE)]
+    Clean {
+        #[structopt(
+            long,
+            parse(try_from_str = parse_minimal_file_size),
+            help = "Maximum size of each cache file in bytes",
+            long_help = "Maximum size of each cache file in bytes, when cache is bigger, entries of files with oldest modification date are removed"
+        )]
+        max_size: Option<u64>,
+        #[structopt(flatten)]
+        cache_dir: CacheDir,
+    },
+    #[structopt(name = "clear", about = "Removes all cache files", help_message = HELP_MESSAGE)]
+    Clear {
+        #[structopt(flatten)]
+        cache_dir: CacheDir,
+    },
+    #[structopt(name = "export", about = "Saves all cache files to one file, which can be imported on other computer", help_message = HELP_MESSAGE)]
+    Export {
+        #[structopt(short = "f", long, parse(from_os_str), required = true, help = "File to which cache is exported")]
+        file: PathBuf,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Exports only files from this folder",
+            long_help = "Exports only entries of files inside this folder, with paths relative to it, e.g. mount point of external drive. Such cache must be imported with --base-path"
+        )]
+        base_path: Option<PathBuf>,
+        #[structopt(flatten)]
+        cache_dir: CacheDir,
+    },
+    #[structopt(name = "import", about = "Adds entries from exported cache to cache files, entries already in cache are kept", help_message = HELP_MESSAGE)]
+    Import {
+        #[structopt(short = "f", long, parse(from_os_str), required = true, help = "File with exported cache")]
+        file: PathBuf,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "Folder to which relative paths are joined",
+            long_help = "Folder to which relative paths of entries exported with --base-path are joined, e.g. mount point of same external drive on this computer"
+        )]
+        base_path: Option<PathBuf>,
+        #[structopt(flatten)]
+        cache_dir: CacheDir,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CacheDir {
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Folder with cache files",
+        long_help = "Folder with cache files, by default cache folder of system is used. Portable cache of volume is placed in .czkawka_cache folder in its root"
+    )]
+    pub cache_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CacheLocation {
+    #[structopt(flatten)]
+    pub cache_dir: CacheDir,
+    #[structopt(
+        long,
+        help = "Saves cache on scanned volumes",
+        long_help = "Cache of files from scanned volume is saved in .czkawka_cache folder in root of this volume, with paths relative to it, so it is used also when volume is mounted in other place or on other computer"
+    )]
+    pub portable_cache: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -204,10 +458,94 @@ pub struct ExcludedDirectories {
     pub excluded_directories: Vec<PathBuf>,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct ReferenceDirectories {
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Reference directorie(s)",
+        long_help = "List of directorie(s) which are searched, but files inside them are never deleted or replaced and groups with only such files are not shown(absolute path)"
+    )]
+    pub reference_directories: Vec<PathBuf>,
+}
+
 #[derive(Debug, StructOpt)]
 pub struct ExcludedItems {
-    #[structopt(short = "E", long, help = "Excluded item(s)", long_help = "List of excluded item(s) which contains * wildcard(may be slow, so use -e where possible)")]
+    #[structopt(
+        short = "E",
+        long,
+        help = "Excluded item(s)",
+        long_help = "List of excluded item(s) as glob patterns(*, **, ?, [abc], {a,b}), prefix ! includes again previously excluded paths and prefix regex: allows to use regular expression(may be slow, so use -e where possible). In items which contain only * wildcards, * matches also path separator like in older versions"
+    )]
     pub excluded_items: Vec<String>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Read excluded items from file(s)",
+        long_help = "List of file(s) with rules in .gitignore format, which are applied relatively to each searched directory"
+    )]
+    pub exclude_from: Vec<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct UseIgnoreFiles {
+    #[structopt(
+        long = "ignore-files",
+        help = "Use rules from .gitignore, .ignore and .czkawkaignore files",
+        long_help = "Rules from .gitignore, .ignore and .czkawkaignore files found in searched folders are used to exclude files from their folder and subfolders"
+    )]
+    pub use_ignore_files: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TraversalLimits {
+    #[structopt(
+        long,
+        value_name = "depth",
+        help = "Maximum depth of checked subfolders",
+        long_help = "Maximum number of subfolder levels checked below included directories, 0 means that only files placed directly in included directories are checked"
+    )]
+    pub max_depth: Option<usize>,
+    #[structopt(
+        long,
+        help = "Doesn't check folders placed on other file systems",
+        long_help = "Folders placed on other file systems than included directory(mount points like /proc, network shares or external disks) are skipped"
+    )]
+    pub one_file_system: bool,
+    #[structopt(long, help = "Skips hidden files and folders", long_help = "Skips files and folders which names start with a dot")]
+    pub skip_hidden: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FileFilters {
+    #[structopt(long, help = "Maximum size in bytes", long_help = "Maximum size of checked files in bytes, bigger files are skipped")]
+    pub maximal_file_size: Option<u64>,
+    #[structopt(
+        long,
+        parse(try_from_str = Common::parse_date),
+        value_name = "date",
+        help = "Checks only files modified after this date",
+        long_help = "Checks only files modified at this date or later, date may be given in UTC like 2021-03-01 or \"2021-03-01 12:30:00\" or as time before now like 12h, 30d, 2w or 1y"
+    )]
+    pub modified_after: Option<u64>,
+    #[structopt(
+        long,
+        parse(try_from_str = Common::parse_date),
+        value_name = "date",
+        help = "Checks only files modified before this date",
+        long_help = "Checks only files modified at this date or earlier, date may be given in UTC like 2021-03-01 or \"2021-03-01 12:30:00\" or as time before now like 12h, 30d, 2w or 1y"
+    )]
+    pub modified_before: Option<u64>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FollowSymlinks {
+    #[structopt(
+        long,
+        help = "Follows symlinks to files and folders",
+        long_help = "Symlinks to files and folders are checked like their destinations, each folder is checked only once and symlink loops are reported as warnings"
+    )]
+    pub follow_symlinks: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -216,9 +554,35 @@ pub struct AllowedExtensions {
         short = "x",
         long,
         help = "Allowed file extension(s)",
-        long_help = "List of checked files with provided extension(s). There are also helpful macros which allow to easy use a typical extensions like:\nIMAGE(\"jpg,kra,gif,png,bmp,tiff,hdr,svg\"),\nTEXT(\"txt,doc,docx,odt,rtf\"),\nVIDEO(\"mp4,flv,mkv,webm,vob,ogv,gifv,avi,mov,wmv,mpg,m4v,m4p,mpeg,3gp\") or\nMUSIC(\"mp3,flac,ogg,tta,wma,webm\")\n "
+        long_help = "List of checked files with provided extension(s). There are also helpful macros which allow to easy use a typical extensions like:\nIMAGE(\"jpg,kra,gif,png,bmp,tiff,hdr,svg\"),\nTEXT(\"txt,doc,docx,odt,rtf\"),\nVIDEO(\"mp4,flv,mkv,webm,vob,ogv,gifv,avi,mov,wmv,mpg,m4v,m4p,mpeg,3gp\") or\nMUSIC(\"mp3,flac,ogg,tta,wma,webm\").\nOwn macros can be defined in extension_macros.txt file in config folder, each in separate line like RAW=cr2,nef,arw\n "
     )]
     pub allowed_extensions: Vec<String>,
+    #[structopt(
+        long,
+        help = "Excluded file extension(s)",
+        long_help = "List of extension(s) of files which are never checked, even when they are allowed. Same macros as in allowed extensions can be used"
+    )]
+    pub excluded_extensions: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ContentTypes {
+    #[structopt(
+        long,
+        help = "Checks only files with this content type(s) (image, audio, video, archive)",
+        long_help = "List of content types of checked files, type is recognized by first bytes of file, so also files with missing or wrong extension like camera dumps saved as .dat are found. Files with unknown content are skipped"
+    )]
+    pub content_types: Vec<FileType>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DetectByContent {
+    #[structopt(
+        long,
+        help = "Recognizes type of files by their content",
+        long_help = "Files are checked when their content has supported type, even if extension is missing or wrong, files with supported extension but different content are skipped"
+    )]
+    pub detect_by_content: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -229,7 +593,7 @@ pub struct NotRecursive {
 
 #[derive(Debug, StructOpt)]
 pub struct FileToSave {
-    #[structopt(short, long, value_name = "file-name", help = "Saves the results into the file")]
+    #[structopt(short, long, value_name = "file-name", help = "Saves the results into the file, files with .json, .jsonl or .csv extension are saved in JSON, JSON Lines or CSV format")]
     pub file_to_save: Option<PathBuf>,
 }
 
@@ -239,12 +603,53 @@ pub struct AllowHardLinks {
     pub allow_hard_links: bool,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct ShowProgress {
+    #[structopt(long = "progress", help = "Show progress of search", long_help = "Show current stage, number of checked files and bytes and estimated time left of search on stderr")]
+    pub show_progress: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DeleteAction {
+    #[structopt(long, help = "Moves deleted files to trash", long_help = "Deleted files are moved to trash of system instead of being removed permanently, so they can be restored")]
+    pub trash: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with = "trash",
+        value_name = "directory",
+        help = "Moves deleted files to this folder",
+        long_help = "Deleted files are moved to this folder instead of being removed, files with same names are not overwritten, but number is added to their names"
+    )]
+    pub move_to: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["trash", "move-to"],
+        value_name = "directory",
+        help = "Moves deleted files to quarantine folder",
+        long_help = "Deleted files are moved to this folder with their full original path and saved in journal, so they can be restored later with undo command"
+    )]
+    pub quarantine: Option<PathBuf>,
+}
+
 #[derive(Debug, StructOpt)]
 pub struct DryRun {
     #[structopt(long, help = "Do nothing and print the operation that would happen.")]
     pub dryrun: bool,
 }
 
+impl DeleteAction {
+    pub fn file_action(&self) -> FileAction {
+        match (&self.move_to, &self.quarantine) {
+            (Some(directory), _) => FileAction::MoveToDirectory(directory.clone()),
+            (None, Some(directory)) => FileAction::MoveToQuarantine(Quarantine::new(directory.clone())),
+            (None, None) if self.trash => FileAction::MoveToTrash,
+            (None, None) => FileAction::Delete,
+        }
+    }
+}
+
 impl FileToSave {
     pub fn file_name(&self) -> Option<&str> {
         if let Some(file_name) = &self.file_to_save {
@@ -280,9 +685,12 @@ fn parse_delete_method(src: &str) -> Result<DeleteMethod, &'static str> {
         "aen" => Ok(DeleteMethod::AllExceptNewest),
         "aeo" => Ok(DeleteMethod::AllExceptOldest),
         "hard" => Ok(DeleteMethod::HardLink),
+        "symlink" => Ok(DeleteMethod::SymLink),
+        "relsymlink" => Ok(DeleteMethod::RelativeSymLink),
+        "dedupe" => Ok(DeleteMethod::Dedupe),
         "on" => Ok(DeleteMethod::OneNewest),
         "oo" => Ok(DeleteMethod::OneOldest),
-        _ => Err("Couldn't parse the delete method (allowed: AEN, AEO, ON, OO, HARD)"),
+        _ => Err("Couldn't parse the delete method (allowed: AEN, AEO, ON, OO, HARD, SYMLINK, RELSYMLINK, DEDUPE)"),
     }
 }
 
@@ -369,4 +777,9 @@ EXAMPLES:
     {bin} zeroed -d /home/rafal -e /home/krzak -f results.txt"
     {bin} music -d /home/rafal -e /home/rafal/Pulpit -z "artist,year, ARTISTALBUM, ALBUM___tiTlE"  -f results.txt
     {bin} symlinks -d /home/kicikici/ /home/szczek -e /home/kicikici/jestempsem -x jpg -f results.txt
-    {bin} broken -d /home/mikrut/ -e /home/mikrut/trakt -f results.txt"#;
+    {bin} broken -d /home/mikrut/ -e /home/mikrut/trakt -f results.txt
+    {bin} import -f results.json --dryrun
+    {bin} cache clean --max-size 104857600
+    {bin} image -d /media/rafal/disk --portable-cache -f results.txt
+    {bin} dup -d /home/rafal --excluded-extensions iso vmdk -f results.txt
+    {bin} zeroed -d /home/rafal --maximal-file-size 1073741824 --modified-after 1y -f results.txt"#;
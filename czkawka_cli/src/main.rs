@@ -1,6 +1,8 @@
 mod commands;
+mod progress;
 
-use commands::Commands;
+use commands::{AllowedExtensions, CacheAction, CacheDir, Commands, DeleteAction};
+use progress::ProgressPrinter;
 
 #[allow(unused_imports)] // It is used in release for print_results().
 use czkawka_core::common_traits::*;
@@ -8,16 +10,24 @@ use czkawka_core::common_traits::*;
 use czkawka_core::{
     big_file::{self, BigFile},
     broken_files::{self, BrokenFiles},
+    common_actions::FileAction,
+    common_cache::{clean_cache, clear_cache, export_cache, get_cache_dir, get_cache_files_info, import_cache},
+    common_extensions::ExtensionMacros,
+    common_messages::Messages,
+    common_scanner::ScanOptions,
     duplicate::DuplicateFinder,
     empty_files::{self, EmptyFiles},
     empty_folder::EmptyFolder,
     invalid_symlinks,
     invalid_symlinks::InvalidSymlinks,
+    quarantine::UndoQuarantine,
+    results_import::ResultsImport,
     same_music::SameMusic,
     similar_images::SimilarImages,
     temporary::{self, Temporary},
     zeroed::{self, ZeroedFiles},
 };
+use std::path::PathBuf;
 use std::process;
 use structopt::StructOpt;
 
@@ -31,34 +41,65 @@ fn main() {
         Commands::Duplicates {
             directories,
             excluded_directories,
+            reference_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             minimal_file_size,
             minimal_cached_file_size,
+            cache_location,
             allowed_extensions,
+            content_types,
             search_method,
             delete_method,
+            delete_action,
             hash_type,
             file_to_save,
+            show_progress,
             not_recursive,
             allow_hard_links,
             dryrun,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                reference_directories: reference_directories.reference_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                allowed_extensions,
+                excluded_extensions,
+                content_types: content_types.content_types,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut df = DuplicateFinder::new();
 
-            df.set_included_directory(directories.directories);
-            df.set_excluded_directory(excluded_directories.excluded_directories);
-            df.set_excluded_items(excluded_items.excluded_items);
+            df.set_scan_options(&scan_options);
             df.set_minimal_file_size(minimal_file_size);
             df.set_minimal_cache_file_size(minimal_cached_file_size);
-            df.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
+            df.set_cache_dir(cache_location.cache_dir.cache_dir);
+            df.set_portable_cache(cache_location.portable_cache);
             df.set_check_method(search_method);
             df.set_delete_method(delete_method);
+            df.set_delete_action(get_file_action(&delete_action));
             df.set_hash_type(hash_type);
-            df.set_recursive_search(!not_recursive.not_recursive);
             df.set_ignore_hard_links(!allow_hard_links.allow_hard_links);
             df.set_dryrun(dryrun.dryrun);
 
-            df.find_duplicates(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            df.find_duplicates(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !df.save_results_to_file(file_name) {
@@ -74,18 +115,38 @@ fn main() {
         Commands::EmptyFolders {
             directories,
             delete_folders,
+            delete_action,
             file_to_save,
+            show_progress,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
         } => {
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                ..ScanOptions::new()
+            };
             let mut ef = EmptyFolder::new();
 
-            ef.set_included_directory(directories.directories);
-            ef.set_excluded_directory(excluded_directories.excluded_directories);
-            ef.set_excluded_items(excluded_items.excluded_items);
+            ef.set_scan_options(&scan_options);
             ef.set_delete_folder(delete_folders);
+            ef.set_delete_action(get_file_action(&delete_action));
 
-            ef.find_empty_folders(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            ef.find_empty_folders(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !ef.save_results_to_file(file_name) {
@@ -102,25 +163,51 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             allowed_extensions,
+            content_types,
             number_of_files,
             file_to_save,
+            show_progress,
             not_recursive,
             delete_files,
+            delete_action,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                allowed_extensions,
+                excluded_extensions,
+                content_types: content_types.content_types,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut bf = BigFile::new();
 
-            bf.set_included_directory(directories.directories);
-            bf.set_excluded_directory(excluded_directories.excluded_directories);
-            bf.set_excluded_items(excluded_items.excluded_items);
-            bf.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
+            bf.set_scan_options(&scan_options);
             bf.set_number_of_files_to_check(number_of_files);
-            bf.set_recursive_search(!not_recursive.not_recursive);
             if delete_files {
                 bf.set_delete_method(big_file::DeleteMethod::Delete);
             }
+            bf.set_delete_action(get_file_action(&delete_action));
 
-            bf.find_big_files(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            bf.find_big_files(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !bf.save_results_to_file(file_name) {
@@ -137,24 +224,49 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             allowed_extensions,
             delete_files,
+            delete_action,
             file_to_save,
+            show_progress,
             not_recursive,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                allowed_extensions,
+                excluded_extensions,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut ef = EmptyFiles::new();
 
-            ef.set_included_directory(directories.directories);
-            ef.set_excluded_directory(excluded_directories.excluded_directories);
-            ef.set_excluded_items(excluded_items.excluded_items);
-            ef.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            ef.set_recursive_search(!not_recursive.not_recursive);
+            ef.set_scan_options(&scan_options);
 
             if delete_files {
                 ef.set_delete_method(empty_files::DeleteMethod::Delete);
             }
 
-            ef.find_empty_files(None, None);
+            ef.set_delete_action(get_file_action(&delete_action));
+
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            ef.find_empty_files(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !ef.save_results_to_file(file_name) {
@@ -171,22 +283,45 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             delete_files,
+            delete_action,
             file_to_save,
+            show_progress,
             not_recursive,
         } => {
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut tf = Temporary::new();
 
-            tf.set_included_directory(directories.directories);
-            tf.set_excluded_directory(excluded_directories.excluded_directories);
-            tf.set_excluded_items(excluded_items.excluded_items);
-            tf.set_recursive_search(!not_recursive.not_recursive);
+            tf.set_scan_options(&scan_options);
 
             if delete_files {
                 tf.set_delete_method(temporary::DeleteMethod::Delete);
             }
 
-            tf.find_temporary_files(None, None);
+            tf.set_delete_action(get_file_action(&delete_action));
+
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            tf.find_temporary_files(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !tf.save_results_to_file(file_name) {
@@ -202,22 +337,49 @@ fn main() {
         Commands::SimilarImages {
             directories,
             excluded_directories,
+            reference_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
+            detect_by_content,
+            cache_location,
             file_to_save,
+            show_progress,
             minimal_file_size,
             similarity,
             not_recursive,
         } => {
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                reference_directories: reference_directories.reference_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                detect_by_content: detect_by_content.detect_by_content,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut sf = SimilarImages::new();
 
-            sf.set_included_directory(directories.directories);
-            sf.set_excluded_directory(excluded_directories.excluded_directories);
-            sf.set_excluded_items(excluded_items.excluded_items);
+            sf.set_scan_options(&scan_options);
             sf.set_minimal_file_size(minimal_file_size);
-            sf.set_recursive_search(!not_recursive.not_recursive);
             sf.set_similarity(similarity);
+            sf.set_cache_dir(cache_location.cache_dir.cache_dir);
+            sf.set_portable_cache(cache_location.portable_cache);
 
-            sf.find_similar_images(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            sf.find_similar_images(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !sf.save_results_to_file(file_name) {
@@ -234,26 +396,51 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             allowed_extensions,
             delete_files,
+            delete_action,
             file_to_save,
+            show_progress,
             not_recursive,
             minimal_file_size,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                allowed_extensions,
+                excluded_extensions,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut zf = ZeroedFiles::new();
 
-            zf.set_included_directory(directories.directories);
-            zf.set_excluded_directory(excluded_directories.excluded_directories);
-            zf.set_excluded_items(excluded_items.excluded_items);
-            zf.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
+            zf.set_scan_options(&scan_options);
             zf.set_minimal_file_size(minimal_file_size);
-            zf.set_recursive_search(!not_recursive.not_recursive);
 
             if delete_files {
                 zf.set_delete_method(zeroed::DeleteMethod::Delete);
             }
 
-            zf.find_zeroed_files(None, None);
+            zf.set_delete_action(get_file_action(&delete_action));
+
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            zf.find_zeroed_files(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !zf.save_results_to_file(file_name) {
@@ -269,27 +456,51 @@ fn main() {
         Commands::SameMusic {
             directories,
             excluded_directories,
+            reference_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
+            detect_by_content,
             // delete_files,
             file_to_save,
+            show_progress,
             not_recursive,
             minimal_file_size,
             music_similarity,
         } => {
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                reference_directories: reference_directories.reference_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                detect_by_content: detect_by_content.detect_by_content,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut mf = SameMusic::new();
 
-            mf.set_included_directory(directories.directories);
-            mf.set_excluded_directory(excluded_directories.excluded_directories);
-            mf.set_excluded_items(excluded_items.excluded_items);
+            mf.set_scan_options(&scan_options);
             mf.set_minimal_file_size(minimal_file_size);
-            mf.set_recursive_search(!not_recursive.not_recursive);
             mf.set_music_similarity(music_similarity);
 
             // if delete_files {
             //     // TODO mf.set_delete_method(same_music::DeleteMethod::Delete);
             // }
 
-            mf.find_same_music(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            mf.find_same_music(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !mf.save_results_to_file(file_name) {
@@ -306,23 +517,45 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
             allowed_extensions,
             file_to_save,
+            show_progress,
             not_recursive,
             delete_files,
+            delete_action,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                allowed_extensions,
+                excluded_extensions,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut ifs = InvalidSymlinks::new();
 
-            ifs.set_included_directory(directories.directories);
-            ifs.set_excluded_directory(excluded_directories.excluded_directories);
-            ifs.set_excluded_items(excluded_items.excluded_items);
-            ifs.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            ifs.set_recursive_search(!not_recursive.not_recursive);
+            ifs.set_scan_options(&scan_options);
             if delete_files {
                 ifs.set_delete_method(invalid_symlinks::DeleteMethod::Delete);
             }
+            ifs.set_delete_action(get_file_action(&delete_action));
 
-            ifs.find_invalid_links(None, None);
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            ifs.find_invalid_links(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !ifs.save_results_to_file(file_name) {
@@ -339,24 +572,54 @@ fn main() {
             directories,
             excluded_directories,
             excluded_items,
+            use_ignore_files,
+            traversal_limits,
+            file_filters,
+            follow_symlinks,
             allowed_extensions,
+            detect_by_content,
             delete_files,
+            delete_action,
+            cache_location,
             file_to_save,
+            show_progress,
             not_recursive,
         } => {
+            let (allowed_extensions, excluded_extensions) = expand_extension_macros(&allowed_extensions);
+            let scan_options = ScanOptions {
+                included_directories: directories.directories,
+                excluded_directories: excluded_directories.excluded_directories,
+                excluded_items: excluded_items.excluded_items,
+                exclude_from: excluded_items.exclude_from,
+                use_ignore_files: use_ignore_files.use_ignore_files,
+                max_depth: traversal_limits.max_depth,
+                one_file_system: traversal_limits.one_file_system,
+                skip_hidden: traversal_limits.skip_hidden,
+                maximal_file_size: file_filters.maximal_file_size,
+                modified_after: file_filters.modified_after,
+                modified_before: file_filters.modified_before,
+                follow_symlinks: follow_symlinks.follow_symlinks,
+                allowed_extensions,
+                excluded_extensions,
+                detect_by_content: detect_by_content.detect_by_content,
+                recursive_search: !not_recursive.not_recursive,
+                ..ScanOptions::new()
+            };
             let mut br = BrokenFiles::new();
 
-            br.set_included_directory(directories.directories);
-            br.set_excluded_directory(excluded_directories.excluded_directories);
-            br.set_excluded_items(excluded_items.excluded_items);
-            br.set_allowed_extensions(allowed_extensions.allowed_extensions.join(","));
-            br.set_recursive_search(!not_recursive.not_recursive);
+            br.set_scan_options(&scan_options);
+            br.set_cache_dir(cache_location.cache_dir.cache_dir);
+            br.set_portable_cache(cache_location.portable_cache);
 
             if delete_files {
                 br.set_delete_method(broken_files::DeleteMethod::Delete);
             }
 
-            br.find_broken_files(None, None);
+            br.set_delete_action(get_file_action(&delete_action));
+
+            let progress_printer = ProgressPrinter::start(show_progress.show_progress);
+            br.find_broken_files(None, progress_printer.sender());
+            progress_printer.finish();
 
             if let Some(file_name) = file_to_save.file_name() {
                 if !br.save_results_to_file(file_name) {
@@ -369,5 +632,102 @@ fn main() {
             br.print_results();
             br.get_text_messages().print_messages();
         }
+        Commands::ImportResults {
+            file_to_load,
+            reference_directories,
+            delete_action,
+            dryrun,
+        } => {
+            let mut ri = ResultsImport::new();
+
+            ri.set_reference_directory(reference_directories.reference_directories);
+
+            ri.set_delete_action(get_file_action(&delete_action));
+            ri.set_dryrun(dryrun.dryrun);
+
+            if !ri.load_results(&file_to_load.to_string_lossy()) {
+                ri.get_text_messages().print_messages();
+                process::exit(1);
+            }
+            ri.apply_actions();
+
+            ri.print_results();
+            ri.get_text_messages().print_messages();
+        }
+        Commands::Undo { journal, dryrun } => {
+            let mut uq = UndoQuarantine::new();
+
+            uq.set_dryrun(dryrun.dryrun);
+
+            if !uq.load_journal(&journal.to_string_lossy()) {
+                uq.get_text_messages().print_messages();
+                process::exit(1);
+            }
+            uq.restore_files();
+
+            uq.print_results();
+            uq.get_text_messages().print_messages();
+        }
+        Commands::Cache { action } => {
+            let mut text_messages = Messages::new();
+
+            match action {
+                CacheAction::Stats { cache_dir } => {
+                    let cache_dir = get_cache_dir_or_exit(cache_dir);
+                    println!("Cache folder - {}", cache_dir.display());
+                    let cache_files_info = get_cache_files_info(&cache_dir);
+                    if cache_files_info.is_empty() {
+                        println!("There are no cache files");
+                    }
+                    for cache_file_info in cache_files_info {
+                        println!("{}", cache_file_info);
+                    }
+                }
+                CacheAction::Clean { max_size, cache_dir } => {
+                    let clean_results = clean_cache(&get_cache_dir_or_exit(cache_dir), max_size, &mut text_messages);
+                    println!("Removed {} entries, {} entries left, freed {} bytes", clean_results.removed_entries, clean_results.remaining_entries, clean_results.freed_bytes);
+                }
+                CacheAction::Clear { cache_dir } => {
+                    println!("Removed {} cache files", clear_cache(&get_cache_dir_or_exit(cache_dir), &mut text_messages));
+                }
+                CacheAction::Export { file, base_path, cache_dir } => {
+                    let exported_entries = export_cache(&get_cache_dir_or_exit(cache_dir), &file, base_path.as_deref(), &mut text_messages);
+                    println!("Exported {} entries to {}", exported_entries, file.display());
+                }
+                CacheAction::Import { file, base_path, cache_dir } => {
+                    let imported_entries = import_cache(&get_cache_dir_or_exit(cache_dir), &file, base_path.as_deref(), &mut text_messages);
+                    println!("Imported {} entries from {}", imported_entries, file.display());
+                }
+            }
+
+            text_messages.print_messages();
+        }
+    }
+}
+
+/// Tools know only built-in macros like IMAGE, so macros defined by user in config folder are replaced here
+fn expand_extension_macros(allowed_extensions: &AllowedExtensions) -> (String, String) {
+    let mut messages = Messages::new();
+    let extension_macros = ExtensionMacros::load(&mut messages);
+    messages.print_messages();
+    (extension_macros.expand(&allowed_extensions.allowed_extensions.join(",")), extension_macros.expand(&allowed_extensions.excluded_extensions.join(",")))
+}
+
+/// Journal is created only when some file is moved, but its name is known before search
+fn get_file_action(delete_action: &DeleteAction) -> FileAction {
+    let file_action = delete_action.file_action();
+    if let FileAction::MoveToQuarantine(quarantine) = &file_action {
+        println!("Deleted files will be moved to quarantine, they can be restored with \"czkawka undo {}\"", quarantine.get_journal_file().display());
+    }
+    file_action
+}
+
+fn get_cache_dir_or_exit(cache_dir: CacheDir) -> PathBuf {
+    match cache_dir.cache_dir.or_else(get_cache_dir) {
+        Some(t) => t,
+        None => {
+            println!("Cannot find cache folder");
+            process::exit(1);
+        }
     }
 }
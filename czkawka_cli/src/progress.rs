@@ -0,0 +1,53 @@
+use czkawka_core::common_progress::ProgressData;
+use futures::channel::mpsc::UnboundedSender;
+use futures::StreamExt;
+use std::io::{self, Write};
+use std::thread::{self, JoinHandle};
+
+/// Prints progress of search to stderr, each update overwrites previous line so results printed to stdout stay readable
+pub struct ProgressPrinter {
+    progress_sender: Option<UnboundedSender<ProgressData>>,
+    printer_thread: Option<JoinHandle<()>>,
+}
+
+impl ProgressPrinter {
+    /// When progress is not shown, nothing is started and tool doesn't send anything
+    pub fn start(show_progress: bool) -> Self {
+        if !show_progress {
+            return Self { progress_sender: None, printer_thread: None };
+        }
+
+        let (progress_sender, mut progress_receiver) = futures::channel::mpsc::unbounded::<ProgressData>();
+        let printer_thread = thread::spawn(move || {
+            let mut last_line_length: usize = 0;
+            while let Some(progress) = futures::executor::block_on(progress_receiver.next()) {
+                let line = progress.to_string();
+                // Spaces clears rest of longer previous line
+                eprint!("\r{}{}", line, " ".repeat(last_line_length.saturating_sub(line.len())));
+                io::stderr().flush().unwrap();
+                last_line_length = line.len();
+            }
+            if last_line_length != 0 {
+                eprintln!();
+            }
+        });
+
+        Self {
+            progress_sender: Some(progress_sender),
+            printer_thread: Some(printer_thread),
+        }
+    }
+
+    pub fn sender(&self) -> Option<&UnboundedSender<ProgressData>> {
+        self.progress_sender.as_ref()
+    }
+
+    /// Must be called after search ends, so last progress line is finished before results are printed
+    pub fn finish(mut self) {
+        // Printer ends when all senders are dropped, tools drop their copies when search ends
+        self.progress_sender = None;
+        if let Some(printer_thread) = self.printer_thread.take() {
+            printer_thread.join().unwrap();
+        }
+    }
+}
@@ -1,30 +1,24 @@
 use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
-use crossbeam_channel::Receiver;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
+use crate::common_traits::{DebugPrint, PrintResults, SaveResults, Scanner};
 use humansize::{file_size_opts as options, FileSize};
+use serde::Serialize;
 use std::collections::BTreeMap;
-use std::ffi::OsStr;
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::PathBuf;
-use std::sync::atomic::Ordering;
-use std::sync::atomic::{AtomicBool, AtomicU64};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::Duration;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub files_checked: usize,
-}
+use std::time::SystemTime;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct FileEntry {
     pub path: PathBuf,
     pub size: u64,
@@ -55,11 +49,12 @@ pub struct BigFile {
     information: Info,
     big_files: BTreeMap<u64, Vec<FileEntry>>,
     excluded_items: ExcludedItems,
+    scan_options: ScanOptions,
     directories: Directories,
     allowed_extensions: Extensions,
-    recursive_search: bool,
     number_of_files_to_check: usize,
     delete_method: DeleteMethod,
+    delete_action: FileAction,
     stopped_search: bool,
 }
 
@@ -70,18 +65,20 @@ impl BigFile {
             information: Info::new(),
             big_files: Default::default(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             directories: Directories::new(),
             allowed_extensions: Extensions::new(),
-            recursive_search: true,
             number_of_files_to_check: 50,
             delete_method: DeleteMethod::None,
+            delete_action: FileAction::Delete,
             stopped_search: false,
         }
     }
 
-    pub fn find_big_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+    pub fn find_big_files(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
         self.optimize_directories();
-        if !self.look_for_big_files(stop_receiver, progress_sender) {
+        if !self.look_for_big_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -108,8 +105,13 @@ impl BigFile {
         self.delete_method = delete_method;
     }
 
+    /// Chooses what happens with found files when they are deleted, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     /// List of allowed extensions, only files with this extensions will be checking if are duplicates
@@ -117,134 +119,44 @@ impl BigFile {
         self.allowed_extensions.set_allowed_extensions(allowed_extensions, &mut self.text_messages);
     }
 
-    fn look_for_big_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn look_for_big_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicU64::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // Be sure that every thread is closed
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-
-            let current_folder = folders_to_check.pop().unwrap();
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    // Extracting file extension
-                    let file_extension = entry_data.path().extension().and_then(OsStr::to_str).map(str::to_lowercase);
-
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().map(|e| e.to_lowercase()).any(|e| file_extension == Some(e));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
-                            continue 'dir;
-                        }
-                    }
-
-                    // Checking expressions
-                    let current_file_name = current_folder.join(entry_data.file_name());
-                    if self.excluded_items.is_excluded(&current_file_name) {
-                        continue 'dir;
-                    }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning files",
+                current_stage: 0,
+                max_stage: 0,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_content_types(self.scan_options.content_types.clone());
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-                    // Creating new file entry
-                    let fe: FileEntry = FileEntry {
-                        path: current_file_name.clone(),
-                        size: metadata.len(),
-                        modified_date: match metadata.modified() {
-                            Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                Ok(d) => d.as_secs(),
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                    0
-                                }
-                            },
-                            Err(_) => {
-                                self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                continue;
-                            } // Permissions Denied
-                        },
-                    };
-
-                    self.big_files.entry(metadata.len()).or_insert_with(Vec::new);
-                    self.big_files.get_mut(&metadata.len()).unwrap().push(fe);
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    let size = fe.size;
+                    self.big_files.entry(size).or_insert_with(Vec::new);
+                    self.big_files.get_mut(&size).unwrap().push(FileEntry {
+                        path: fe.path,
+                        size: fe.size,
+                        modified_date: fe.modified_date,
+                    });
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
-
         // Extract n biggest files to new TreeMap
         let mut new_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
 
@@ -274,7 +186,7 @@ impl BigFile {
         self.number_of_files_to_check = number_of_files_to_check;
     }
 
-    /// Setting excluded items which needs to contains * wildcard
+    /// Setting excluded items which are glob patterns
     /// Are a lot of slower than absolute path, so it should be used to heavy
     pub fn set_excluded_items(&mut self, excluded_items: Vec<String>) {
         self.excluded_items.set_excluded_items(excluded_items, &mut self.text_messages);
@@ -282,7 +194,7 @@ impl BigFile {
 
     /// Remove unused entries when included or excluded overlaps with each other or are duplicated etc.
     fn optimize_directories(&mut self) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
     }
 
     /// Setting included directories, at least one must be provided
@@ -303,9 +215,7 @@ impl BigFile {
             DeleteMethod::Delete => {
                 for vec_file_entry in self.big_files.values() {
                     for file_entry in vec_file_entry {
-                        if fs::remove_file(file_entry.path.clone()).is_err() {
-                            self.text_messages.warnings.push(file_entry.path.display().to_string());
-                        }
+                        self.delete_action.apply_and_report(&file_entry.path, &self.directories, false, &mut self.text_messages);
                     }
                 }
             }
@@ -324,6 +234,42 @@ impl Default for BigFile {
     }
 }
 
+impl Scanner for BigFile {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_allowed_extensions(scan_options.allowed_extensions.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_big_files(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        // Biggest files first, same as in printed results
+        ScanResults::Entries(
+            self.big_files
+                .values()
+                .rev()
+                .flatten()
+                .map(|e| ResultEntry {
+                    path: e.path.clone(),
+                    size: Some(e.size),
+                    modified_date: e.modified_date,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for BigFile {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -346,7 +292,7 @@ impl DebugPrint for BigFile {
         println!("Excluded items - {:?}", self.excluded_items.items);
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Number of files to check - {:?}", self.number_of_files_to_check);
         println!("-----------------------------------------");
     }
@@ -361,23 +307,44 @@ impl SaveResults for BigFile {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["size", "modified_date", "path"],
+                    self.big_files.values().rev().flatten().map(|e| vec![e.size.to_string(), format_csv_date(e.modified_date), e.path.to_string_lossy().to_string()]),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.big_files.values().rev().flatten().collect::<Vec<_>>(), &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push("Failed to create file ".to_string() + file_name.as_str());
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
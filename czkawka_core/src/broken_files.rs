@@ -1,33 +1,30 @@
-use std::fs::{File, Metadata, OpenOptions};
+use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, mem, thread};
+use std::time::SystemTime;
+use std::{fs, mem};
 
 use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_cache::{self, get_cache_volume_roots, CacheEntry, CacheLocation, CacheUsage, MovedEntries};
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_extensions::Extensions;
+use crate::common_file_type::{detect_type, open_image};
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
 use crate::common_traits::*;
-use crossbeam_channel::Receiver;
-use directories_next::ProjectDirs;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::io::{BufReader, BufWriter};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-
-const CACHE_FILE_NAME: &str = "cache_broken_files.txt";
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub files_checked: usize,
-    pub files_to_check: usize,
-}
+
+pub(crate) const CACHE_FILE_NAME: &str = "cache_broken_files.bin";
+const LEGACY_CACHE_FILE_NAME: &str = "cache_broken_files.txt";
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum DeleteMethod {
@@ -35,7 +32,7 @@ pub enum DeleteMethod {
     Delete,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub path: PathBuf,
     pub modified_date: u64,
@@ -44,7 +41,22 @@ pub struct FileEntry {
     pub error_string: String,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeOfFile {
     Unknown = -1,
     Image = 0,
@@ -75,10 +87,12 @@ pub struct BrokenFiles {
     directories: Directories,
     allowed_extensions: Extensions,
     excluded_items: ExcludedItems,
-    recursive_search: bool,
+    scan_options: ScanOptions,
     delete_method: DeleteMethod,
+    delete_action: FileAction,
     stopped_search: bool,
     use_cache: bool,
+    cache_location: CacheLocation,
 }
 
 impl BrokenFiles {
@@ -86,25 +100,28 @@ impl BrokenFiles {
         Self {
             text_messages: Messages::new(),
             information: Info::new(),
-            recursive_search: true,
             allowed_extensions: Extensions::new(),
             directories: Directories::new(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             files_to_check: Default::default(),
             delete_method: DeleteMethod::None,
+            delete_action: FileAction::Delete,
             stopped_search: false,
             broken_files: Default::default(),
             use_cache: true,
+            cache_location: CacheLocation::new(),
         }
     }
 
-    pub fn find_broken_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
-        if !self.check_files(stop_receiver, progress_sender) {
+    pub fn find_broken_files(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
+        if !self.check_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
-        if !self.look_for_broken_files(stop_receiver, progress_sender) {
+        if !self.look_for_broken_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -132,12 +149,26 @@ impl BrokenFiles {
         self.delete_method = delete_method;
     }
 
+    /// Chooses what happens with found files when they are deleted, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     pub fn set_use_cache(&mut self, use_cache: bool) {
         self.use_cache = use_cache;
     }
 
+    /// `None` means default cache folder
+    pub fn set_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.cache_location.cache_dir = cache_dir;
+    }
+
+    pub fn set_portable_cache(&mut self, portable_cache: bool) {
+        self.cache_location.portable = portable_cache;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
@@ -155,170 +186,83 @@ impl BrokenFiles {
         self.excluded_items.set_excluded_items(excluded_items, &mut self.text_messages);
     }
 
-    fn check_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 1,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Collecting files",
+                current_stage: 0,
+                max_stage: 1,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        if self.scan_options.detect_by_content {
+            dir_traversal.set_file_filter(|fe| get_type_of_file_by_content(&fe.path) != TypeOfFile::Unknown);
         } else {
-            progress_thread_handle = thread::spawn(|| {});
+            dir_traversal.set_file_filter(|fe| check_extension_avaibility(&fe.path.to_string_lossy().to_lowercase()) != TypeOfFile::Unknown);
         }
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue;
-                    }
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    }
-                    .to_lowercase();
-
-                    let type_of_file = check_extension_avaibility(&file_name_lowercase);
-                    if type_of_file == TypeOfFile::Unknown {
-                        continue 'dir;
-                    }
-
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
-                            continue 'dir;
-                        }
-                    }
-
-                    // Checking files
-                    let current_file_name = current_folder.join(entry_data.file_name());
-                    if self.excluded_items.is_excluded(&current_file_name) {
-                        continue 'dir;
-                    }
-
-                    // Creating new file entry
-                    let fe: FileEntry = FileEntry {
-                        path: current_file_name.clone(),
-                        modified_date: match metadata.modified() {
-                            Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                Ok(d) => d.as_secs(),
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                    0
-                                }
-                            },
-                            Err(_) => {
-                                self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                continue;
-                            } // Permissions Denied
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    let fe = FileEntry {
+                        type_of_file: if self.scan_options.detect_by_content {
+                            get_type_of_file_by_content(&fe.path)
+                        } else {
+                            check_extension_avaibility(&fe.path.to_string_lossy().to_lowercase())
                         },
-                        size: metadata.len(),
-                        type_of_file,
+                        path: fe.path,
+                        modified_date: fe.modified_date,
+                        size: fe.size,
                         error_string: "".to_string(),
                     };
-
-                    // Adding files to Vector
                     self.files_to_check.insert(fe.path.to_string_lossy().to_string(), fe);
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
 
         Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
         true
     }
-    fn look_for_broken_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn look_for_broken_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let system_time = SystemTime::now();
 
-        let loaded_hash_map;
+        let mut loaded_hash_map;
 
         let mut records_already_cached: BTreeMap<String, FileEntry> = Default::default();
         let mut non_cached_files_to_check: BTreeMap<String, FileEntry> = Default::default();
 
+        let volume_roots = get_cache_volume_roots(&self.cache_location, &self.directories.included_directories);
         if self.use_cache {
-            loaded_hash_map = match load_cache_from_file(&mut self.text_messages) {
-                Some(t) => t,
-                None => Default::default(),
-            };
+            let (loaded_entries, moved_entries) = load_cache_from_file(&mut self.text_messages, &self.cache_location, &volume_roots).unwrap_or_default();
+            loaded_hash_map = loaded_entries;
 
             for (name, file_entry) in &self.files_to_check {
                 #[allow(clippy::if_same_then_else)]
                 if !loaded_hash_map.contains_key(name) {
-                    // If loaded data doesn't contains current image info
-                    non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                    // Renamed or moved file still may have valid entry, it is also saved with new path
+                    match moved_entries.find(&file_entry.path, file_entry.size, file_entry.modified_date) {
+                        Some(moved_entry) => {
+                            records_already_cached.insert(name.clone(), moved_entry.clone());
+                            loaded_hash_map.insert(name.clone(), moved_entry);
+                        }
+                        // If loaded data doesn't contains current image info
+                        None => {
+                            non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                        }
+                    }
                 } else if file_entry.size != loaded_hash_map.get(name).unwrap().size || file_entry.modified_date != loaded_hash_map.get(name).unwrap().modified_date {
                     // When size or modification date of image changed, then it is clear that is different image
                     non_cached_files_to_check.insert(name.clone(), file_entry.clone());
@@ -332,50 +276,36 @@ impl BrokenFiles {
             mem::swap(&mut self.files_to_check, &mut non_cached_files_to_check);
         }
 
-        let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = non_cached_files_to_check.len();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 1,
-                        max_stage: 1,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
+        let cache_usage = CacheUsage {
+            loaded_from_cache: records_already_cached.len() as u64,
+            checked_files: (records_already_cached.len() + non_cached_files_to_check.len()) as u64,
+        };
+
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Checking files",
+                current_stage: 1,
+                max_stage: 1,
+                items_to_check: non_cached_files_to_check.len(),
+                bytes_to_check: non_cached_files_to_check.values().map(|fe| fe.size).sum(),
+                ..Default::default()
+            },
+        );
         let mut vec_file_entry: Vec<FileEntry> = non_cached_files_to_check
             .par_iter()
             .map(|file_entry| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    check_was_breaked.store(true, Ordering::Relaxed);
+                if stop_token.is_stopped() {
                     return None;
                 }
                 let file_entry = file_entry.1;
+                progress_thread.counters().add_items(1);
+                progress_thread.counters().add_bytes(file_entry.size);
+                progress_thread.counters().set_current_path(&file_entry.path);
 
                 match file_entry.type_of_file {
                     TypeOfFile::Image => {
-                        match image::open(&file_entry.path) {
+                        match open_image(&file_entry.path) {
                             Ok(_) => Some(None),
                             Err(t) => {
                                 let error_string = t.to_string();
@@ -427,11 +357,10 @@ impl BrokenFiles {
             .collect::<Vec<FileEntry>>();
 
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
 
-        // Break if stop was clicked
-        if check_was_breaked.load(Ordering::Relaxed) {
+        // Workers end early after stop, so results are incomplete
+        if stop_token.is_stopped() {
             return false;
         }
 
@@ -452,7 +381,7 @@ impl BrokenFiles {
             for (_name, file_entry) in loaded_hash_map {
                 all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
             }
-            save_cache_to_file(&all_results, &mut self.text_messages);
+            save_cache_to_file(&all_results, &mut self.text_messages, &self.cache_location, &volume_roots, cache_usage);
         }
 
         self.information.number_of_broken_files = self.broken_files.len();
@@ -471,9 +400,7 @@ impl BrokenFiles {
         match self.delete_method {
             DeleteMethod::Delete => {
                 for file_entry in self.broken_files.iter() {
-                    if fs::remove_file(&file_entry.path).is_err() {
-                        self.text_messages.warnings.push(file_entry.path.display().to_string());
-                    }
+                    self.delete_action.apply_and_report(&file_entry.path, &self.directories, false, &mut self.text_messages);
                 }
             }
             DeleteMethod::None => {
@@ -490,6 +417,39 @@ impl Default for BrokenFiles {
     }
 }
 
+impl Scanner for BrokenFiles {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_allowed_extensions(scan_options.allowed_extensions.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_broken_files(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Entries(
+            self.broken_files
+                .iter()
+                .map(|e| ResultEntry {
+                    path: e.path.clone(),
+                    size: Some(e.size),
+                    modified_date: e.modified_date,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for BrokenFiles {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -514,8 +474,9 @@ impl DebugPrint for BrokenFiles {
         println!("Excluded items - {:?}", self.excluded_items.items);
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Delete Method - {:?}", self.delete_method);
+        println!("Delete Action - {:?}", self.delete_action);
         println!("-----------------------------------------");
     }
 }
@@ -527,23 +488,46 @@ impl SaveResults for BrokenFiles {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["size", "modified_date", "path", "error"],
+                    self.broken_files
+                        .iter()
+                        .map(|e| vec![e.size.to_string(), format_csv_date(e.modified_date), e.path.to_string_lossy().to_string(), e.error_string.clone()]),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.broken_files, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push(format!("Failed to create file {}", file_name));
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
@@ -573,108 +557,93 @@ impl PrintResults for BrokenFiles {
     }
 }
 
-fn save_cache_to_file(hashmap_file_entry: &BTreeMap<String, FileEntry>, text_messages: &mut Messages) {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        // Lin: /home/username/.cache/czkawka
-        // Win: C:\Users\Username\AppData\Local\Qarmin\Czkawka\cache
-        // Mac: /Users/Username/Library/Caches/pl.Qarmin.Czkawka
-
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        if cache_dir.exists() {
-            if !cache_dir.is_dir() {
-                text_messages.messages.push(format!("Config dir {} is a file!", cache_dir.display()));
-                return;
-            }
-        } else if fs::create_dir_all(&cache_dir).is_err() {
-            text_messages.messages.push(format!("Cannot create config dir {}", cache_dir.display()));
-            return;
-        }
-        let cache_file = cache_dir.join(CACHE_FILE_NAME);
-        let file_handler = match OpenOptions::new().truncate(true).write(true).create(true).open(&cache_file) {
-            Ok(t) => t,
-            Err(_) => {
-                text_messages.messages.push(format!("Cannot create or open cache file {}", cache_file.display()));
-                return;
-            }
-        };
-        let mut writer = BufWriter::new(file_handler);
+fn save_cache_to_file(hashmap_file_entry: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, cache_location: &CacheLocation, volume_roots: &[PathBuf], cache_usage: CacheUsage) {
+    // Only save to cache files which have more than 1KB
+    let entries: Vec<&FileEntry> = hashmap_file_entry.values().filter(|file_entry| file_entry.size > 1024).collect();
+    common_cache::save_cache_to_file(&entries, cache_location, volume_roots, CACHE_FILE_NAME, LEGACY_CACHE_FILE_NAME, cache_usage, text_messages);
+}
 
-        for file_entry in hashmap_file_entry.values() {
-            // Only save to cache files which have more than 1KB
-            if file_entry.size > 1024 {
-                let string: String = format!("{}//{}//{}//{}", file_entry.path.display(), file_entry.size, file_entry.modified_date, file_entry.error_string);
+fn load_cache_from_file(text_messages: &mut Messages, cache_location: &CacheLocation, volume_roots: &[PathBuf]) -> Option<(BTreeMap<String, FileEntry>, MovedEntries<FileEntry>)> {
+    let (loaded_entries, moved_entries) = common_cache::load_cache_from_file(cache_location, volume_roots, CACHE_FILE_NAME, LEGACY_CACHE_FILE_NAME, load_cache_from_legacy_file, text_messages)?;
 
-                if writeln!(writer, "{}", string).is_err() {
-                    text_messages.messages.push(format!("Failed to save some data to cache file {}", cache_file.display()));
-                    return;
-                };
-            }
-        }
-    }
+    Some((loaded_entries.into_iter().map(|file_entry| (file_entry.path.to_string_lossy().to_string(), file_entry)).collect(), moved_entries))
 }
 
-fn load_cache_from_file(text_messages: &mut Messages) -> Option<BTreeMap<String, FileEntry>> {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        let cache_file = cache_dir.join(CACHE_FILE_NAME);
-        let file_handler = match OpenOptions::new().read(true).open(&cache_file) {
+/// Loads cache saved in text format by older versions of Czkawka
+fn load_cache_from_legacy_file(cache_file: &Path, text_messages: &mut Messages) -> Option<Vec<FileEntry>> {
+    let file_handler = match OpenOptions::new().read(true).open(cache_file) {
+        Ok(t) => t,
+        Err(_) => return None,
+    };
+
+    let reader = BufReader::new(file_handler);
+
+    let mut loaded_entries: Vec<FileEntry> = Vec::new();
+
+    // Read the file line by line using the lines() iterator from std::io::BufRead.
+    for (index, line) in reader.lines().enumerate() {
+        let line = match line {
             Ok(t) => t,
-            Err(_) => {
-                // text_messages.messages.push(format!("Cannot find or open cache file {}", cache_file.display())); // This shouldn't be write to output
+            Err(e) => {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::ReadCache,
+                    path: cache_file.to_path_buf(),
+                    kind: e.kind(),
+                });
                 return None;
             }
         };
-
-        let reader = BufReader::new(file_handler);
-
-        let mut hashmap_loaded_entries: BTreeMap<String, FileEntry> = Default::default();
-
-        // Read the file line by line using the lines() iterator from std::io::BufRead.
-        for (index, line) in reader.lines().enumerate() {
-            let line = match line {
+        let uuu = line.split("//").collect::<Vec<&str>>();
+        if uuu.len() != 4 {
+            text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                path: cache_file.to_path_buf(),
+                line_number: index + 1,
+                line: line.clone(),
+                value: "data",
+            });
+            continue;
+        }
+        loaded_entries.push(FileEntry {
+            path: PathBuf::from(uuu[0]),
+            size: match uuu[1].parse::<u64>() {
                 Ok(t) => t,
                 Err(_) => {
-                    text_messages.warnings.push(format!("Failed to load line number {} from cache file {}", index + 1, cache_file.display()));
-                    return None;
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "size value",
+                    });
+                    continue;
                 }
-            };
-            let uuu = line.split("//").collect::<Vec<&str>>();
-            if uuu.len() != 4 {
-                text_messages.warnings.push(format!("Found invalid data in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                continue;
-            }
-            // Don't load cache data if destination file not exists
-            if Path::new(uuu[0]).exists() {
-                hashmap_loaded_entries.insert(
-                    uuu[0].to_string(),
-                    FileEntry {
-                        path: PathBuf::from(uuu[0]),
-                        size: match uuu[1].parse::<u64>() {
-                            Ok(t) => t,
-                            Err(_) => {
-                                text_messages.warnings.push(format!("Found invalid size value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                                continue;
-                            }
-                        },
-                        modified_date: match uuu[2].parse::<u64>() {
-                            Ok(t) => t,
-                            Err(_) => {
-                                text_messages.warnings.push(format!("Found invalid modified date value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                                continue;
-                            }
-                        },
-                        type_of_file: check_extension_avaibility(&uuu[0].to_lowercase()),
-                        error_string: uuu[3].to_string(),
-                    },
-                );
-            }
-        }
-
-        return Some(hashmap_loaded_entries);
+            },
+            modified_date: match uuu[2].parse::<u64>() {
+                Ok(t) => t,
+                Err(_) => {
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "modified date value",
+                    });
+                    continue;
+                }
+            },
+            type_of_file: check_extension_avaibility(&uuu[0].to_lowercase()),
+            error_string: uuu[3].to_string(),
+        });
     }
 
-    text_messages.messages.push("Cannot find or open system config dir to save cache file".to_string());
-    None
+    Some(loaded_entries)
+}
+
+/// Detected type has the most popular extension of such files, so the same list of supported extensions is used
+/// Type of files which are not recognized by content(e.g. some audio formats) is taken from extension
+fn get_type_of_file_by_content(path: &Path) -> TypeOfFile {
+    match detect_type(path) {
+        Some(detected_type) => check_extension_avaibility(&format!(".{}", detected_type.extension)),
+        None => check_extension_avaibility(&path.to_string_lossy().to_lowercase()),
+    }
 }
 
 fn check_extension_avaibility(file_name_lowercase: &str) -> TypeOfFile {
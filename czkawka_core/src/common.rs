@@ -1,7 +1,8 @@
 use std::ffi::OsString;
-use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{NaiveDate, NaiveDateTime};
 
 /// Class for common functions used across other class/functions
 
@@ -14,42 +15,6 @@ impl Common {
         println!("Execution of function \"{}\" took {:?}", function_name, end_time.duration_since(start_time).expect("Time cannot go reverse."));
     }
 
-    pub fn delete_multiple_entries(entries: &[String]) -> Vec<String> {
-        let mut path: &Path;
-        let mut warnings: Vec<String> = Vec::new();
-        for entry in entries {
-            path = Path::new(entry);
-            if path.is_dir() {
-                match fs::remove_dir_all(&entry) {
-                    Ok(_) => (),
-                    Err(_) => warnings.push("Failed to remove folder ".to_owned() + entry.as_str()),
-                }
-            } else {
-                match fs::remove_file(&entry) {
-                    Ok(_) => (),
-                    Err(_) => warnings.push("Failed to remove file ".to_owned() + entry.as_str()),
-                }
-            }
-        }
-        warnings
-    }
-    pub fn delete_one_entry(entry: &str) -> String {
-        let path: &Path = Path::new(entry);
-        let mut warning: String = String::from("");
-        if path.is_dir() {
-            match fs::remove_dir_all(&entry) {
-                Ok(_) => (),
-                Err(_) => warning = "Failed to remove folder ".to_owned() + entry,
-            }
-        } else {
-            match fs::remove_file(&entry) {
-                Ok(_) => (),
-                Err(_) => warning = "Failed to remove file ".to_owned() + entry,
-            }
-        }
-        warning
-    }
-
     /// Function to check if directory match expression
     pub fn regex_check(expression: &str, directory: impl AsRef<Path>) -> bool {
         // if !expression.contains('*') {
@@ -130,12 +95,43 @@ impl Common {
             _ => path.to_path_buf(),
         }
     }
+
+    /// Converts date given by user to seconds since Unix Epoch, so it can be compared with modification date of files.
+    /// Accepts UTC date like "2021-03-01" or "2021-03-01 12:30:00" and time before now like "12h", "30d", "2w" or "1y"
+    pub fn parse_date(src: &str) -> Result<u64, String> {
+        let src = src.trim();
+        if let Some(unit) = src.chars().last().filter(|e| e.is_ascii_alphabetic()) {
+            let seconds_in_unit: u64 = match unit.to_ascii_lowercase() {
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                'w' => 7 * 24 * 60 * 60,
+                'y' => 365 * 24 * 60 * 60,
+                _ => return Err(format!("Unknown time unit \"{}\" in \"{}\" (allowed: h, d, w, y)", unit, src)),
+            };
+            let number = src[..src.len() - 1].parse::<u64>().map_err(|e| format!("Invalid time \"{}\": {}", src, e))?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            return Ok(now.saturating_sub(number.saturating_mul(seconds_in_unit)));
+        }
+
+        let date_time = match NaiveDateTime::parse_from_str(src, "%Y-%m-%d %H:%M:%S") {
+            Ok(t) => t,
+            Err(_) => match NaiveDate::parse_from_str(src, "%Y-%m-%d") {
+                Ok(t) => t.and_hms(0, 0, 0),
+                Err(_) => return Err(format!("Invalid date \"{}\" (allowed formats: YYYY-MM-DD, \"YYYY-MM-DD HH:MM:SS\" or time before now like 30d)", src)),
+            },
+        };
+        if date_time.timestamp() < 0 {
+            return Err(format!("Date \"{}\" is before 1970", src));
+        }
+        Ok(date_time.timestamp() as u64)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::common::Common;
     use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     #[test]
     fn test_regex() {
@@ -164,6 +160,19 @@ mod test {
             assert!(Common::regex_check("*/home", "C:\\home"));
         }
     }
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(Common::parse_date("2021-03-01"), Ok(1_614_556_800));
+        assert_eq!(Common::parse_date("1970-01-01 00:01:00"), Ok(60));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let day_ago = Common::parse_date("1d").unwrap();
+        assert!(day_ago <= now - 24 * 60 * 60 && day_ago + 60 > now - 24 * 60 * 60);
+        assert!(Common::parse_date("1969-12-31").is_err());
+        assert!(Common::parse_date("5x").is_err());
+        assert!(Common::parse_date("yesterday").is_err());
+        assert!(Common::parse_date("2021-13-01").is_err());
+    }
+
     #[test]
     fn test_windows_path() {
         assert_eq!(PathBuf::from("C:\\path.txt"), Common::normalize_windows_path("c:/PATH.tXt"));
@@ -0,0 +1,481 @@
+use crate::common_directory::Directories;
+use crate::common_extents::dedupe_file;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::quarantine::Quarantine;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+/// Size of parts of files compared before replacing file with symlink
+const COMPARE_CHUNK_SIZE: u64 = 128 * 1024;
+
+/// What is done with file chosen to remove by tool or by user, it is shared by CLI and GUI
+/// Folders are deleted, moved to trash or moved only when they don't contain any file, so data added after search is never lost
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    /// Removes file permanently
+    Delete,
+    /// Moves file to trash of system, so it can be restored later
+    MoveToTrash,
+    /// Moves file into given folder, already existing files are never overwritten
+    MoveToDirectory(PathBuf),
+    /// Moves file into quarantine folder and saves it in journal, so it can be restored by undo
+    MoveToQuarantine(Quarantine),
+    /// Replaces file with hard link to given file
+    HardLink(PathBuf),
+    /// Replaces file with symlink to given identical file, relative link works also after moving folder which contains both files
+    SymLink { original: PathBuf, relative: bool },
+    /// Shares data of file with given identical file, works only on Linux file systems with copy-on-write support
+    Dedupe(PathBuf),
+}
+
+/// Result of action done with single file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActionOutcome {
+    /// File was changed, moved files contains also their new path
+    Done { new_path: Option<PathBuf> },
+    /// Nothing was changed, because only dry run was requested
+    DryRun,
+    /// File was left unchanged, because operation failed
+    Failed(ErrorKind),
+    /// File was left unchanged, because it is inside reference directory
+    InReferenceDirectory,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActionResult {
+    pub path: PathBuf,
+    pub outcome: ActionOutcome,
+}
+
+impl ActionResult {
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, ActionOutcome::Failed(_) | ActionOutcome::InReferenceDirectory)
+    }
+}
+
+impl FileAction {
+    /// Text which describes planned change, it is shown in dry run
+    pub fn describe(&self, path: &Path) -> String {
+        match self {
+            FileAction::Delete => format!("Delete {}", path.display()),
+            FileAction::MoveToTrash => format!("Move {} to trash", path.display()),
+            FileAction::MoveToDirectory(directory) => format!("Move {} to {}", path.display(), directory.display()),
+            FileAction::MoveToQuarantine(quarantine) => format!("Move {} to quarantine {}", path.display(), quarantine.get_directory().display()),
+            FileAction::HardLink(original) => format!("Replace file {} with hard link to {}", path.display(), original.display()),
+            FileAction::SymLink { original, relative: false } => format!("Replace file {} with symlink to {}", path.display(), original.display()),
+            FileAction::SymLink { original, relative: true } => format!("Replace file {} with relative symlink to {}", path.display(), original.display()),
+            FileAction::Dedupe(original) => format!("Share data of file {} with {}", path.display(), original.display()),
+        }
+    }
+
+    /// Operation which is reported in warning when action fails
+    pub fn operation(&self, path: &Path) -> Operation {
+        match self {
+            FileAction::Delete if is_folder(path) => Operation::RemoveFolder,
+            FileAction::Delete => Operation::RemoveFile,
+            FileAction::MoveToTrash => Operation::MoveToTrash,
+            FileAction::MoveToDirectory(_) | FileAction::MoveToQuarantine(_) => Operation::MoveFile,
+            FileAction::HardLink(_) => Operation::CreateHardLink,
+            FileAction::SymLink { .. } => Operation::CreateSymlink,
+            FileAction::Dedupe(_) => Operation::Dedupe,
+        }
+    }
+
+    /// Files inside reference directories are never changed, also when they were chosen by user or loaded from edited results
+    pub fn apply(&self, path: &Path, directories: &Directories, dryrun: bool) -> ActionResult {
+        let outcome = if directories.is_protected(path) {
+            ActionOutcome::InReferenceDirectory
+        } else if dryrun {
+            ActionOutcome::DryRun
+        } else {
+            let result = match self {
+                FileAction::Delete => check_folder_without_files(path).and_then(|_| delete(path)).map(|_| None),
+                FileAction::MoveToTrash => check_folder_without_files(path).and_then(|_| move_to_trash(path)).map(|_| None),
+                FileAction::MoveToDirectory(directory) => check_folder_without_files(path).and_then(|_| move_to_directory(path, directory)).map(Some),
+                FileAction::MoveToQuarantine(quarantine) => check_folder_without_files(path).and_then(|_| quarantine.move_to_quarantine(path)).map(Some),
+                FileAction::HardLink(original) => check_identical_files(original, path).and_then(|_| make_hard_link(original, path)).map(|_| None),
+                FileAction::SymLink { original, relative } => check_identical_files(original, path)
+                    .and_then(|_| if *relative { get_relative_target(original, path) } else { Ok(original.clone()) })
+                    .and_then(|target| make_symlink(&target, path))
+                    .map(|_| None),
+                FileAction::Dedupe(original) => dedupe_file(original, path).map(|_| None),
+            };
+            match result {
+                Ok(new_path) => ActionOutcome::Done { new_path },
+                Err(e) => ActionOutcome::Failed(e.kind()),
+            }
+        };
+        ActionResult { path: path.to_path_buf(), outcome }
+    }
+
+    /// Applies action and adds warning when it failed or message with planned change in dry run
+    pub fn apply_and_report(&self, path: &Path, directories: &Directories, dryrun: bool, text_messages: &mut Messages) -> ActionResult {
+        // Must be checked before applying, because removed folder cannot be recognized
+        let operation = self.operation(path);
+        let result = self.apply(path, directories, dryrun);
+        match result.outcome {
+            ActionOutcome::Done { .. } => {}
+            ActionOutcome::DryRun => text_messages.messages.push(self.describe(path)),
+            ActionOutcome::Failed(kind) => text_messages.add_warning(MessageEntry::Io { operation, path: path.to_path_buf(), kind }),
+            ActionOutcome::InReferenceDirectory => text_messages.add_warning(MessageEntry::InReferenceDirectory { path: path.to_path_buf() }),
+        }
+        result
+    }
+}
+
+fn is_folder(path: &Path) -> bool {
+    fs::symlink_metadata(path).map(|metadata| metadata.is_dir()).unwrap_or(false)
+}
+
+fn delete(path: &Path) -> io::Result<()> {
+    if is_folder(path) {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Checks that folder and all its subfolders doesn't contain any file, other paths are always accepted
+fn check_folder_without_files(path: &Path) -> io::Result<()> {
+    if !is_folder(path) {
+        return Ok(());
+    }
+    let mut folders_to_check: Vec<PathBuf> = vec![path.to_path_buf()];
+    while let Some(current_folder) = folders_to_check.pop() {
+        for entry in fs::read_dir(&current_folder)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                folders_to_check.push(entry.path());
+            } else {
+                return Err(Error::new(ErrorKind::DirectoryNotEmpty, "Folder contains files"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn move_to_trash(path: &Path) -> io::Result<()> {
+    trash::delete(path).map_err(|e| {
+        let kind = match e {
+            trash::Error::CouldNotAccess { .. } => ErrorKind::NotFound,
+            trash::Error::CanonicalizePath { code: Some(code) } => Error::from_raw_os_error(code).kind(),
+            trash::Error::TargetedRoot => ErrorKind::InvalidInput,
+            _ => ErrorKind::Other,
+        };
+        Error::new(kind, format!("{:?}", e))
+    })
+}
+
+/// Returns new path of file, when file with same name already exists in folder, then number is added to name e.g. `photo (1).jpg`
+fn move_to_directory(path: &Path, directory: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Path doesn't contain file name"))?;
+    fs::create_dir_all(directory)?;
+
+    let new_path = get_free_path(directory.join(file_name));
+    move_path(path, &new_path)?;
+    Ok(new_path)
+}
+
+/// Adds number to name of file until it is not used by any other file
+pub(crate) fn get_free_path(path: PathBuf) -> PathBuf {
+    let (directory, file_name) = match (path.parent(), path.file_name()) {
+        (Some(directory), Some(file_name)) => (directory, file_name),
+        _ => return path,
+    };
+    let mut new_path = path.clone();
+    let mut number = 1;
+    while fs::symlink_metadata(&new_path).is_ok() {
+        let stem = Path::new(file_name).file_stem().unwrap_or(file_name).to_string_lossy();
+        new_path = match Path::new(file_name).extension() {
+            Some(extension) => directory.join(format!("{} ({}).{}", stem, number, extension.to_string_lossy())),
+            None => directory.join(format!("{} ({})", stem, number)),
+        };
+        number += 1;
+    }
+    new_path
+}
+
+/// Moves file or folder to path which must not exist
+pub(crate) fn move_path(path: &Path, new_path: &Path) -> io::Result<()> {
+    if let Err(e) = fs::rename(path, new_path) {
+        // Rename doesn't work between different file systems, so file must be copied
+        if !fs::symlink_metadata(path)?.is_file() {
+            return Err(e);
+        }
+        if let Err(e) = fs::copy(path, new_path) {
+            let _ = fs::remove_file(new_path);
+            return Err(e);
+        }
+        if let Err(e) = fs::remove_file(path) {
+            let _ = fs::remove_file(new_path);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Original file may be changed after search, so content of both files is compared again just before replacing it with link
+fn check_identical_files(original: &Path, path: &Path) -> io::Result<()> {
+    if fs::canonicalize(original)? == fs::canonicalize(path)? {
+        return Err(Error::new(ErrorKind::InvalidInput, "File cannot be linked to itself"));
+    }
+    let (original_metadata, metadata) = (fs::metadata(original)?, fs::symlink_metadata(path)?);
+    if !original_metadata.is_file() || !metadata.is_file() || original_metadata.len() != metadata.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "Files are different"));
+    }
+
+    let (mut original_file, mut file) = (File::open(original)?, File::open(path)?);
+    let (mut original_buffer, mut buffer) = (Vec::with_capacity(COMPARE_CHUNK_SIZE as usize), Vec::with_capacity(COMPARE_CHUNK_SIZE as usize));
+    loop {
+        original_buffer.clear();
+        buffer.clear();
+        (&mut original_file).take(COMPARE_CHUNK_SIZE).read_to_end(&mut original_buffer)?;
+        (&mut file).take(COMPARE_CHUNK_SIZE).read_to_end(&mut buffer)?;
+        if original_buffer != buffer {
+            return Err(Error::new(ErrorKind::InvalidData, "Files are different"));
+        }
+        if buffer.is_empty() {
+            return Ok(());
+        }
+    }
+}
+
+/// Path of original file relative to folder of link, both are canonicalized, so symlinks inside them don't break link
+fn get_relative_target(original: &Path, link: &Path) -> io::Result<PathBuf> {
+    let original = fs::canonicalize(original)?;
+    let link_folder = fs::canonicalize(link.parent().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Path doesn't contain parent folder"))?)?;
+    let common_components = original.components().zip(link_folder.components()).take_while(|(a, b)| a == b).count();
+    // e.g. files on different drives on Windows
+    if common_components == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "Files don't have common parent folder"));
+    }
+
+    let mut target = PathBuf::new();
+    for _ in link_folder.components().skip(common_components) {
+        target.push("..");
+    }
+    for component in original.components().skip(common_components) {
+        target.push(component);
+    }
+    Ok(target)
+}
+
+/// Original file is moved to temporary file and restored when new link couldn't be created
+fn replace_with_link(dst: &Path, create_link: impl FnOnce() -> io::Result<()>) -> io::Result<()> {
+    let dst_dir = dst.parent().ok_or_else(|| Error::other("No parent"))?;
+    let temp = tempfile::Builder::new().tempfile_in(dst_dir)?;
+    fs::rename(dst, temp.path())?;
+    let result = create_link();
+    if result.is_err() {
+        fs::rename(temp.path(), dst)?;
+    }
+    result
+}
+
+pub fn make_hard_link(src: &Path, dst: &Path) -> io::Result<()> {
+    replace_with_link(dst, || fs::hard_link(src, dst))
+}
+
+pub fn make_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    #[cfg(target_family = "unix")]
+    {
+        replace_with_link(dst, || std::os::unix::fs::symlink(src, dst))
+    }
+    #[cfg(target_family = "windows")]
+    {
+        replace_with_link(dst, || std::os::windows::fs::symlink_file(src, dst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_dir, File, Metadata};
+    #[cfg(target_family = "windows")]
+    use std::os::fs::MetadataExt;
+    #[cfg(target_family = "unix")]
+    use std::os::unix::fs::MetadataExt;
+
+    #[cfg(target_family = "unix")]
+    fn assert_inode(before: &Metadata, after: &Metadata) {
+        assert_eq!(before.ino(), after.ino());
+    }
+    #[cfg(target_family = "windows")]
+    fn assert_inode(_: &Metadata, _: &Metadata) {}
+
+    #[test]
+    fn test_make_hard_link() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst) = (dir.path().join("a"), dir.path().join("b"));
+        File::create(&src)?;
+        let metadata = fs::metadata(&src)?;
+        File::create(&dst)?;
+
+        make_hard_link(&src, &dst)?;
+
+        assert_inode(&metadata, &fs::metadata(&dst)?);
+        assert_eq!(metadata.permissions(), fs::metadata(&dst)?.permissions());
+        assert_eq!(metadata.modified()?, fs::metadata(&dst)?.modified()?);
+        assert_inode(&metadata, &fs::metadata(&src)?);
+        assert_eq!(metadata.permissions(), fs::metadata(&src)?.permissions());
+        assert_eq!(metadata.modified()?, fs::metadata(&src)?.modified()?);
+
+        let mut actual = read_dir(&dir)?.map(|e| e.unwrap().path()).collect::<Vec<PathBuf>>();
+        actual.sort();
+        assert_eq!(vec![src, dst], actual);
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_hard_link_fails() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst) = (dir.path().join("a"), dir.path().join("b"));
+        File::create(&dst)?;
+        let metadata = fs::metadata(&dst)?;
+
+        assert!(make_hard_link(&src, &dst).is_err());
+
+        assert_inode(&metadata, &fs::metadata(&dst)?);
+        assert_eq!(metadata.permissions(), fs::metadata(&dst)?.permissions());
+        assert_eq!(metadata.modified()?, fs::metadata(&dst)?.modified()?);
+
+        assert_eq!(vec![dst], read_dir(&dir)?.map(|e| e.unwrap().path()).collect::<Vec<PathBuf>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_actions() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (original, copy) = (dir.path().join("original.txt"), dir.path().join("copy.txt"));
+        fs::write(&original, "data")?;
+        fs::write(&copy, "data")?;
+
+        let mut text_messages = Messages::new();
+        let result = FileAction::Delete.apply_and_report(&copy, &Directories::new(), true, &mut text_messages);
+        assert_eq!(result.outcome, ActionOutcome::DryRun);
+        assert_eq!(text_messages.messages, vec![format!("Delete {}", copy.display())]);
+        assert!(copy.exists());
+
+        #[cfg(target_family = "unix")]
+        {
+            let result = FileAction::SymLink { original: original.clone(), relative: false }.apply(&copy, &Directories::new(), false);
+            assert_eq!(result.outcome, ActionOutcome::Done { new_path: None });
+            assert_eq!(fs::read_link(&copy)?, original);
+        }
+
+        // Files with same name are not overwritten
+        let quarantine = dir.path().join("quarantine");
+        fs::create_dir(&quarantine)?;
+        fs::write(quarantine.join("original.txt"), "other data")?;
+        let result = FileAction::MoveToDirectory(quarantine.clone()).apply(&original, &Directories::new(), false);
+        assert_eq!(
+            result.outcome,
+            ActionOutcome::Done {
+                new_path: Some(quarantine.join("original (1).txt"))
+            }
+        );
+        assert_eq!(fs::read_to_string(quarantine.join("original (1).txt"))?, "data");
+        assert!(!original.exists());
+
+        let result = FileAction::Delete.apply_and_report(&original, &Directories::new(), false, &mut text_messages);
+        assert_eq!(result.outcome, ActionOutcome::Failed(ErrorKind::NotFound));
+        assert!(result.is_failed());
+        assert_eq!(text_messages.typed_warnings[0].operation(), Some(Operation::RemoveFile));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hard_link_changed_file() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (original, copy) = (dir.path().join("original"), dir.path().join("copy"));
+        fs::write(&original, "data")?;
+        fs::write(&copy, "data")?;
+        let metadata = fs::metadata(&copy)?;
+
+        // File changed after search keeps its own data
+        fs::write(&copy, "date")?;
+        let result = FileAction::HardLink(original.clone()).apply(&copy, &Directories::new(), false);
+        assert_eq!(result.outcome, ActionOutcome::Failed(ErrorKind::InvalidData));
+        assert_eq!(fs::read_to_string(&copy)?, "date");
+        assert_inode(&metadata, &fs::metadata(&copy)?);
+
+        fs::write(&copy, "data")?;
+        assert!(!FileAction::HardLink(original.clone()).apply(&copy, &Directories::new(), false).is_failed());
+        assert_inode(&fs::metadata(&original)?, &fs::metadata(&copy)?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_relative_symlink() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("a/b"))?;
+        fs::create_dir_all(dir.path().join("c"))?;
+        let (original, copy) = (dir.path().join("a/b/original"), dir.path().join("c/copy"));
+        fs::write(&original, "data")?;
+        fs::write(&copy, "data")?;
+
+        let action = FileAction::SymLink { original: original.clone(), relative: true };
+        assert!(action.apply(&original, &Directories::new(), false).is_failed());
+        assert!(!action.apply(&copy, &Directories::new(), false).is_failed());
+        assert_eq!(fs::read_link(&copy)?, PathBuf::from("../a/b/original"));
+
+        // Links keep working after moving folder with both files
+        let moved = dir.path().join("moved");
+        fs::create_dir(&moved)?;
+        fs::rename(dir.path().join("a"), moved.join("a"))?;
+        fs::rename(dir.path().join("c"), moved.join("c"))?;
+        assert_eq!(fs::read_to_string(moved.join("c/copy"))?, "data");
+        assert_eq!(fs::read_to_string(moved.join("a/b/original"))?, "data");
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference_directory_is_not_changed() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("reference"))?;
+        fs::create_dir_all(dir.path().join("included"))?;
+        let reference_file = dir.path().join("reference/file");
+        fs::write(&reference_file, "data")?;
+
+        let mut directories = Directories::new();
+        directories.set_reference_directory(vec![dir.path().join("reference")], &mut Messages::new());
+        let mut text_messages = Messages::new();
+        let result = FileAction::Delete.apply_and_report(&reference_file, &directories, true, &mut text_messages);
+        assert_eq!(result.outcome, ActionOutcome::InReferenceDirectory);
+        assert_eq!(text_messages.typed_warnings, vec![MessageEntry::InReferenceDirectory { path: reference_file.clone() }]);
+
+        // File reached by symlinked folder is also protected
+        #[cfg(target_family = "unix")]
+        {
+            std::os::unix::fs::symlink(dir.path().join("reference"), dir.path().join("included/link"))?;
+            assert!(FileAction::Delete.apply(&dir.path().join("included/link/file"), &directories, false).is_failed());
+        }
+        assert!(reference_file.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_only_empty_folders() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (empty, not_empty) = (dir.path().join("empty"), dir.path().join("not_empty"));
+        fs::create_dir_all(empty.join("a/b"))?;
+        fs::create_dir_all(not_empty.join("a"))?;
+        File::create(not_empty.join("a/file"))?;
+
+        let mut text_messages = Messages::new();
+        assert!(!FileAction::Delete.apply_and_report(&empty, &Directories::new(), false, &mut text_messages).is_failed());
+        assert!(!empty.exists());
+
+        let result = FileAction::Delete.apply_and_report(&not_empty, &Directories::new(), false, &mut text_messages);
+        assert_eq!(result.outcome, ActionOutcome::Failed(ErrorKind::DirectoryNotEmpty));
+        assert_eq!(text_messages.typed_warnings[0].operation(), Some(Operation::RemoveFolder));
+        let result = FileAction::MoveToDirectory(dir.path().join("quarantine")).apply(&not_empty, &Directories::new(), false);
+        assert_eq!(result.outcome, ActionOutcome::Failed(ErrorKind::DirectoryNotEmpty));
+        assert!(not_empty.join("a/file").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,1018 @@
+use crate::broken_files;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::duplicate;
+use crate::similar_images;
+use bincode::Options;
+use directories_next::ProjectDirs;
+use humansize::{file_size_opts as options, FileSize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const CACHE_MAGIC: &[u8; 8] = b"CZKCACHE";
+/// Must be increased every time when layout of cached entries changes
+const CACHE_FORMAT_VERSION: u32 = 4;
+/// Header contains only few small fields, so anything bigger means that file is damaged
+const CACHE_HEADER_LIMIT: u64 = 1024;
+/// Folder created in root of scanned volume when portable cache is used
+pub const PORTABLE_CACHE_DIR_NAME: &str = ".czkawka_cache";
+
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    format_version: u32,
+    czkawka_version: String,
+    number_of_entries: u64,
+    /// crc32 of serialized entries
+    checksum: u32,
+    last_scan_usage: CacheUsage,
+}
+
+/// Entry saved in cache, it is valid only as long as file with same path and modification date exists
+pub(crate) trait CacheEntry: Clone + Serialize + DeserializeOwned {
+    fn get_path(&self) -> &Path;
+    fn set_path(&mut self, path: PathBuf);
+    fn get_size(&self) -> u64;
+    fn get_modified_date(&self) -> u64;
+}
+
+/// Identity of file which doesn't change when file is renamed or moved inside same volume
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub(crate) struct FileId {
+    device: u64,
+    inode: u64,
+}
+
+#[cfg(target_family = "unix")]
+fn get_file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some(FileId { device: metadata.dev(), inode: metadata.ino() })
+}
+
+// Std doesn't expose stable file index on other systems, so there entries are found only by path
+#[cfg(not(target_family = "unix"))]
+fn get_file_id(_path: &Path) -> Option<FileId> {
+    None
+}
+
+/// Entry saved in cache file, with identity which file had when cache was saved
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedEntry<T> {
+    file_id: Option<FileId>,
+    entry: T,
+}
+
+impl<T: CacheEntry> CachedEntry<T> {
+    fn new(entry: T) -> Self {
+        Self { file_id: get_file_id(entry.get_path()), entry }
+    }
+}
+
+impl<T: CacheEntry> CacheEntry for CachedEntry<T> {
+    fn get_path(&self) -> &Path {
+        self.entry.get_path()
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.entry.set_path(path);
+    }
+    fn get_size(&self) -> u64 {
+        self.entry.get_size()
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.entry.get_modified_date()
+    }
+}
+
+/// Cached entries of files which no longer exist under saved path, but may be renamed or moved
+pub(crate) struct MovedEntries<T> {
+    entries: BTreeMap<(FileId, u64, u64), T>,
+}
+
+impl<T> Default for MovedEntries<T> {
+    fn default() -> Self {
+        Self { entries: Default::default() }
+    }
+}
+
+impl<T: CacheEntry> MovedEntries<T> {
+    /// Entry is valid only when file has same device, inode, size and modification date, it is returned with current path
+    pub(crate) fn find(&self, path: &Path, size: u64, modified_date: u64) -> Option<T> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let file_id = get_file_id(path)?;
+        let mut entry = self.entries.get(&(file_id, size, modified_date))?.clone();
+        entry.set_path(path.to_path_buf());
+        Some(entry)
+    }
+}
+
+/// Type of entries saved in cache file, which is known only from its name
+enum CacheKind {
+    Duplicates,
+    SimilarImages,
+    BrokenFiles,
+}
+
+impl CacheKind {
+    fn from_file_name(cache_file_name: &str) -> Option<Self> {
+        if duplicate::get_all_cache_file_names().iter().any(|name| name == cache_file_name) {
+            Some(CacheKind::Duplicates)
+        } else if cache_file_name == similar_images::CACHE_FILE_NAME {
+            Some(CacheKind::SimilarImages)
+        } else if cache_file_name == broken_files::CACHE_FILE_NAME {
+            Some(CacheKind::BrokenFiles)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where cache of search is saved and loaded from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheLocation {
+    /// Custom cache folder, `None` means default one from `get_cache_dir`
+    pub cache_dir: Option<PathBuf>,
+    /// Cache of files from scanned volumes is saved in `PORTABLE_CACHE_DIR_NAME` folder in root of each volume, with paths relative to this root.
+    /// This way hashes of external drive are used also when it is mounted in other place or on other computer
+    pub portable: bool,
+}
+
+impl CacheLocation {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn get_cache_dir(&self) -> Option<PathBuf> {
+        self.cache_dir.clone().or_else(get_cache_dir)
+    }
+}
+
+/// How many files checked during search were loaded from cache instead of being checked again
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheUsage {
+    pub loaded_from_cache: u64,
+    pub checked_files: u64,
+}
+
+impl CacheUsage {
+    /// `None` when no file was checked
+    pub fn hit_rate(&self) -> Option<f64> {
+        if self.checked_files == 0 {
+            None
+        } else {
+            Some(self.loaded_from_cache as f64 / self.checked_files as f64)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CacheFileState {
+    Valid {
+        number_of_entries: u64,
+        last_scan_usage: CacheUsage,
+    },
+    /// Saved in text format by older versions of Czkawka, it will be converted during next search
+    Legacy,
+    /// Saved by other version of Czkawka or damaged, it will be created again during next search
+    Invalid,
+}
+
+/// Statistics of one cache file
+#[derive(Clone, Debug)]
+pub struct CacheFileInfo {
+    pub path: PathBuf,
+    pub file_size: u64,
+    pub state: CacheFileState,
+}
+
+impl fmt::Display for CacheFileInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file_name = self.path.file_name().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        write!(f, "{} - {}", file_name, self.file_size.file_size(options::BINARY).unwrap())?;
+        match &self.state {
+            CacheFileState::Valid { number_of_entries, last_scan_usage } => {
+                write!(f, ", {} entries", number_of_entries)?;
+                if let Some(hit_rate) = last_scan_usage.hit_rate() {
+                    write!(
+                        f,
+                        ", {} of {} files loaded from cache during last search ({:.1}%)",
+                        last_scan_usage.loaded_from_cache,
+                        last_scan_usage.checked_files,
+                        hit_rate * 100.0
+                    )?;
+                }
+                Ok(())
+            }
+            CacheFileState::Legacy => write!(f, ", saved in old text format, will be converted during next search"),
+            CacheFileState::Invalid => write!(f, ", created by different version of Czkawka or damaged, will be created again during next search"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheCleanResults {
+    pub removed_entries: u64,
+    pub remaining_entries: u64,
+    pub freed_bytes: u64,
+}
+
+/// Folder where all cache files are stored
+pub fn get_cache_dir() -> Option<PathBuf> {
+    // Lin: /home/username/.cache/czkawka
+    // Win: C:\Users\Username\AppData\Local\Qarmin\Czkawka\cache
+    // Mac: /Users/Username/Library/Caches/pl.Qarmin.Czkawka
+    ProjectDirs::from("pl", "Qarmin", "Czkawka").map(|proj_dirs| PathBuf::from(proj_dirs.cache_dir()))
+}
+
+fn calculate_checksum(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+/// Binary cache file is made from magic bytes, header and entries
+pub(crate) fn serialize_cache<T: Serialize>(entries: &[&T], last_scan_usage: CacheUsage) -> Vec<u8> {
+    let entries_data = bincode_options().serialize(entries).unwrap();
+    let header = CacheHeader {
+        format_version: CACHE_FORMAT_VERSION,
+        czkawka_version: env!("CARGO_PKG_VERSION").to_string(),
+        number_of_entries: entries.len() as u64,
+        checksum: calculate_checksum(&entries_data),
+        last_scan_usage,
+    };
+
+    let mut data = CACHE_MAGIC.to_vec();
+    data.extend(bincode_options().serialize(&header).unwrap());
+    data.extend(entries_data);
+    data
+}
+
+/// Returns `None` when data was saved by other version of Czkawka or is damaged
+pub(crate) fn deserialize_cache<T: DeserializeOwned>(data: &[u8]) -> Option<Vec<T>> {
+    deserialize_cache_with_header(data).map(|(_header, entries)| entries)
+}
+
+/// Checks header and returns it with still serialized entries
+fn deserialize_header(data: &[u8]) -> Option<(CacheHeader, &[u8])> {
+    if !data.starts_with(CACHE_MAGIC) {
+        return None;
+    }
+    let mut data = &data[CACHE_MAGIC.len()..];
+    let header: CacheHeader = bincode_options().with_limit(CACHE_HEADER_LIMIT).deserialize_from(&mut data).ok()?;
+    if header.format_version != CACHE_FORMAT_VERSION || header.czkawka_version != env!("CARGO_PKG_VERSION") || header.checksum != calculate_checksum(data) {
+        return None;
+    }
+    Some((header, data))
+}
+
+fn deserialize_cache_with_header<T: DeserializeOwned>(data: &[u8]) -> Option<(CacheHeader, Vec<T>)> {
+    let (header, data) = deserialize_header(data)?;
+    let entries: Vec<T> = bincode_options().deserialize(data).ok()?;
+    if entries.len() as u64 != header.number_of_entries {
+        return None;
+    }
+    Some((header, entries))
+}
+
+/// Cache is written to temporary file first, so cache is never left half written when app is closed
+fn write_cache_file(cache_file: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temporary_cache_file = cache_file.as_os_str().to_os_string();
+    temporary_cache_file.push(".tmp");
+    let result = fs::write(&temporary_cache_file, data).and_then(|_| fs::rename(&temporary_cache_file, cache_file));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary_cache_file);
+    }
+    result
+}
+
+fn add_write_warning(path: &Path, error: io::Error, text_messages: &mut Messages) {
+    text_messages.add_warning(MessageEntry::Io {
+        operation: Operation::WriteFile,
+        path: path.to_path_buf(),
+        kind: error.kind(),
+    });
+}
+
+fn create_cache_dir(cache_dir: &Path, text_messages: &mut Messages) -> bool {
+    // Fails also when cache dir is a file
+    if let Err(e) = fs::create_dir_all(cache_dir) {
+        add_write_warning(cache_dir, e, text_messages);
+        return false;
+    }
+    true
+}
+
+fn save_cache_in_dir<T: Serialize>(entries: &[&T], cache_dir: &Path, cache_file_name: &str, last_scan_usage: CacheUsage, text_messages: &mut Messages) -> bool {
+    if !create_cache_dir(cache_dir, text_messages) {
+        return false;
+    }
+    let cache_file = cache_dir.join(cache_file_name);
+    if let Err(e) = write_cache_file(&cache_file, &serialize_cache(entries, last_scan_usage)) {
+        add_write_warning(&cache_file, e, text_messages);
+        return false;
+    }
+    true
+}
+
+/// Paths of entries outside `base_path` are dropped, other are made relative to it
+fn make_paths_relative<T: CacheEntry>(entries: Vec<T>, base_path: &Path) -> Vec<T> {
+    entries
+        .into_iter()
+        .filter_map(|mut entry| {
+            let relative_path = entry.get_path().strip_prefix(base_path).ok()?.to_path_buf();
+            entry.set_path(relative_path);
+            Some(entry)
+        })
+        .collect()
+}
+
+/// Entries with absolute paths are dropped, relative paths are joined to `base_path`
+fn make_paths_absolute<T: CacheEntry>(entries: Vec<T>, base_path: &Path) -> Vec<T> {
+    entries
+        .into_iter()
+        .filter(|entry| entry.get_path().is_relative())
+        .map(|mut entry| {
+            let absolute_path = base_path.join(entry.get_path());
+            entry.set_path(absolute_path);
+            entry
+        })
+        .collect()
+}
+
+/// Root folder of volume on which path is placed - mount point on Unix and drive on other systems
+#[cfg(target_family = "unix")]
+fn get_volume_root(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+    let device = fs::metadata(path).ok()?.dev();
+    let mut volume_root = path;
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(metadata) if metadata.dev() == device => volume_root = ancestor,
+            _ => break,
+        }
+    }
+    Some(volume_root.to_path_buf())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn get_volume_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors().last().map(Path::to_path_buf)
+}
+
+/// Roots of volumes with included directories, which are used only by portable cache.
+/// Nested volumes are first, so their files are not saved in cache of parent volume
+pub(crate) fn get_cache_volume_roots(cache_location: &CacheLocation, included_directories: &[PathBuf]) -> Vec<PathBuf> {
+    if !cache_location.portable {
+        return Vec::new();
+    }
+    let mut volume_roots: Vec<PathBuf> = included_directories.iter().filter_map(|directory| get_volume_root(directory)).collect();
+    volume_roots.sort_by_key(|volume_root| std::cmp::Reverse(volume_root.components().count()));
+    volume_roots.dedup();
+    volume_roots
+}
+
+/// Saves entries to binary cache file and removes cache in old text format, because it is no longer needed.
+/// With portable cache, entries from `volume_roots` are saved on these volumes and only rest goes to cache folder
+pub(crate) fn save_cache_to_file<T: CacheEntry>(entries: &[&T], cache_location: &CacheLocation, volume_roots: &[PathBuf], cache_file_name: &str, legacy_cache_file_name: &str, last_scan_usage: CacheUsage, text_messages: &mut Messages) {
+    // Identity must be read before paths are made relative
+    let mut remaining_entries: Vec<CachedEntry<T>> = entries.iter().map(|entry| CachedEntry::new((*entry).clone())).collect();
+    if cache_location.portable {
+        for volume_root in volume_roots {
+            let (volume_entries, other_entries): (Vec<CachedEntry<T>>, Vec<CachedEntry<T>>) = remaining_entries.into_iter().partition(|entry| entry.get_path().starts_with(volume_root));
+            remaining_entries = other_entries;
+            if volume_entries.is_empty() {
+                continue;
+            }
+            let relative_entries = make_paths_relative(volume_entries.clone(), volume_root);
+            // e.g. volume is read only, so cache can be saved only in cache folder
+            if !save_cache_in_dir(&relative_entries.iter().collect::<Vec<_>>(), &volume_root.join(PORTABLE_CACHE_DIR_NAME), cache_file_name, last_scan_usage, text_messages) {
+                remaining_entries.extend(volume_entries);
+            }
+        }
+    }
+
+    let cache_dir = match cache_location.get_cache_dir() {
+        Some(t) => t,
+        None => return,
+    };
+    if !save_cache_in_dir(&remaining_entries.iter().collect::<Vec<_>>(), &cache_dir, cache_file_name, last_scan_usage, text_messages) {
+        return;
+    }
+
+    let legacy_cache_file = cache_dir.join(legacy_cache_file_name);
+    if legacy_cache_file.exists() {
+        let _ = fs::remove_file(legacy_cache_file);
+    }
+}
+
+fn load_cache_from_dir<T: CacheEntry>(cache_dir: &Path, cache_file_name: &str, text_messages: &mut Messages) -> Option<Vec<T>> {
+    let cache_file = cache_dir.join(cache_file_name);
+    if !cache_file.exists() {
+        return None;
+    }
+
+    let data = match fs::read(&cache_file) {
+        Ok(t) => t,
+        Err(e) => {
+            text_messages.add_warning(MessageEntry::Io {
+                operation: Operation::ReadFile,
+                path: cache_file,
+                kind: e.kind(),
+            });
+            return None;
+        }
+    };
+    match deserialize_cache(&data) {
+        Some(entries) => Some(entries),
+        None => {
+            // Cache will be overwritten with valid data after this search
+            text_messages
+                .messages
+                .push(format!("Cache file {} was created by different version of Czkawka or is damaged, it will be created again", cache_file.display()));
+            None
+        }
+    }
+}
+
+/// Loads entries from binary cache file, entries of files which exist under saved path are returned first and rest may be found later in `MovedEntries`.
+/// When it doesn't exist yet, cache in old text format is loaded by `load_legacy_cache`, so it is migrated to new format with next save.
+/// With portable cache, entries saved on volumes from `volume_roots` are also loaded
+pub(crate) fn load_cache_from_file<T: CacheEntry>(
+    cache_location: &CacheLocation,
+    volume_roots: &[PathBuf],
+    cache_file_name: &str,
+    legacy_cache_file_name: &str,
+    load_legacy_cache: fn(&Path, &mut Messages) -> Option<Vec<T>>,
+    text_messages: &mut Messages,
+) -> Option<(Vec<T>, MovedEntries<T>)> {
+    let mut loaded_entries: Option<Vec<CachedEntry<T>>> = match cache_location.get_cache_dir() {
+        Some(cache_dir) => {
+            let legacy_cache_file = cache_dir.join(legacy_cache_file_name);
+            if !cache_dir.join(cache_file_name).exists() && legacy_cache_file.exists() {
+                load_legacy_cache(&legacy_cache_file, text_messages).map(|entries| entries.into_iter().map(|entry| CachedEntry { file_id: None, entry }).collect())
+            } else {
+                load_cache_from_dir(&cache_dir, cache_file_name, text_messages)
+            }
+        }
+        None => {
+            text_messages.messages.push("Cannot find or open system config dir to save cache file".to_string());
+            None
+        }
+    };
+
+    if cache_location.portable {
+        for volume_root in volume_roots {
+            if let Some(volume_entries) = load_cache_from_dir(&volume_root.join(PORTABLE_CACHE_DIR_NAME), cache_file_name, text_messages) {
+                loaded_entries.get_or_insert_with(Vec::new).extend(make_paths_absolute(volume_entries, volume_root));
+            }
+        }
+    }
+
+    // File could be saved in both cache folder and on volume, when volume was earlier read only
+    let loaded_entries: BTreeMap<PathBuf, CachedEntry<T>> = loaded_entries?.into_iter().map(|entry| (entry.get_path().to_path_buf(), entry)).collect();
+
+    let mut entries = Vec::new();
+    let mut moved_entries = MovedEntries::default();
+    for cached_entry in loaded_entries.into_values() {
+        if cached_entry.get_path().exists() {
+            entries.push(cached_entry.entry);
+        } else if let Some(file_id) = cached_entry.file_id {
+            moved_entries.entries.insert((file_id, cached_entry.get_size(), cached_entry.get_modified_date()), cached_entry.entry);
+        }
+    }
+    Some((entries, moved_entries))
+}
+
+fn is_cache_file(path: &Path) -> bool {
+    let file_name = match path.file_name() {
+        Some(t) => t.to_string_lossy(),
+        None => return false,
+    };
+    file_name.starts_with("cache_") && (file_name.ends_with(".bin") || file_name.ends_with(".txt"))
+}
+
+fn get_cache_files(cache_dir: &Path) -> Vec<PathBuf> {
+    let read_dir = match fs::read_dir(cache_dir) {
+        Ok(t) => t,
+        Err(_) => return Vec::new(),
+    };
+    let mut cache_files: Vec<PathBuf> = read_dir.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_file() && is_cache_file(path)).collect();
+    cache_files.sort();
+    cache_files
+}
+
+/// Statistics of all cache files from cache folder
+pub fn get_cache_files_info(cache_dir: &Path) -> Vec<CacheFileInfo> {
+    get_cache_files(cache_dir)
+        .into_iter()
+        .map(|path| {
+            let data = fs::read(&path).unwrap_or_default();
+            let state = if path.extension() == Some(OsStr::new("txt")) {
+                CacheFileState::Legacy
+            } else {
+                // Checksum is checked with header, so entries don't need to be loaded
+                match deserialize_header(&data) {
+                    Some((header, _entries_data)) => CacheFileState::Valid {
+                        number_of_entries: header.number_of_entries,
+                        last_scan_usage: header.last_scan_usage,
+                    },
+                    None => CacheFileState::Invalid,
+                }
+            };
+            CacheFileInfo { path, file_size: data.len() as u64, state }
+        })
+        .collect()
+}
+
+/// Relative paths from portable cache are relative to `volume_root`, absolute paths are not changed by it
+fn is_entry_outdated<T: CacheEntry>(entry: &T, volume_root: &Path) -> bool {
+    match fs::metadata(volume_root.join(entry.get_path())) {
+        // Same as when collecting files, date before Unix Epoch is saved as 0
+        Ok(metadata) => match metadata.modified() {
+            Ok(modified) => modified.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) != entry.get_modified_date(),
+            Err(_) => true,
+        },
+        Err(_) => true,
+    }
+}
+
+/// Removes entries of files which no longer exists or were modified, renamed files are found only during search, so their entries are also removed.
+/// When cache is still bigger than `max_cache_file_size`, entries of files with oldest modification date are also removed
+fn clean_cache_file<T: CacheEntry>(cache_file: &Path, max_cache_file_size: Option<u64>, clean_results: &mut CacheCleanResults, text_messages: &mut Messages) {
+    let data = match fs::read(cache_file) {
+        Ok(t) => t,
+        Err(_) => return,
+    };
+    let (header, entries) = match deserialize_cache_with_header::<CachedEntry<T>>(&data) {
+        Some(t) => t,
+        None => {
+            // Invalid cache will be created again during next search, so it is only a waste of space
+            if fs::remove_file(cache_file).is_ok() {
+                clean_results.freed_bytes += data.len() as u64;
+            }
+            return;
+        }
+    };
+
+    let volume_root = cache_file.parent().and_then(Path::parent).unwrap_or_else(|| Path::new(""));
+    let number_of_entries = entries.len() as u64;
+    let mut entries: Vec<CachedEntry<T>> = entries.into_iter().filter(|entry| !is_entry_outdated(entry, volume_root)).collect();
+    if let Some(max_cache_file_size) = max_cache_file_size {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.get_modified_date()));
+        let mut cache_size: u64 = 0;
+        let number_of_fitting_entries = entries
+            .iter()
+            .take_while(|entry| {
+                cache_size += bincode_options().serialized_size(entry).unwrap();
+                cache_size <= max_cache_file_size
+            })
+            .count();
+        entries.truncate(number_of_fitting_entries);
+    }
+
+    clean_results.removed_entries += number_of_entries - entries.len() as u64;
+    clean_results.remaining_entries += entries.len() as u64;
+    if entries.len() as u64 == number_of_entries {
+        return;
+    }
+
+    let new_data = serialize_cache(&entries.iter().collect::<Vec<_>>(), header.last_scan_usage);
+    match write_cache_file(cache_file, &new_data) {
+        Ok(()) => clean_results.freed_bytes += (data.len() as u64).saturating_sub(new_data.len() as u64),
+        Err(e) => add_write_warning(cache_file, e, text_messages),
+    }
+}
+
+/// Removes outdated entries from all cache files, `max_cache_file_size` limits size of each cache file.
+/// Works also with portable cache folder placed on volume
+pub fn clean_cache(cache_dir: &Path, max_cache_file_size: Option<u64>, text_messages: &mut Messages) -> CacheCleanResults {
+    let mut clean_results = CacheCleanResults::default();
+    for cache_file in get_cache_files(cache_dir) {
+        let cache_file_name = cache_file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        match CacheKind::from_file_name(&cache_file_name) {
+            Some(CacheKind::Duplicates) => clean_cache_file::<duplicate::FileEntry>(&cache_file, max_cache_file_size, &mut clean_results, text_messages),
+            Some(CacheKind::SimilarImages) => clean_cache_file::<similar_images::FileEntry>(&cache_file, max_cache_file_size, &mut clean_results, text_messages),
+            Some(CacheKind::BrokenFiles) => clean_cache_file::<broken_files::FileEntry>(&cache_file, max_cache_file_size, &mut clean_results, text_messages),
+            // Legacy cache is converted during next search
+            None => {}
+        }
+    }
+    clean_results
+}
+
+/// Removes all cache files, returns number of removed files
+pub fn clear_cache(cache_dir: &Path, text_messages: &mut Messages) -> usize {
+    let mut removed_files: usize = 0;
+    for cache_file in get_cache_files(cache_dir) {
+        match fs::remove_file(&cache_file) {
+            Ok(_) => removed_files += 1,
+            Err(e) => text_messages.add_warning(MessageEntry::Io {
+                operation: Operation::RemoveFile,
+                path: cache_file,
+                kind: e.kind(),
+            }),
+        }
+    }
+    removed_files
+}
+
+/// One cache file inside file with exported cache
+#[derive(Serialize, Deserialize)]
+struct ExportedCacheFile {
+    file_name: String,
+    data: Vec<u8>,
+}
+
+fn export_cache_file<T: CacheEntry>(data: &[u8], base_path: Option<&Path>) -> Option<(Vec<u8>, u64)> {
+    let mut entries: Vec<CachedEntry<T>> = deserialize_cache(data)?;
+    // Identity of file is valid only on this computer
+    for entry in &mut entries {
+        entry.file_id = None;
+    }
+    if let Some(base_path) = base_path {
+        entries = make_paths_relative(entries, base_path);
+    }
+    Some((serialize_cache(&entries.iter().collect::<Vec<_>>(), CacheUsage::default()), entries.len() as u64))
+}
+
+/// Entries which are already in cache are not changed, returns number of added entries
+fn import_cache_file<T: CacheEntry>(exported_data: &[u8], cache_file: &Path, base_path: Option<&Path>, text_messages: &mut Messages) -> u64 {
+    let mut imported_entries: Vec<CachedEntry<T>> = match deserialize_cache(exported_data) {
+        Some(t) => t,
+        None => return 0,
+    };
+    imported_entries = match base_path {
+        Some(base_path) => make_paths_absolute(imported_entries, base_path),
+        // Relative paths are valid only with base path
+        None => imported_entries.into_iter().filter(|entry| entry.get_path().is_absolute()).collect(),
+    };
+
+    let (last_scan_usage, cached_entries) = match fs::read(cache_file).ok().and_then(|data| deserialize_cache_with_header::<CachedEntry<T>>(&data)) {
+        Some((header, entries)) => (header.last_scan_usage, entries),
+        None => (CacheUsage::default(), Vec::new()),
+    };
+    let mut entries: BTreeMap<PathBuf, CachedEntry<T>> = cached_entries.into_iter().map(|entry| (entry.get_path().to_path_buf(), entry)).collect();
+    let mut imported_entries_number: u64 = 0;
+    for entry in imported_entries {
+        if !entries.contains_key(entry.get_path()) {
+            entries.insert(entry.get_path().to_path_buf(), entry);
+            imported_entries_number += 1;
+        }
+    }
+
+    if imported_entries_number > 0 {
+        if let Err(e) = write_cache_file(cache_file, &serialize_cache(&entries.values().collect::<Vec<_>>(), last_scan_usage)) {
+            add_write_warning(cache_file, e, text_messages);
+            return 0;
+        }
+    }
+    imported_entries_number
+}
+
+/// Saves all cache files to one file, which can be imported on other computer.
+/// With `base_path` only entries of files inside it are exported, with paths relative to it, so they can be imported with other base path.
+/// Returns number of exported entries
+pub fn export_cache(cache_dir: &Path, exported_file: &Path, base_path: Option<&Path>, text_messages: &mut Messages) -> u64 {
+    let mut exported_files: Vec<ExportedCacheFile> = Vec::new();
+    let mut exported_entries: u64 = 0;
+    for cache_file in get_cache_files(cache_dir) {
+        let file_name = cache_file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let data = match fs::read(&cache_file) {
+            Ok(t) => t,
+            Err(e) => {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::ReadFile,
+                    path: cache_file,
+                    kind: e.kind(),
+                });
+                continue;
+            }
+        };
+        let exported_data = match CacheKind::from_file_name(&file_name) {
+            Some(CacheKind::Duplicates) => export_cache_file::<duplicate::FileEntry>(&data, base_path),
+            Some(CacheKind::SimilarImages) => export_cache_file::<similar_images::FileEntry>(&data, base_path),
+            Some(CacheKind::BrokenFiles) => export_cache_file::<broken_files::FileEntry>(&data, base_path),
+            None => continue,
+        };
+        // Invalid cache is silently skipped, like during search
+        if let Some((data, entries_number)) = exported_data {
+            exported_files.push(ExportedCacheFile { file_name, data });
+            exported_entries += entries_number;
+        }
+    }
+
+    if let Err(e) = write_cache_file(exported_file, &serialize_cache(&exported_files.iter().collect::<Vec<_>>(), CacheUsage::default())) {
+        add_write_warning(exported_file, e, text_messages);
+        return 0;
+    }
+    exported_entries
+}
+
+/// Adds entries from file created by `export_cache` to cache files, entries already in cache are kept.
+/// `base_path` must be given when cache was exported with base path. Returns number of added entries
+pub fn import_cache(cache_dir: &Path, imported_file: &Path, base_path: Option<&Path>, text_messages: &mut Messages) -> u64 {
+    let exported_files: Vec<ExportedCacheFile> = match fs::read(imported_file).ok().and_then(|data| deserialize_cache(&data)) {
+        Some(t) => t,
+        None => {
+            text_messages
+                .messages
+                .push(format!("File {} is not exported cache, was created by different version of Czkawka or is damaged", imported_file.display()));
+            return 0;
+        }
+    };
+    if !create_cache_dir(cache_dir, text_messages) {
+        return 0;
+    }
+
+    let mut imported_entries: u64 = 0;
+    for exported_file in exported_files {
+        let cache_file = cache_dir.join(&exported_file.file_name);
+        imported_entries += match CacheKind::from_file_name(&exported_file.file_name) {
+            Some(CacheKind::Duplicates) => import_cache_file::<duplicate::FileEntry>(&exported_file.data, &cache_file, base_path, text_messages),
+            Some(CacheKind::SimilarImages) => import_cache_file::<similar_images::FileEntry>(&exported_file.data, &cache_file, base_path, text_messages),
+            Some(CacheKind::BrokenFiles) => import_cache_file::<broken_files::FileEntry>(&exported_file.data, &cache_file, base_path, text_messages),
+            None => 0,
+        };
+    }
+    imported_entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+    struct TestEntry {
+        path: PathBuf,
+        modified_date: u64,
+        hash: String,
+    }
+
+    impl CacheEntry for TestEntry {
+        fn get_path(&self) -> &Path {
+            &self.path
+        }
+        fn set_path(&mut self, path: PathBuf) {
+            self.path = path;
+        }
+        fn get_size(&self) -> u64 {
+            0
+        }
+        fn get_modified_date(&self) -> u64 {
+            self.modified_date
+        }
+    }
+
+    fn without_file_id<T: CacheEntry>(entries: &[T]) -> Vec<CachedEntry<T>> {
+        entries.iter().map(|entry| CachedEntry { file_id: None, entry: entry.clone() }).collect()
+    }
+
+    fn test_entries() -> Vec<TestEntry> {
+        vec![
+            TestEntry {
+                path: PathBuf::from("/home/rafal//double/slash.txt"),
+                modified_date: 5,
+                hash: "abc".to_string(),
+            },
+            TestEntry {
+                path: PathBuf::from("/home/rafal/b.txt"),
+                modified_date: 0,
+                hash: "".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let entries = test_entries();
+        let data = serialize_cache(&entries.iter().collect::<Vec<_>>(), CacheUsage::default());
+        assert_eq!(deserialize_cache::<TestEntry>(&data), Some(entries));
+    }
+
+    #[test]
+    fn test_invalid_cache() {
+        let entries = test_entries();
+        let data = serialize_cache(&entries.iter().collect::<Vec<_>>(), CacheUsage::default());
+
+        // Old text cache
+        assert_eq!(deserialize_cache::<TestEntry>(b"/home/rafal/a.txt//5//1000//abc"), None);
+
+        // Damaged entries
+        let mut damaged_data = data.clone();
+        *damaged_data.last_mut().unwrap() ^= 1;
+        assert_eq!(deserialize_cache::<TestEntry>(&damaged_data), None);
+
+        // Cut file
+        assert_eq!(deserialize_cache::<TestEntry>(&data[..data.len() - 3]), None);
+
+        // Other version
+        let header = CacheHeader {
+            format_version: CACHE_FORMAT_VERSION + 1,
+            czkawka_version: env!("CARGO_PKG_VERSION").to_string(),
+            number_of_entries: 0,
+            checksum: 0,
+            last_scan_usage: CacheUsage::default(),
+        };
+        let mut other_version_data = CACHE_MAGIC.to_vec();
+        other_version_data.extend(bincode_options().serialize(&header).unwrap());
+        assert_eq!(deserialize_cache::<TestEntry>(&other_version_data), None);
+    }
+
+    #[test]
+    fn test_clean_cache_file() -> std::io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let existing_file = dir.path().join("a.txt");
+        fs::write(&existing_file, b"a")?;
+        let modified_date = fs::metadata(&existing_file)?.modified()?.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let entries = vec![
+            TestEntry {
+                path: existing_file.clone(),
+                modified_date,
+                hash: "a".to_string(),
+            },
+            TestEntry {
+                path: existing_file.clone(),
+                modified_date: modified_date + 1,
+                hash: "b".to_string(),
+            },
+            TestEntry {
+                path: dir.path().join("removed.txt"),
+                modified_date,
+                hash: "c".to_string(),
+            },
+        ];
+        let last_scan_usage = CacheUsage { loaded_from_cache: 1, checked_files: 4 };
+        let cache_file = dir.path().join("cache_test.bin");
+        fs::write(&cache_file, serialize_cache(&without_file_id(&entries).iter().collect::<Vec<_>>(), last_scan_usage))?;
+
+        let mut clean_results = CacheCleanResults::default();
+        clean_cache_file::<TestEntry>(&cache_file, None, &mut clean_results, &mut Messages::new());
+        assert_eq!(clean_results.removed_entries, 2);
+        assert_eq!(clean_results.remaining_entries, 1);
+        assert!(clean_results.freed_bytes > 0);
+
+        let (header, loaded_entries) = deserialize_cache_with_header::<CachedEntry<TestEntry>>(&fs::read(&cache_file)?).unwrap();
+        assert_eq!(loaded_entries, without_file_id(&entries[..1]));
+        assert_eq!(header.last_scan_usage.hit_rate(), Some(0.25));
+
+        // Even single entry is bigger than limit
+        clean_cache_file::<TestEntry>(&cache_file, Some(1), &mut clean_results, &mut Messages::new());
+        assert_eq!(deserialize_cache::<CachedEntry<TestEntry>>(&fs::read(&cache_file)?), Some(Vec::new()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_portable_cache() -> std::io::Result<()> {
+        let cache_dir = tempfile::Builder::new().tempdir()?;
+        let volume_root = tempfile::Builder::new().tempdir()?;
+        let cache_location = CacheLocation {
+            cache_dir: Some(cache_dir.path().to_path_buf()),
+            portable: true,
+        };
+        let volume_entry = TestEntry {
+            path: volume_root.path().join("photos/a.jpg"),
+            modified_date: 5,
+            hash: "a".to_string(),
+        };
+        let other_entry = TestEntry {
+            path: PathBuf::from("/home/rafal/b.jpg"),
+            modified_date: 5,
+            hash: "b".to_string(),
+        };
+        let volume_roots = vec![volume_root.path().to_path_buf()];
+        save_cache_to_file(&[&volume_entry, &other_entry], &cache_location, &volume_roots, "cache_test.bin", "cache_test.txt", CacheUsage::default(), &mut Messages::new());
+
+        // Entries from volume are saved only there, with relative paths
+        let volume_entries: Vec<CachedEntry<TestEntry>> = deserialize_cache(&fs::read(volume_root.path().join(PORTABLE_CACHE_DIR_NAME).join("cache_test.bin"))?).unwrap();
+        assert_eq!(volume_entries[0].get_path(), Path::new("photos/a.jpg"));
+        assert_eq!(deserialize_cache::<CachedEntry<TestEntry>>(&fs::read(cache_dir.path().join("cache_test.bin"))?), Some(without_file_id(&[other_entry.clone()])));
+
+        // Only entries of existing files are loaded
+        fs::create_dir_all(volume_root.path().join("photos"))?;
+        fs::write(&volume_entry.path, b"a")?;
+        let (loaded_entries, _moved_entries) = load_cache_from_file::<TestEntry>(&cache_location, &volume_roots, "cache_test.bin", "cache_test.txt", |_, _| None, &mut Messages::new()).unwrap();
+        assert_eq!(loaded_entries, vec![volume_entry.clone()]);
+
+        // Volume mounted in other place
+        let other_mount_point = PathBuf::from("/media/other");
+        let loaded_entries = make_paths_absolute(volume_entries, &other_mount_point);
+        assert_eq!(loaded_entries[0].get_path(), other_mount_point.join("photos/a.jpg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_cache() -> std::io::Result<()> {
+        let cache_dir = tempfile::Builder::new().tempdir()?;
+        let other_cache_dir = tempfile::Builder::new().tempdir()?;
+        let exported_file = cache_dir.path().join("exported.bin");
+        let entry = duplicate::FileEntry {
+            path: PathBuf::from("/media/rafal/disk/a.txt"),
+            size: 10,
+            modified_date: 5,
+            hash: "abc".to_string(),
+            already_deduplicated: false,
+        };
+        let outside_entry = duplicate::FileEntry {
+            path: PathBuf::from("/home/rafal/b.txt"),
+            ..entry.clone()
+        };
+        let cache_file_name = duplicate::get_all_cache_file_names().remove(0);
+        let cached_entries = without_file_id(&[entry.clone(), outside_entry]);
+        fs::write(cache_dir.path().join(&cache_file_name), serialize_cache(&cached_entries.iter().collect::<Vec<_>>(), CacheUsage::default()))?;
+
+        let mut messages = Messages::new();
+        assert_eq!(export_cache(cache_dir.path(), &exported_file, Some(Path::new("/media/rafal/disk")), &mut messages), 1);
+        // Relative paths cannot be imported without base path
+        assert_eq!(import_cache(other_cache_dir.path(), &exported_file, None, &mut messages), 0);
+        assert_eq!(import_cache(other_cache_dir.path(), &exported_file, Some(Path::new("/mnt/disk")), &mut messages), 1);
+        // Already imported entries are not added again
+        assert_eq!(import_cache(other_cache_dir.path(), &exported_file, Some(Path::new("/mnt/disk")), &mut messages), 0);
+        assert!(messages.messages.is_empty() && messages.warnings.is_empty());
+
+        let imported_entries: Vec<CachedEntry<duplicate::FileEntry>> = deserialize_cache(&fs::read(other_cache_dir.path().join(&cache_file_name))?).unwrap();
+        assert_eq!(
+            imported_entries,
+            without_file_id(&[duplicate::FileEntry {
+                path: PathBuf::from("/mnt/disk/a.txt"),
+                ..entry
+            }])
+        );
+        Ok(())
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_io_warnings() -> std::io::Result<()> {
+        let temp_dir = tempfile::Builder::new().tempdir()?;
+        let cache_dir = temp_dir.path().join("cache");
+        fs::write(&cache_dir, b"")?;
+        let cache_location = CacheLocation {
+            cache_dir: Some(cache_dir.clone()),
+            portable: false,
+        };
+
+        let mut messages = Messages::new();
+        save_cache_to_file(&[&test_entries()[0]], &cache_location, &[], "cache_test.bin", "cache_test.txt", CacheUsage::default(), &mut messages);
+        let exported_file = temp_dir.path().join("missing/exported.bin");
+        export_cache(temp_dir.path(), &exported_file, None, &mut messages);
+        assert_eq!(
+            messages.typed_warnings,
+            vec![
+                MessageEntry::Io {
+                    operation: Operation::WriteFile,
+                    path: cache_dir,
+                    kind: io::ErrorKind::AlreadyExists,
+                },
+                MessageEntry::Io {
+                    operation: Operation::WriteFile,
+                    path: exported_file,
+                    kind: io::ErrorKind::NotFound,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_moved_entries() -> std::io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let cache_location = CacheLocation {
+            cache_dir: Some(dir.path().join("cache")),
+            portable: false,
+        };
+        let old_path = dir.path().join("a.txt");
+        let new_path = dir.path().join("renamed/a.txt");
+        fs::write(&old_path, b"data")?;
+        let entry = duplicate::FileEntry {
+            path: old_path.clone(),
+            size: 4,
+            modified_date: 5,
+            hash: "abc".to_string(),
+            already_deduplicated: false,
+        };
+        save_cache_to_file(&[&entry], &cache_location, &[], "cache_test.bin", "cache_test.txt", CacheUsage::default(), &mut Messages::new());
+
+        fs::create_dir(dir.path().join("renamed"))?;
+        fs::rename(&old_path, &new_path)?;
+        let (loaded_entries, moved_entries) = load_cache_from_file::<duplicate::FileEntry>(&cache_location, &[], "cache_test.bin", "cache_test.txt", |_, _| None, &mut Messages::new()).unwrap();
+        assert!(loaded_entries.is_empty());
+        // Size and modification date must be still same
+        assert_eq!(moved_entries.find(&new_path, 4, 6), None);
+        assert_eq!(moved_entries.find(&new_path, 5, 5), None);
+        assert_eq!(moved_entries.find(&old_path, 4, 5), None);
+        assert_eq!(moved_entries.find(&new_path, 4, 5), Some(duplicate::FileEntry { path: new_path, ..entry }));
+        Ok(())
+    }
+}
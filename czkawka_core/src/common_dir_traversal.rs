@@ -0,0 +1,931 @@
+use crate::common_directory::Directories;
+use crate::common_extensions::Extensions;
+use crate::common_file_type::{detect_type, FileType};
+use crate::common_ignore_files::{IgnorePatterns, IgnoreStack, IGNORE_FILE_NAMES};
+use crate::common_items::ExcludedItems;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::ProgressCounters;
+use crate::common_scanner::ScanOptions;
+use crate::common_stop::StopToken;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+/// After this number of jumps symlink is treated as infinite loop
+pub(crate) const MAX_NUMBER_OF_SYMLINK_JUMPS: i32 = 20;
+
+/// What kind of entries should be collected by traversal
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Collect {
+    Files,
+    InvalidSymlinks,
+    EmptyFolders,
+}
+
+/// Basic info about found file or symlink, each tool converts it into its own entry
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified_date: u64,
+}
+
+/// Enum with values which show if folder is empty.
+/// In function "optimize_folders" automatically "Maybe" is changed to "Yes", so it is not necessary to put it here
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub(crate) enum FolderEmptiness {
+    No,
+    Maybe,
+}
+
+/// Struct assigned to each checked folder with parent path(used to ignore parent if children are not empty) and flag which shows if folder is empty
+#[derive(Clone, Debug)]
+pub struct FolderEntry {
+    pub(crate) parent_path: Option<PathBuf>, // Usable only when finding
+    pub(crate) is_empty: FolderEmptiness,
+    pub modified_date: u64,
+}
+
+pub enum DirTraversalResult {
+    SuccessFiles { entries: Vec<FileEntry>, warnings: Vec<MessageEntry> },
+    SuccessFolders { folder_entries: BTreeMap<PathBuf, FolderEntry>, warnings: Vec<MessageEntry> },
+    Stopped,
+}
+
+/// Walks included directories in parallel(every level of directory tree is checked by rayon thread pool) and applies
+/// the same excluded directories, excluded items, allowed and excluded extensions, size and modification date limits for all tools
+pub struct DirTraversal<'a> {
+    directories: &'a Directories,
+    excluded_items: &'a ExcludedItems,
+    allowed_extensions: Option<&'a Extensions>,
+    excluded_extensions: Extensions,
+    recursive_search: bool,
+    minimal_file_size: u64,
+    maximal_file_size: Option<u64>,
+    modified_after: Option<u64>,
+    modified_before: Option<u64>,
+    file_filter: Option<fn(&FileEntry) -> bool>,
+    content_types: Vec<FileType>,
+    collect: Collect,
+    use_ignore_files: bool,
+    max_depth: Option<usize>,
+    one_file_system: bool,
+    skip_hidden: bool,
+    follow_symlinks: bool,
+}
+
+/// Folder waiting to be checked with rules inherited from its parents
+struct FolderToCheck {
+    path: PathBuf,
+    ignore_stack: Option<Arc<IgnoreStack>>,
+    depth: usize,
+    device: Option<u64>, // Device of included directory, used only when scan must stay on one file system
+}
+
+/// Results of checking single folder, merged after every level of directory tree
+#[derive(Default)]
+struct FolderResult {
+    folders_to_check: Vec<FolderToCheck>,
+    warnings: Vec<MessageEntry>,
+    entries: Vec<FileEntry>,
+    folder_entries: Vec<(PathBuf, FolderEntry)>,
+    not_empty_folders: Vec<PathBuf>,
+    file_ids: Vec<(u64, u64)>,                       // Device and inode of collected files, used only when following symlinks
+    symlinked_entries: Vec<(FileEntry, (u64, u64))>, // Files found by symlinks, collected only when their destination is not collected
+}
+
+impl<'a> DirTraversal<'a> {
+    pub fn new(directories: &'a Directories, excluded_items: &'a ExcludedItems) -> Self {
+        Self {
+            directories,
+            excluded_items,
+            allowed_extensions: None,
+            excluded_extensions: Extensions::new(),
+            recursive_search: true,
+            minimal_file_size: 0,
+            maximal_file_size: None,
+            modified_after: None,
+            modified_before: None,
+            file_filter: None,
+            content_types: Vec::new(),
+            collect: Collect::Files,
+            use_ignore_files: false,
+            max_depth: None,
+            one_file_system: false,
+            skip_hidden: false,
+            follow_symlinks: false,
+        }
+    }
+
+    pub fn set_allowed_extensions(&mut self, allowed_extensions: &'a Extensions) {
+        self.allowed_extensions = Some(allowed_extensions);
+    }
+
+    pub fn set_recursive_search(&mut self, recursive_search: bool) {
+        self.recursive_search = recursive_search;
+    }
+
+    pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
+        self.minimal_file_size = minimal_file_size;
+    }
+
+    pub fn set_maximal_file_size(&mut self, maximal_file_size: Option<u64>) {
+        self.maximal_file_size = maximal_file_size;
+    }
+
+    /// Only entries modified at this time or later(in seconds since Unix Epoch) are collected
+    pub fn set_modified_after(&mut self, modified_after: Option<u64>) {
+        self.modified_after = modified_after;
+    }
+
+    /// Only entries modified at this time or earlier(in seconds since Unix Epoch) are collected
+    pub fn set_modified_before(&mut self, modified_before: Option<u64>) {
+        self.modified_before = modified_before;
+    }
+
+    /// Additional check used by tool to decide if file should be collected e.g. by checking its name
+    pub fn set_file_filter(&mut self, file_filter: fn(&FileEntry) -> bool) {
+        self.file_filter = Some(file_filter);
+    }
+
+    /// Only files which content is recognized as one of this types are collected, empty list means that type is not checked.
+    /// Start of every file which passed other checks must be read, so it is slower than checking extensions
+    pub fn set_content_types(&mut self, content_types: Vec<FileType>) {
+        self.content_types = content_types;
+    }
+
+    pub fn set_collect(&mut self, collect: Collect) {
+        self.collect = collect;
+    }
+
+    /// Rules from .gitignore, .ignore and .czkawkaignore files are used to exclude entries in folder where file is placed and its subfolders
+    pub fn set_use_ignore_files(&mut self, use_ignore_files: bool) {
+        self.use_ignore_files = use_ignore_files;
+    }
+
+    /// Maximum number of subfolder levels which are checked below included directory, 0 means that only files from included directory are checked
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
+
+    /// Folders placed on other devices than included directory(mount points, network shares etc.) are skipped
+    pub fn set_one_file_system(&mut self, one_file_system: bool) {
+        self.one_file_system = one_file_system;
+    }
+
+    /// Skips files and folders which names start with dot(and also these with hidden attribute on Windows)
+    pub fn set_skip_hidden(&mut self, skip_hidden: bool) {
+        self.skip_hidden = skip_hidden;
+    }
+
+    /// Symlinks to files and folders are checked like their destinations, every folder is visited only once, so symlink loops are not a problem.
+    /// Used only when collecting files, because invalid symlinks and empty folders must see symlinks themselves
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) {
+        self.follow_symlinks = follow_symlinks;
+    }
+
+    /// Options shared by all tools, content types are not set here, because only some tools support them
+    pub fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.recursive_search = scan_options.recursive_search;
+        self.use_ignore_files = scan_options.use_ignore_files;
+        self.max_depth = scan_options.max_depth;
+        self.one_file_system = scan_options.one_file_system;
+        self.skip_hidden = scan_options.skip_hidden;
+        self.maximal_file_size = scan_options.maximal_file_size;
+        self.modified_after = scan_options.modified_after;
+        self.modified_before = scan_options.modified_before;
+        self.follow_symlinks = scan_options.follow_symlinks;
+        // Excluded extensions are already validated when options are set in tool, so there is nothing to report
+        self.excluded_extensions = Extensions::new();
+        self.excluded_extensions.set_excluded_extensions(scan_options.excluded_extensions.clone(), &mut Messages::new());
+    }
+
+    /// Walks all included directories, counter is increased for every checked file(or folder when looking for empty folders)
+    pub fn run(&self, stop_token: &StopToken, progress: &ProgressCounters) -> DirTraversalResult {
+        // Rules loaded from excluded files are relative to each included directory
+        let mut folders_to_check: Vec<FolderToCheck> = self
+            .directories
+            .included_directories
+            .iter()
+            .map(|id| FolderToCheck {
+                path: id.clone(),
+                ignore_stack: if self.excluded_items.exclude_from.is_empty() {
+                    None
+                } else {
+                    Some(Arc::new(IgnoreStack::new(id.clone(), self.excluded_items.exclude_from.clone(), None)))
+                },
+                depth: 0,
+                device: if self.one_file_system { fs::metadata(id).ok().and_then(|metadata| get_device(&metadata)) } else { None },
+            })
+            .collect();
+        let mut entries: Vec<FileEntry> = Vec::new();
+        let mut folder_entries: BTreeMap<PathBuf, FolderEntry> = Default::default();
+        let mut warnings: Vec<MessageEntry> = Vec::new();
+        let mut file_ids: HashSet<(u64, u64)> = HashSet::new();
+        let mut symlinked_entries: Vec<(FileEntry, (u64, u64))> = Vec::new();
+
+        // Device and inode of every checked folder, so folder reachable by multiple symlinks is checked only once
+        let visited_folders: Option<Mutex<HashSet<(u64, u64)>>> = if self.follow_symlinks && self.collect == Collect::Files {
+            Some(Mutex::new(folders_to_check.iter().filter_map(|e| fs::metadata(&e.path).ok().and_then(|metadata| get_entry_id(&metadata))).collect()))
+        } else {
+            None
+        };
+
+        if self.collect == Collect::EmptyFolders {
+            for id in &self.directories.included_directories {
+                folder_entries.insert(
+                    id.clone(),
+                    FolderEntry {
+                        parent_path: None,
+                        is_empty: FolderEmptiness::Maybe,
+                        modified_date: 0,
+                    },
+                );
+            }
+        }
+
+        while !folders_to_check.is_empty() {
+            if stop_token.is_stopped() {
+                return DirTraversalResult::Stopped;
+            }
+
+            let folder_results: Vec<FolderResult> = folders_to_check
+                .par_iter()
+                .map(|current_folder| {
+                    if stop_token.is_stopped() {
+                        return FolderResult::default();
+                    }
+                    self.check_folder(current_folder, visited_folders.as_ref(), progress)
+                })
+                .collect();
+
+            // Results of folders skipped after stop are empty, so they must not be used
+            if stop_token.is_stopped() {
+                return DirTraversalResult::Stopped;
+            }
+
+            folders_to_check = Vec::with_capacity(folder_results.iter().map(|e| e.folders_to_check.len()).sum());
+            for folder_result in folder_results {
+                folders_to_check.extend(folder_result.folders_to_check);
+                warnings.extend(folder_result.warnings);
+                entries.extend(folder_result.entries);
+                file_ids.extend(folder_result.file_ids);
+                symlinked_entries.extend(folder_result.symlinked_entries);
+                folder_entries.extend(folder_result.folder_entries);
+                for not_empty_folder in folder_result.not_empty_folders {
+                    set_as_not_empty_folder(&mut folder_entries, &not_empty_folder);
+                }
+            }
+        }
+
+        // Symlink to file which is also found directly(or by other symlink) would be reported as its own duplicate, so removing one of them would break the other
+        for (fe, file_id) in symlinked_entries {
+            if file_ids.insert(file_id) {
+                entries.push(fe);
+            }
+        }
+
+        match self.collect {
+            Collect::EmptyFolders => DirTraversalResult::SuccessFolders { folder_entries, warnings },
+            Collect::Files | Collect::InvalidSymlinks => DirTraversalResult::SuccessFiles { entries, warnings },
+        }
+    }
+
+    fn check_folder(&self, folder_to_check: &FolderToCheck, visited_folders: Option<&Mutex<HashSet<(u64, u64)>>>, progress: &ProgressCounters) -> FolderResult {
+        let mut result = FolderResult::default();
+        let current_folder = folder_to_check.path.as_path();
+        progress.set_current_path(current_folder);
+
+        let mut ignore_stack = folder_to_check.ignore_stack.clone();
+        if self.use_ignore_files {
+            let mut ignore_patterns = IgnorePatterns::new();
+            for ignore_file_name in &IGNORE_FILE_NAMES {
+                ignore_patterns.load_from_file(&current_folder.join(ignore_file_name), &mut result.warnings);
+            }
+            if !ignore_patterns.is_empty() {
+                ignore_stack = Some(Arc::new(IgnoreStack::new(current_folder.to_path_buf(), ignore_patterns, ignore_stack)));
+            }
+        }
+        let is_ignored = |path: &Path, is_folder: bool| match &ignore_stack {
+            Some(ignore_stack) => ignore_stack.is_ignored(path, is_folder),
+            None => false,
+        };
+
+        // Read current dir, if permission are denied just go to next
+        let read_dir = match fs::read_dir(current_folder) {
+            Ok(t) => t,
+            Err(e) => {
+                if self.collect == Collect::EmptyFolders {
+                    // Checked folder may be deleted or we may not have permissions to open it so we assume that this folder is not be empty
+                    result.not_empty_folders.push(current_folder.to_path_buf());
+                } else {
+                    result.warnings.push(MessageEntry::Io {
+                        operation: Operation::OpenFolder,
+                        path: current_folder.to_path_buf(),
+                        kind: e.kind(),
+                    });
+                }
+                return result;
+            } // Permissions denied
+        };
+
+        // Check every sub folder/file/link etc.
+        for entry in read_dir {
+            let entry_data = match entry {
+                Ok(t) => t,
+                Err(e) => {
+                    result.warnings.push(MessageEntry::Io {
+                        operation: Operation::ReadFolderEntry,
+                        path: current_folder.to_path_buf(),
+                        kind: e.kind(),
+                    });
+                    self.set_as_not_empty(&mut result, current_folder);
+                    continue;
+                } //Permissions denied
+            };
+            let mut metadata: Metadata = match entry_data.metadata() {
+                Ok(t) => t,
+                Err(e) => {
+                    result.warnings.push(MessageEntry::Io {
+                        operation: Operation::ReadMetadata,
+                        path: current_folder.to_path_buf(),
+                        kind: e.kind(),
+                    });
+                    self.set_as_not_empty(&mut result, current_folder);
+                    continue;
+                } //Permissions denied
+            };
+
+            let is_followed_symlink = visited_folders.is_some() && metadata.file_type().is_symlink();
+            if is_followed_symlink {
+                let symlink_path = current_folder.join(entry_data.file_name());
+                metadata = match fs::metadata(&symlink_path) {
+                    Ok(t) => t,
+                    Err(_) => {
+                        // Broken symlinks are just skipped, they can be found by invalid symlinks tool
+                        if is_symlink_loop(&symlink_path) {
+                            result.warnings.push(MessageEntry::SymlinkLoop { path: symlink_path, to_parent: false });
+                        }
+                        continue;
+                    }
+                };
+            }
+
+            if metadata.is_dir() {
+                if self.collect == Collect::EmptyFolders {
+                    progress.add_items(1);
+                } else if !self.recursive_search {
+                    continue;
+                }
+
+                let next_folder = current_folder.join(entry_data.file_name());
+                let too_deep = matches!(self.max_depth, Some(max_depth) if folder_to_check.depth >= max_depth);
+                let other_device = folder_to_check.device.is_some() && get_device(&metadata) != folder_to_check.device;
+                if too_deep || other_device || (self.skip_hidden && is_hidden(&entry_data.file_name(), &metadata)) || self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) || is_ignored(&next_folder, true) {
+                    // Excluded or not checked folder may contain files, so parent cannot be treated as empty
+                    self.set_as_not_empty(&mut result, current_folder);
+                    continue;
+                }
+
+                if let Some(visited_folders) = visited_folders {
+                    if let Some(folder_id) = get_entry_id(&metadata) {
+                        if !visited_folders.lock().unwrap().insert(folder_id) {
+                            if is_followed_symlink && points_to_ancestor(&next_folder, current_folder) {
+                                result.warnings.push(MessageEntry::SymlinkLoop { path: next_folder, to_parent: true });
+                            }
+                            continue;
+                        }
+                    } else if is_followed_symlink && points_to_ancestor(&next_folder, current_folder) {
+                        result.warnings.push(MessageEntry::SymlinkLoop { path: next_folder, to_parent: true });
+                        continue;
+                    }
+                }
+
+                if self.collect == Collect::EmptyFolders {
+                    let modified_date = match get_modified_date(&metadata, &next_folder, &mut result.warnings) {
+                        Some(t) => t,
+                        None => {
+                            // Can't read data, so assuming that is not empty
+                            result.not_empty_folders.push(current_folder.to_path_buf());
+                            continue;
+                        }
+                    };
+                    result.folder_entries.push((
+                        next_folder.clone(),
+                        FolderEntry {
+                            parent_path: Some(current_folder.to_path_buf()),
+                            is_empty: FolderEmptiness::Maybe,
+                            modified_date,
+                        },
+                    ));
+                    // Folder modified outside of date range is not reported, but empty folders inside it still may be
+                    if !self.is_modified_in_range(modified_date) {
+                        result.not_empty_folders.push(next_folder.clone());
+                    }
+                }
+
+                result.folders_to_check.push(FolderToCheck {
+                    path: next_folder,
+                    ignore_stack: ignore_stack.clone(),
+                    depth: folder_to_check.depth + 1,
+                    device: folder_to_check.device,
+                });
+            } else {
+                // Not folder so it may be a file or symbolic link so it isn't empty
+                self.set_as_not_empty(&mut result, current_folder);
+
+                let is_wanted = match self.collect {
+                    Collect::Files => metadata.is_file(),
+                    Collect::InvalidSymlinks => metadata.is_file() || metadata.file_type().is_symlink(),
+                    Collect::EmptyFolders => false,
+                };
+                if !is_wanted || (self.skip_hidden && is_hidden(&entry_data.file_name(), &metadata)) {
+                    continue;
+                }
+                progress.add_items(1);
+                if metadata.is_file() {
+                    progress.add_bytes(metadata.len());
+                }
+                if self.collect == Collect::InvalidSymlinks && !metadata.file_type().is_symlink() {
+                    continue;
+                }
+
+                if metadata.is_file() && (metadata.len() < self.minimal_file_size || matches!(self.maximal_file_size, Some(maximal_file_size) if metadata.len() > maximal_file_size)) {
+                    continue;
+                }
+
+                let file_name_lowercase = entry_data.file_name().to_string_lossy().to_lowercase();
+                if let Some(allowed_extensions) = self.allowed_extensions {
+                    if !allowed_extensions.is_allowed(&file_name_lowercase) {
+                        continue;
+                    }
+                }
+                if !self.excluded_extensions.is_allowed(&file_name_lowercase) {
+                    continue;
+                }
+
+                let current_file_name = current_folder.join(entry_data.file_name());
+                if self.excluded_items.is_excluded(&current_file_name) || is_ignored(&current_file_name, false) {
+                    continue;
+                }
+
+                let modified_date = match get_modified_date(&metadata, &current_file_name, &mut result.warnings) {
+                    Some(t) => t,
+                    None => continue,
+                };
+                if !self.is_modified_in_range(modified_date) {
+                    continue;
+                }
+
+                let fe = FileEntry {
+                    path: current_file_name,
+                    size: metadata.len(),
+                    modified_date,
+                };
+
+                if let Some(file_filter) = self.file_filter {
+                    if !file_filter(&fe) {
+                        continue;
+                    }
+                }
+
+                if !self.content_types.is_empty() && !matches!(detect_type(&fe.path), Some(detected_type) if self.content_types.contains(&detected_type.file_type)) {
+                    continue;
+                }
+
+                if visited_folders.is_some() {
+                    match get_entry_id(&metadata) {
+                        Some(file_id) if is_followed_symlink => result.symlinked_entries.push((fe, file_id)),
+                        Some(file_id) => {
+                            result.file_ids.push(file_id);
+                            result.entries.push(fe);
+                        }
+                        None => result.entries.push(fe),
+                    }
+                } else {
+                    result.entries.push(fe);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn is_modified_in_range(&self, modified_date: u64) -> bool {
+        !matches!(self.modified_after, Some(modified_after) if modified_date < modified_after) && !matches!(self.modified_before, Some(modified_before) if modified_date > modified_before)
+    }
+
+    fn set_as_not_empty(&self, result: &mut FolderResult, current_folder: &Path) {
+        if self.collect == Collect::EmptyFolders {
+            result.not_empty_folders.push(current_folder.to_path_buf());
+        }
+    }
+}
+
+/// Returns modification date in seconds since Unix Epoch, `None` when it cannot be read
+fn get_modified_date(metadata: &Metadata, path: &Path, warnings: &mut Vec<MessageEntry>) -> Option<u64> {
+    match metadata.modified() {
+        Ok(t) => match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => Some(d.as_secs()),
+            Err(_) => {
+                warnings.push(MessageEntry::ModifiedBeforeUnixEpoch { path: path.to_path_buf() });
+                Some(0)
+            }
+        },
+        Err(e) => {
+            warnings.push(MessageEntry::Io {
+                operation: Operation::ReadModificationDate,
+                path: path.to_path_buf(),
+                kind: e.kind(),
+            });
+            None
+        } // Permissions Denied
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn get_device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+// Std doesn't expose volume of file on other systems, so checking file system is not supported there
+#[cfg(not(target_family = "unix"))]
+fn get_device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Device and inode of file or folder, the same for all symlinks and hard links to it
+#[cfg(target_family = "unix")]
+fn get_entry_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// On other systems only symlinks to parent folders are detected and symlinks to files are always collected
+#[cfg(not(target_family = "unix"))]
+fn get_entry_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Checks if symlinked folder is one of parents of folder in which symlink is placed
+fn points_to_ancestor(symlink_path: &Path, current_folder: &Path) -> bool {
+    match (fs::canonicalize(symlink_path), fs::canonicalize(current_folder)) {
+        (Ok(destination), Ok(current_folder)) => current_folder.starts_with(destination),
+        _ => false,
+    }
+}
+
+/// Symlink is treated as loop when destination is still a symlink after MAX_NUMBER_OF_SYMLINK_JUMPS jumps
+pub(crate) fn is_symlink_loop(symlink_path: &Path) -> bool {
+    let mut current_path = symlink_path.to_path_buf();
+    for _ in 0..MAX_NUMBER_OF_SYMLINK_JUMPS {
+        current_path = match current_path.read_link() {
+            // Relative destination is relative to folder with symlink
+            Ok(t) => match current_path.parent() {
+                Some(parent) => parent.join(t),
+                None => t,
+            },
+            Err(_) => return false,
+        };
+    }
+    true
+}
+
+#[cfg_attr(not(target_family = "windows"), allow(unused_variables))]
+fn is_hidden(file_name: &OsStr, metadata: &Metadata) -> bool {
+    #[cfg(target_family = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return true;
+        }
+    }
+    file_name.to_string_lossy().starts_with('.')
+}
+
+fn set_as_not_empty_folder(folder_entries: &mut BTreeMap<PathBuf, FolderEntry>, current_folder: &Path) {
+    let mut d = match folder_entries.get_mut(current_folder) {
+        Some(t) => t,
+        None => return,
+    };
+    // Loop to recursively set as non empty this and all his parent folders
+    loop {
+        d.is_empty = FolderEmptiness::No;
+        if d.parent_path.is_some() {
+            let cf = d.parent_path.clone().unwrap();
+            d = folder_entries.get_mut(&cf).unwrap();
+        } else {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_messages::Messages;
+    use std::fs::File;
+    use std::io;
+    use std::time::Duration;
+
+    fn directories_for(root: &Path) -> Directories {
+        let mut directories = Directories::new();
+        directories.set_included_directory(vec![root.to_path_buf()], &mut Messages::new());
+        directories
+    }
+
+    #[test]
+    fn test_traversal_files() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("a/b"))?;
+        fs::write(dir.path().join("first.txt"), b"1234")?;
+        fs::write(dir.path().join("a/second.txt"), b"12")?;
+        fs::write(dir.path().join("a/b/third.jpg"), b"")?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut extensions = Extensions::new();
+        extensions.set_allowed_extensions("txt".to_string(), &mut Messages::new());
+        let progress = ProgressCounters::new();
+
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_allowed_extensions(&extensions);
+        dir_traversal.set_minimal_file_size(3);
+        let entries = match dir_traversal.run(&StopToken::new(), &progress) {
+            DirTraversalResult::SuccessFiles { entries, .. } => entries,
+            _ => panic!(),
+        };
+        assert_eq!(progress.items_checked(), 3);
+        assert_eq!(progress.bytes_checked(), 6);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, dir.path().join("first.txt"));
+        assert_eq!(entries[0].size, 4);
+
+        dir_traversal.set_recursive_search(false);
+        dir_traversal.set_minimal_file_size(0);
+        dir_traversal.set_file_filter(|fe| fe.size == 4);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => assert_eq!(entries.len(), 1),
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_size_and_date_limits() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("old_empty"))?;
+        fs::write(dir.path().join("small.txt"), b"12")?;
+        fs::write(dir.path().join("big.txt"), b"123456")?;
+        let old_file = File::create(dir.path().join("old.txt"))?;
+        old_file.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000))?;
+        File::open(dir.path().join("old_empty"))?.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_maximal_file_size(Some(4));
+        dir_traversal.set_modified_after(Some(2_000_000));
+        let paths: Vec<PathBuf> = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => entries.into_iter().map(|fe| fe.path).collect(),
+            _ => panic!(),
+        };
+        assert_eq!(paths, vec![dir.path().join("small.txt")]);
+
+        dir_traversal.set_maximal_file_size(None);
+        dir_traversal.set_modified_after(None);
+        dir_traversal.set_modified_before(Some(2_000_000));
+        let paths: Vec<PathBuf> = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => entries.into_iter().map(|fe| fe.path).collect(),
+            _ => panic!(),
+        };
+        assert_eq!(paths, vec![dir.path().join("old.txt")]);
+
+        // Old empty folder is found only when its modification date is in range
+        dir_traversal.set_collect(Collect::EmptyFolders);
+        dir_traversal.set_modified_before(None);
+        dir_traversal.set_modified_after(Some(2_000_000));
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFolders { folder_entries, .. } => assert_eq!(folder_entries[&dir.path().join("old_empty")].is_empty, FolderEmptiness::No),
+            _ => panic!(),
+        };
+        dir_traversal.set_modified_after(None);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFolders { folder_entries, .. } => assert_eq!(folder_entries[&dir.path().join("old_empty")].is_empty, FolderEmptiness::Maybe),
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_content_types() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::write(dir.path().join("photo.dat"), b"\xFF\xD8\xFF\xE0\x00\x10JFIF")?;
+        fs::write(dir.path().join("fake.jpg"), b"text")?;
+        fs::write(dir.path().join("archive"), b"PK\x03\x04\x14\x00")?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_content_types(vec![FileType::Image, FileType::Video]);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => assert_eq!(entries.into_iter().map(|fe| fe.path).collect::<Vec<_>>(), vec![dir.path().join("photo.dat")]),
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_excluded() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("excluded"))?;
+        fs::create_dir_all(dir.path().join("included"))?;
+        File::create(dir.path().join("excluded/a.txt"))?;
+        File::create(dir.path().join("included/a.txt"))?;
+        File::create(dir.path().join("included/a.bak"))?;
+
+        let mut directories = directories_for(dir.path());
+        directories.set_excluded_directory(vec![dir.path().join("excluded")], &mut Messages::new());
+        let mut excluded_items = ExcludedItems::new();
+        excluded_items.set_excluded_items(vec!["*.bak".to_string()], &mut Messages::new());
+
+        match DirTraversal::new(&directories, &excluded_items).run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].path, dir.path().join("included/a.txt"));
+            }
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_excluded_extensions() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        File::create(dir.path().join("a.txt"))?;
+        File::create(dir.path().join("b.iso"))?;
+        File::create(dir.path().join("c.MKV"))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let scan_options = ScanOptions {
+            excluded_extensions: "iso,VIDEO".to_string(),
+            ..ScanOptions::new()
+        };
+
+        // Excluded extensions are used also by tools without allowed extensions
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_scan_options(&scan_options);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].path, dir.path().join("a.txt"));
+            }
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_ignore_files() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("target"))?;
+        fs::create_dir_all(dir.path().join("src/generated"))?;
+        fs::write(dir.path().join(".gitignore"), "/target\n*.tmp\n")?;
+        fs::write(dir.path().join("src/.czkawkaignore"), "generated/\n!keep.tmp\n")?;
+        File::create(dir.path().join("target/a.txt"))?;
+        File::create(dir.path().join("src/generated/a.txt"))?;
+        File::create(dir.path().join("src/a.tmp"))?;
+        File::create(dir.path().join("src/keep.tmp"))?;
+        File::create(dir.path().join("src/main.rs"))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_use_ignore_files(true);
+        let mut paths: Vec<PathBuf> = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => entries.into_iter().map(|fe| fe.path).collect(),
+            _ => panic!(),
+        };
+        paths.sort();
+        assert_eq!(paths, vec![dir.path().join(".gitignore"), dir.path().join("src/.czkawkaignore"), dir.path().join("src/keep.tmp"), dir.path().join("src/main.rs")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_depth_and_hidden() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("a/b/c"))?;
+        fs::create_dir_all(dir.path().join(".hidden"))?;
+        File::create(dir.path().join("root.txt"))?;
+        File::create(dir.path().join(".root.txt"))?;
+        File::create(dir.path().join("a/first.txt"))?;
+        File::create(dir.path().join("a/b/second.txt"))?;
+        File::create(dir.path().join("a/b/c/third.txt"))?;
+        File::create(dir.path().join(".hidden/file.txt"))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_max_depth(Some(1));
+        dir_traversal.set_skip_hidden(true);
+        dir_traversal.set_one_file_system(true);
+        let mut paths: Vec<PathBuf> = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => entries.into_iter().map(|fe| fe.path).collect(),
+            _ => panic!(),
+        };
+        paths.sort();
+        assert_eq!(paths, vec![dir.path().join("a/first.txt"), dir.path().join("root.txt")]);
+
+        dir_traversal.set_max_depth(Some(0));
+        dir_traversal.set_skip_hidden(false);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => assert_eq!(entries.len(), 2),
+            _ => panic!(),
+        };
+
+        // Folders which were not checked because of depth limit cannot be treated as empty
+        fs::create_dir_all(dir.path().join("empty/empty_inside"))?;
+        dir_traversal.set_max_depth(Some(1));
+        dir_traversal.set_collect(Collect::EmptyFolders);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFolders { folder_entries, .. } => assert!(folder_entries.values().all(|fe| fe.is_empty == FolderEmptiness::No)),
+            _ => panic!(),
+        };
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_traversal_follow_symlinks() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let outside = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("a"))?;
+        File::create(dir.path().join("a/file.txt"))?;
+        File::create(outside.path().join("outside.txt"))?;
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("to_outside"))?;
+        std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("to_a"))?;
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("a/to_parent"))?;
+        std::os::unix::fs::symlink(dir.path().join("a/file.txt"), dir.path().join("to_file.txt"))?;
+        std::os::unix::fs::symlink(dir.path().join("loop_2"), dir.path().join("loop_1"))?;
+        std::os::unix::fs::symlink(dir.path().join("loop_1"), dir.path().join("loop_2"))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, .. } => assert_eq!(entries.len(), 1),
+            _ => panic!(),
+        };
+
+        dir_traversal.set_follow_symlinks(true);
+        let (mut paths, warnings): (Vec<PathBuf>, Vec<MessageEntry>) = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFiles { entries, warnings } => (entries.into_iter().map(|fe| fe.path).collect(), warnings),
+            _ => panic!(),
+        };
+        paths.sort();
+        // Folder `a` is reachable also by `to_a` symlink, but it is checked only once and file is collected without its symlink
+        assert_eq!(paths.len(), 2, "{:?}", paths);
+        assert!(!paths.contains(&dir.path().join("to_file.txt")));
+        assert!(paths.contains(&dir.path().join("to_outside/outside.txt")));
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_empty_folders() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("empty/empty_inside"))?;
+        fs::create_dir_all(dir.path().join("not_empty/empty_inside"))?;
+        File::create(dir.path().join("not_empty/file"))?;
+
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let mut dir_traversal = DirTraversal::new(&directories, &excluded_items);
+        dir_traversal.set_collect(Collect::EmptyFolders);
+        let folder_entries = match dir_traversal.run(&StopToken::new(), &ProgressCounters::new()) {
+            DirTraversalResult::SuccessFolders { folder_entries, .. } => folder_entries,
+            _ => panic!(),
+        };
+        let empty: Vec<&PathBuf> = folder_entries.iter().filter(|(_, fe)| fe.is_empty != FolderEmptiness::No).map(|(path, _)| path).collect();
+        assert_eq!(empty, vec![&dir.path().join("empty"), &dir.path().join("empty/empty_inside"), &dir.path().join("not_empty/empty_inside")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_stopped() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let directories = directories_for(dir.path());
+        let excluded_items = ExcludedItems::new();
+        let stop_token = StopToken::new();
+        stop_token.stop();
+        assert!(matches!(DirTraversal::new(&directories, &excluded_items).run(&stop_token, &ProgressCounters::new()), DirTraversalResult::Stopped));
+    }
+}
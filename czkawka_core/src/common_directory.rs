@@ -1,5 +1,6 @@
 use crate::common::Common;
-use crate::common_messages::Messages;
+use crate::common_messages::{DirectoryKind, DirectoryProblem, MessageEntry, Messages};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
@@ -7,6 +8,7 @@ use std::time::SystemTime;
 pub struct Directories {
     pub excluded_directories: Vec<PathBuf>,
     pub included_directories: Vec<PathBuf>,
+    pub reference_directories: Vec<PathBuf>,
 }
 impl Directories {
     pub fn new() -> Self {
@@ -27,27 +29,47 @@ impl Directories {
         let mut checked_directories: Vec<PathBuf> = Vec::new();
         for directory in directories {
             if directory.to_string_lossy().contains('*') {
-                text_messages.warnings.push(format!("Included Directory Warning: Wildcards in path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Included,
+                    problem: DirectoryProblem::Wildcard,
+                });
                 continue;
             }
 
             #[cfg(not(target_family = "windows"))]
             if directory.is_relative() {
-                text_messages.warnings.push(format!("Included Directory Warning: Relative path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Included,
+                    problem: DirectoryProblem::Relative,
+                });
                 continue;
             }
             #[cfg(target_family = "windows")]
             if directory.is_relative() && !directory.starts_with("\\") {
-                text_messages.warnings.push(format!("Included Directory Warning: Relative path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Included,
+                    problem: DirectoryProblem::Relative,
+                });
                 continue;
             }
 
             if !directory.exists() {
-                text_messages.warnings.push(format!("Included Directory Warning: Provided folder path must exits, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Included,
+                    problem: DirectoryProblem::NotExists,
+                });
                 continue;
             }
             if !directory.is_dir() {
-                text_messages.warnings.push(format!("Included Directory Warning: Provided path must point at the directory, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Included,
+                    problem: DirectoryProblem::NotDirectory,
+                });
                 continue;
             }
             checked_directories.push(directory);
@@ -81,17 +103,29 @@ impl Directories {
                 break;
             }
             if directory_as_string.contains('*') {
-                text_messages.warnings.push(format!("Excluded Directory Warning: Wildcards in path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Excluded,
+                    problem: DirectoryProblem::Wildcard,
+                });
                 continue;
             }
             #[cfg(not(target_family = "windows"))]
             if directory.is_relative() {
-                text_messages.warnings.push(format!("Excluded Directory Warning: Relative path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Excluded,
+                    problem: DirectoryProblem::Relative,
+                });
                 continue;
             }
             #[cfg(target_family = "windows")]
             if directory.is_relative() && !directory.starts_with("\\") {
-                text_messages.warnings.push(format!("Excluded Directory Warning: Relative path are not supported, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Excluded,
+                    problem: DirectoryProblem::Relative,
+                });
                 continue;
             }
 
@@ -100,7 +134,11 @@ impl Directories {
                 continue;
             }
             if !directory.is_dir() {
-                text_messages.warnings.push(format!("Excluded Directory Warning: Provided path must point at the directory, ignoring {}", directory.display()));
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Excluded,
+                    problem: DirectoryProblem::NotDirectory,
+                });
                 continue;
             }
             checked_directories.push(directory);
@@ -110,6 +148,106 @@ impl Directories {
         Common::print_time(start_time, SystemTime::now(), "set_excluded_directory".to_string());
     }
 
+    /// Setting reference directories, files inside them are compared with other files, but are never removed or replaced.
+    /// Reference directories are always checked, so they don't need to be also added to included directories
+    pub fn set_reference_directory(&mut self, reference_directory: Vec<PathBuf>, text_messages: &mut Messages) {
+        let start_time: SystemTime = SystemTime::now();
+
+        let mut checked_directories: Vec<PathBuf> = Vec::new();
+        for directory in reference_directory {
+            if directory.to_string_lossy().contains('*') {
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Reference,
+                    problem: DirectoryProblem::Wildcard,
+                });
+                continue;
+            }
+            #[cfg(not(target_family = "windows"))]
+            if directory.is_relative() {
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Reference,
+                    problem: DirectoryProblem::Relative,
+                });
+                continue;
+            }
+            #[cfg(target_family = "windows")]
+            if directory.is_relative() && !directory.starts_with("\\") {
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Reference,
+                    problem: DirectoryProblem::Relative,
+                });
+                continue;
+            }
+            if !directory.is_dir() {
+                text_messages.add_warning(MessageEntry::InvalidDirectory {
+                    path: directory.clone(),
+                    kind: DirectoryKind::Reference,
+                    problem: DirectoryProblem::NotDirectory,
+                });
+                continue;
+            }
+            checked_directories.push(directory);
+        }
+        self.reference_directories = checked_directories;
+
+        Common::print_time(start_time, SystemTime::now(), "set_reference_directory".to_string());
+    }
+
+    /// Checks whether file is placed inside one of reference directories, so it must stay untouched
+    pub fn is_in_reference_directory(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        #[cfg(target_family = "windows")]
+        let path = &Common::normalize_windows_path(path);
+
+        self.reference_directories.iter().any(|rd| path.starts_with(rd))
+    }
+
+    /// Like `is_in_reference_directory`, but also finds files reached by symlinked folders, so it is used just before changing file
+    pub fn is_protected(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        if self.reference_directories.is_empty() {
+            return false;
+        }
+        if self.is_in_reference_directory(path) {
+            return true;
+        }
+        // Symlink itself may be removed, so only its parent folder is resolved
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        match fs::canonicalize(parent) {
+            Ok(parent) => self.reference_directories.iter().filter_map(|rd| fs::canonicalize(rd).ok()).any(|rd| parent.starts_with(rd)),
+            Err(_) => false,
+        }
+    }
+
+    /// Checks whether all files from group are placed inside reference directories, such groups are not shown to user
+    pub fn all_in_reference_directories<'a>(&self, mut paths: impl Iterator<Item = &'a Path>) -> bool {
+        !self.reference_directories.is_empty() && paths.all(|path| self.is_in_reference_directory(path))
+    }
+
+    /// Included directory which contains path, after optimization included directories don't overlap, so there is at most one such directory
+    pub fn get_included_directory(&self, path: impl AsRef<Path>) -> Option<&Path> {
+        let path = path.as_ref();
+        #[cfg(target_family = "windows")]
+        let path = &Common::normalize_windows_path(path);
+
+        self.included_directories.iter().find(|id| path.starts_with(id)).map(PathBuf::as_path)
+    }
+
+    /// Links between files from different included directories may break, when one of them is e.g. unmounted.
+    /// Reference directories are added to included directories only to be compared, so links to files inside them are always allowed
+    pub fn is_in_same_included_directory(&self, first: impl AsRef<Path>, second: impl AsRef<Path>) -> bool {
+        if self.is_in_reference_directory(&first) || self.is_in_reference_directory(&second) {
+            return true;
+        }
+        matches!((self.get_included_directory(first), self.get_included_directory(second)), (Some(first), Some(second)) if first == second)
+    }
+
     /// Remove unused entries when included or excluded overlaps with each other or are duplicated etc.
     pub fn optimize_directories(&mut self, recursive_search: bool, text_messages: &mut Messages) -> bool {
         let start_time: SystemTime = SystemTime::now();
@@ -117,9 +255,13 @@ impl Directories {
         let mut optimized_included: Vec<PathBuf> = Vec::new();
         let mut optimized_excluded: Vec<PathBuf> = Vec::new();
 
+        // Files from reference directories must be compared with others, so these directories are also checked
+        self.included_directories.extend(self.reference_directories.iter().cloned());
+
         if cfg!(target_family = "windows") {
             self.included_directories = self.included_directories.iter().map(Common::normalize_windows_path).collect();
             self.excluded_directories = self.excluded_directories.iter().map(Common::normalize_windows_path).collect();
+            self.reference_directories = self.reference_directories.iter().map(Common::normalize_windows_path).collect();
         }
 
         // Remove duplicated entries like: "/", "/"
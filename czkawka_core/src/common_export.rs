@@ -0,0 +1,205 @@
+use crate::common_directory::Directories;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Format of file with results, it is chosen by extension of file name
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Human readable format, different for each tool
+    Text,
+    /// Single JSON object with scanned directories and array with all results
+    Json,
+    /// First line contains scanned directories, next lines contain each result(single entry or whole group)
+    JsonLines,
+    /// One row per file, files from same group have same group id
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_file_name(file_name: &str) -> Self {
+        match Path::new(file_name).extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+            Some("json") => ExportFormat::Json,
+            Some("jsonl") | Some("ndjson") => ExportFormat::JsonLines,
+            Some("csv") => ExportFormat::Csv,
+            _ => ExportFormat::Text,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportedResults<'a, T> {
+    included_directories: &'a [PathBuf],
+    #[serde(skip_serializing_if = "<[PathBuf]>::is_empty")]
+    reference_directories: &'a [PathBuf],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<&'a [T]>,
+}
+
+/// Saves results in JSON format, other formats must be handled by tool itself, so they are reported as error
+/// Included and reference directories are saved together with results, so later only files inside included and outside reference directories may be changed when importing results
+pub(crate) fn save_results_to_json<T: Serialize>(directories: &Directories, results: &[T], file_name: &str, format: ExportFormat, text_messages: &mut Messages) -> bool {
+    if !matches!(format, ExportFormat::Json | ExportFormat::JsonLines) {
+        text_messages.add_error(MessageEntry::UnsupportedResultsFormat { path: PathBuf::from(file_name), saving: true });
+        return false;
+    }
+
+    let file_handler = match File::create(file_name) {
+        Ok(t) => t,
+        Err(e) => {
+            text_messages.add_error(MessageEntry::Io {
+                operation: Operation::CreateFile,
+                path: PathBuf::from(file_name),
+                kind: e.kind(),
+            });
+            return false;
+        }
+    };
+    let mut writer = BufWriter::new(file_handler);
+
+    let result = match format {
+        ExportFormat::Json => {
+            let exported_results = ExportedResults {
+                included_directories: &directories.included_directories,
+                reference_directories: &directories.reference_directories,
+                results: Some(results),
+            };
+            serde_json::to_writer_pretty(&mut writer, &exported_results).map_err(io::Error::from).and_then(|_| writeln!(writer))
+        }
+        ExportFormat::JsonLines => {
+            let header: ExportedResults<T> = ExportedResults {
+                included_directories: &directories.included_directories,
+                reference_directories: &directories.reference_directories,
+                results: None,
+            };
+            std::iter::once(serde_json::to_value(&header))
+                .chain(results.iter().map(serde_json::to_value))
+                .try_for_each(|value| value.and_then(|value| serde_json::to_writer(&mut writer, &value)).map_err(io::Error::from).and_then(|_| writeln!(writer)))
+        }
+        ExportFormat::Text | ExportFormat::Csv => unreachable!(),
+    };
+    if let Err(e) = result.and_then(|_| writer.flush()) {
+        text_messages.add_error(MessageEntry::Io {
+            operation: Operation::WriteFile,
+            path: PathBuf::from(file_name),
+            kind: e.kind(),
+        });
+        return false;
+    }
+    true
+}
+
+/// Modification date in CSV is saved as UTC date in the same format as accepted by date filters, so it is readable in spreadsheets
+/// Date which cannot be represented is saved as raw number of seconds
+pub(crate) fn format_csv_date(modified_date: u64) -> String {
+    match i64::try_from(modified_date).ok().and_then(|t| NaiveDateTime::from_timestamp_opt(t, 0)) {
+        Some(date) => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => modified_date.to_string(),
+    }
+}
+
+/// Saves results as CSV file with header, every row must have same number of columns as header
+pub(crate) fn save_results_to_csv<I: IntoIterator<Item = Vec<String>>>(headers: &[&str], rows: I, file_name: &str, text_messages: &mut Messages) -> bool {
+    let file_handler = match File::create(file_name) {
+        Ok(t) => t,
+        Err(e) => {
+            text_messages.add_error(MessageEntry::Io {
+                operation: Operation::CreateFile,
+                path: PathBuf::from(file_name),
+                kind: e.kind(),
+            });
+            return false;
+        }
+    };
+    let mut writer = csv::Writer::from_writer(file_handler);
+
+    let result = writer.write_record(headers).and_then(|_| rows.into_iter().try_for_each(|row| writer.write_record(&row)));
+    if let Err(e) = result.map_err(io::Error::from).and_then(|_| writer.flush()) {
+        text_messages.add_error(MessageEntry::Io {
+            operation: Operation::WriteFile,
+            path: PathBuf::from(file_name),
+            kind: e.kind(),
+        });
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[derive(Serialize)]
+    struct Entry {
+        path: PathBuf,
+        size: u64,
+    }
+
+    #[test]
+    fn test_export_format() {
+        assert_eq!(ExportFormat::from_file_name("results.txt"), ExportFormat::Text);
+        assert_eq!(ExportFormat::from_file_name("results"), ExportFormat::Text);
+        assert_eq!(ExportFormat::from_file_name("/tmp/results.JSON"), ExportFormat::Json);
+        assert_eq!(ExportFormat::from_file_name("results.jsonl"), ExportFormat::JsonLines);
+        assert_eq!(ExportFormat::from_file_name("results.csv"), ExportFormat::Csv);
+    }
+
+    #[test]
+    fn test_save_results_to_json() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let groups = vec![vec![Entry { path: PathBuf::from("/a"), size: 1 }, Entry { path: PathBuf::from("/b"), size: 1 }], vec![Entry { path: PathBuf::from("/c"), size: 2 }]];
+        let mut text_messages = Messages::new();
+
+        let file_name = dir.path().join("results.json").to_string_lossy().to_string();
+        let mut directories = Directories::new();
+        directories.included_directories = vec![PathBuf::from("/")];
+        assert!(save_results_to_json(&directories, &groups, &file_name, ExportFormat::Json, &mut text_messages));
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&file_name)?)?;
+        assert_eq!(value["included_directories"][0], "/");
+        assert!(value.get("reference_directories").is_none());
+        assert_eq!(value["results"][0][1]["path"], "/b");
+        assert_eq!(value["results"][1][0]["size"], 2);
+
+        let file_name = dir.path().join("results.jsonl").to_string_lossy().to_string();
+        assert!(save_results_to_json(&directories, &groups, &file_name, ExportFormat::JsonLines, &mut text_messages));
+        let content = fs::read_to_string(&file_name)?;
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            vec![r#"{"included_directories":["/"]}"#, r#"[{"path":"/a","size":1},{"path":"/b","size":1}]"#, r#"[{"path":"/c","size":2}]"#]
+        );
+
+        assert!(!save_results_to_json(&directories, &groups, &dir.path().join("missing/results.json").to_string_lossy(), ExportFormat::Json, &mut text_messages));
+        assert_eq!(text_messages.typed_errors[0].operation(), Some(Operation::CreateFile));
+
+        let file_name = dir.path().join("results.csv").to_string_lossy().to_string();
+        assert!(!save_results_to_json(&directories, &groups, &file_name, ExportFormat::Csv, &mut text_messages));
+        assert!(!Path::new(&file_name).exists());
+        assert_eq!(text_messages.typed_errors[1], MessageEntry::UnsupportedResultsFormat { path: PathBuf::from(&file_name), saving: true });
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_csv_date() {
+        assert_eq!(format_csv_date(0), "1970-01-01 00:00:00");
+        assert_eq!(format_csv_date(1_614_601_800), "2021-03-01 12:30:00");
+        assert_eq!(format_csv_date(i64::MAX as u64), (i64::MAX as u64).to_string());
+        assert_eq!(format_csv_date(u64::MAX), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn test_save_results_to_csv() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let rows = vec![vec!["1".to_string(), "/a".to_string()], vec!["1".to_string(), "/b, \"c\"".to_string()]];
+        let mut text_messages = Messages::new();
+
+        let file_name = dir.path().join("results.csv").to_string_lossy().to_string();
+        assert!(save_results_to_csv(&["group", "path"], rows, &file_name, &mut text_messages));
+        assert_eq!(fs::read_to_string(&file_name)?, "group,path\n1,/a\n1,\"/b, \"\"c\"\"\"\n");
+        Ok(())
+    }
+}
@@ -1,10 +1,19 @@
 use crate::common::Common;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use directories_next::ProjectDirs;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+/// File in config folder with extension macros defined by user, it is read by both CLI and GUI
+pub const EXTENSION_MACROS_FILE_NAME: &str = "extension_macros.txt";
+
 #[derive(Default)]
 pub struct Extensions {
     pub file_extensions: Vec<String>,
+    pub excluded_extensions: Vec<String>,
 }
 
 impl Extensions {
@@ -13,39 +22,181 @@ impl Extensions {
     }
     /// List of allowed extensions, only files with this extensions will be checking if are duplicates
     /// After, extensions cannot contains any dot, commas etc.
-    pub fn set_allowed_extensions(&mut self, mut allowed_extensions: String, text_messages: &mut Messages) {
+    pub fn set_allowed_extensions(&mut self, allowed_extensions: String, text_messages: &mut Messages) {
         let start_time: SystemTime = SystemTime::now();
         if allowed_extensions.is_empty() {
             return;
         }
-        allowed_extensions = allowed_extensions.replace("IMAGE", "jpg,kra,gif,png,bmp,tiff,hdr,svg");
-        allowed_extensions = allowed_extensions.replace("VIDEO", "mp4,flv,mkv,webm,vob,ogv,gifv,avi,mov,wmv,mpg,m4v,m4p,mpeg,3gp");
-        allowed_extensions = allowed_extensions.replace("MUSIC", "mp3,flac,ogg,tta,wma,webm");
-        allowed_extensions = allowed_extensions.replace("TEXT", "txt,doc,docx,odt,rtf");
 
-        let extensions: Vec<String> = allowed_extensions.split(',').map(String::from).collect();
-        for mut extension in extensions {
-            if extension.is_empty() || extension.replace('.', "").trim() == "" {
-                continue;
-            }
+        add_extensions(&mut self.file_extensions, &allowed_extensions, text_messages);
 
-            if extension.starts_with('.') {
-                extension = extension[1..].to_string();
-            }
+        if self.file_extensions.is_empty() {
+            text_messages.messages.push("No valid extensions were provided, so allowing all extensions by default.".to_string());
+        }
+        Common::print_time(start_time, SystemTime::now(), "set_allowed_extensions".to_string());
+    }
+
+    /// Files with this extensions are never checked, even when they are also allowed
+    pub fn set_excluded_extensions(&mut self, excluded_extensions: String, text_messages: &mut Messages) {
+        if excluded_extensions.is_empty() {
+            return;
+        }
+
+        add_extensions(&mut self.excluded_extensions, &excluded_extensions, text_messages);
+    }
+
+    /// Expects file name in lowercase
+    pub fn is_allowed(&self, file_name_lowercase: &str) -> bool {
+        let has_extension = |extensions: &Vec<String>| extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
+        (self.file_extensions.is_empty() || has_extension(&self.file_extensions)) && !has_extension(&self.excluded_extensions)
+    }
+}
+
+fn add_extensions(file_extensions: &mut Vec<String>, extensions: &str, text_messages: &mut Messages) {
+    let mut extensions = extensions.to_string();
+    extensions = extensions.replace("IMAGE", "jpg,kra,gif,png,bmp,tiff,hdr,svg");
+    extensions = extensions.replace("VIDEO", "mp4,flv,mkv,webm,vob,ogv,gifv,avi,mov,wmv,mpg,m4v,m4p,mpeg,3gp");
+    extensions = extensions.replace("MUSIC", "mp3,flac,ogg,tta,wma,webm");
+    extensions = extensions.replace("TEXT", "txt,doc,docx,odt,rtf");
+
+    for mut extension in extensions.split(',').map(str::trim) {
+        if extension.is_empty() || extension.replace('.', "").trim() == "" {
+            continue;
+        }
+
+        if let Some(stripped) = extension.strip_prefix('.') {
+            extension = stripped;
+        }
+
+        if extension[1..].contains('.') {
+            text_messages.warnings.push(".".to_string() + extension + " is not valid extension(valid extension doesn't have dot inside)");
+            continue;
+        }
+
+        if !file_extensions.iter().any(|e| e == extension) {
+            file_extensions.push(extension.to_string());
+        }
+    }
+}
+
+/// Named lists of extensions defined by user like `RAW=cr2,nef,arw`, which can be used in the same way as built-in IMAGE or VIDEO macros
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExtensionMacros {
+    macros: BTreeMap<String, String>,
+}
+
+impl ExtensionMacros {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn get_macros_file() -> Option<PathBuf> {
+        ProjectDirs::from("pl", "Qarmin", "Czkawka").map(|proj_dirs| proj_dirs.config_dir().join(EXTENSION_MACROS_FILE_NAME))
+    }
 
-            if extension[1..].contains('.') {
-                text_messages.warnings.push(".".to_string() + extension.as_str() + " is not valid extension(valid extension doesn't have dot inside)");
+    /// Loads macros from file in config folder, when file doesn't exist no macros are defined
+    pub fn load(text_messages: &mut Messages) -> Self {
+        match Self::get_macros_file() {
+            Some(macros_file) => Self::load_from_file(&macros_file, text_messages),
+            None => Self::new(),
+        }
+    }
+
+    pub fn load_from_file(macros_file: &Path, text_messages: &mut Messages) -> Self {
+        let mut extension_macros = Self::new();
+        match fs::read_to_string(macros_file) {
+            Ok(content) => extension_macros.add_macros(&content, macros_file, text_messages),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => text_messages.add_warning(MessageEntry::Io {
+                operation: Operation::ReadFile,
+                path: macros_file.to_path_buf(),
+                kind: e.kind(),
+            }),
+        }
+        extension_macros
+    }
+
+    /// Each line contains one macro in format `NAME=ext1,ext2`, empty lines and lines starting with # are skipped
+    pub fn add_macros(&mut self, content: &str, macros_file: &Path, text_messages: &mut Messages) {
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
-            if !self.file_extensions.contains(&extension.trim().to_string()) {
-                self.file_extensions.push(extension.trim().to_string());
+            match line.split_once('=') {
+                Some((name, extensions)) if !name.trim().is_empty() && !name.contains(',') => {
+                    self.macros.insert(name.trim().to_string(), extensions.trim().to_string());
+                }
+                _ => text_messages
+                    .warnings
+                    .push(format!("Line {} - \"{}\" in file {} is not valid extension macro(valid macro looks like RAW=cr2,nef,arw)", index + 1, line, macros_file.display())),
             }
         }
+    }
 
-        if self.file_extensions.is_empty() {
-            text_messages.messages.push("No valid extensions were provided, so allowing all extensions by default.".to_string());
+    pub fn get_macros(&self) -> &BTreeMap<String, String> {
+        &self.macros
+    }
+
+    /// Replaces names of user macros in list of extensions separated by commas with their extensions, built-in macros are replaced later by Extensions
+    pub fn expand(&self, extensions: &str) -> String {
+        if self.macros.is_empty() {
+            return extensions.to_string();
         }
-        Common::print_time(start_time, SystemTime::now(), "set_allowed_extensions".to_string());
+        extensions
+            .split(',')
+            .map(|e| match self.macros.get(e.trim()) {
+                Some(macro_extensions) => macro_extensions.as_str(),
+                None => e,
+            })
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excluded_extensions() {
+        let mut messages = Messages::new();
+        let mut extensions = Extensions::new();
+        extensions.set_excluded_extensions("iso, .vmdk,VIDEO".to_string(), &mut messages);
+        assert!(extensions.is_allowed("disk.img"));
+        assert!(!extensions.is_allowed("disk.iso"));
+        assert!(!extensions.is_allowed("machine.vmdk"));
+        assert!(!extensions.is_allowed("film.mkv"));
+
+        extensions.set_allowed_extensions("IMAGE,iso".to_string(), &mut messages);
+        assert!(extensions.is_allowed("photo.jpg"));
+        assert!(!extensions.is_allowed("disk.img"));
+        assert!(!extensions.is_allowed("disk.iso"));
+        assert!(messages.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_extension_macros() {
+        let mut messages = Messages::new();
+        let mut extension_macros = ExtensionMacros::new();
+        extension_macros.add_macros("# Raw photos\nRAW = cr2,nef,arw\n\nVM=iso,vmdk\nbroken line\n", Path::new("extension_macros.txt"), &mut messages);
+        assert_eq!(extension_macros.get_macros().len(), 2);
+        assert_eq!(messages.warnings.len(), 1);
+
+        assert_eq!(extension_macros.expand("RAW, txt,VM"), "cr2,nef,arw, txt,iso,vmdk");
+        assert_eq!(extension_macros.expand("RAWS,IMAGE"), "RAWS,IMAGE");
+
+        let mut extensions = Extensions::new();
+        extensions.set_allowed_extensions(extension_macros.expand("RAW,IMAGE"), &mut messages);
+        assert!(extensions.is_allowed("photo.nef"));
+        assert!(extensions.is_allowed("photo.png"));
+        assert!(!extensions.is_allowed("photo.txt"));
+
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let macros_file = dir.path().join(EXTENSION_MACROS_FILE_NAME);
+        assert_eq!(ExtensionMacros::load_from_file(&macros_file, &mut messages), ExtensionMacros::new());
+        fs::write(&macros_file, "RAW=cr2,nef,arw").unwrap();
+        assert_eq!(ExtensionMacros::load_from_file(&macros_file, &mut messages).expand("RAW"), "cr2,nef,arw");
     }
 }
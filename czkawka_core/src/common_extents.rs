@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+
+/// Continuous part of file data placed on disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Extent {
+    pub logical: u64,
+    pub physical: u64,
+    pub length: u64,
+}
+
+/// Makes `dst` share data with `src` on copy-on-write file systems(e.g. Btrfs or XFS), unlike hard links both files can be still edited separately
+/// Content of files is compared again by kernel, so file which differs from original is never changed
+pub fn dedupe_file(src: &Path, dst: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::dedupe_file(src, dst)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (src, dst);
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Deduplication is supported only on Linux"))
+    }
+}
+
+/// Physical extents of file, available only when all of them are shared with other files e.g. after reflink copy or deduplication
+/// Hard links use the same extents, but they are not marked as shared, so they are never returned here
+pub fn get_shared_extents(path: &Path) -> Option<Vec<Extent>> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::get_shared_extents(path).map(|extents| merge_extents(&extents))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Number of copies of data which really take space on disk, files which share all their extents are counted once
+pub fn count_data_copies<'a>(paths: impl Iterator<Item = &'a Path>) -> usize {
+    let mut seen_extents: HashSet<Vec<Extent>> = HashSet::new();
+    paths
+        .filter(|path| match get_shared_extents(path) {
+            Some(extents) => seen_extents.insert(extents),
+            None => true,
+        })
+        .count()
+}
+
+/// File systems may split same data in different places, e.g. deduplicated file contains many small references to one big extent of original file
+fn merge_extents(extents: &[Extent]) -> Vec<Extent> {
+    let mut merged: Vec<Extent> = Vec::with_capacity(extents.len());
+    for extent in extents {
+        match merged.last_mut() {
+            Some(last) if last.logical + last.length == extent.logical && last.physical + last.length == extent.physical => last.length += extent.length,
+            _ => merged.push(*extent),
+        }
+    }
+    merged
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::Extent;
+    use std::cmp::min;
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::io::{Error, ErrorKind};
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    /// _IOWR(0x94, 54, struct file_dedupe_range), not available in libc
+    const FIDEDUPERANGE: u64 = 0xC018_9436;
+    const FILE_DEDUPE_RANGE_DIFFERS: i32 = 1;
+    /// Btrfs doesn't accept bigger ranges in one call
+    const MAX_DEDUPE_LENGTH: u64 = 16 * 1024 * 1024;
+
+    #[repr(C)]
+    struct FileDedupeRangeInfo {
+        dest_fd: i64,
+        dest_offset: u64,
+        bytes_deduped: u64,
+        status: i32,
+        reserved: u32,
+    }
+
+    /// Kernel struct ends with array of destinations, here only one is used
+    #[repr(C)]
+    struct FileDedupeRange {
+        src_offset: u64,
+        src_length: u64,
+        dest_count: u16,
+        reserved1: u16,
+        reserved2: u32,
+        info: [FileDedupeRangeInfo; 1],
+    }
+
+    pub fn dedupe_file(src: &Path, dst: &Path) -> io::Result<()> {
+        let src_file = File::open(src)?;
+        let dst_file = OpenOptions::new().write(true).open(dst)?;
+        let size = src_file.metadata()?.len();
+        if size != dst_file.metadata()?.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "Files have different sizes"));
+        }
+
+        let mut offset = 0;
+        while offset < size {
+            let mut range = FileDedupeRange {
+                src_offset: offset,
+                src_length: min(size - offset, MAX_DEDUPE_LENGTH),
+                dest_count: 1,
+                reserved1: 0,
+                reserved2: 0,
+                info: [FileDedupeRangeInfo {
+                    dest_fd: dst_file.as_raw_fd() as i64,
+                    dest_offset: offset,
+                    bytes_deduped: 0,
+                    status: 0,
+                    reserved: 0,
+                }],
+            };
+            // Struct has layout expected by kernel and lives until the end of call
+            if unsafe { libc::ioctl(src_file.as_raw_fd(), FIDEDUPERANGE as _, &mut range) } != 0 {
+                return Err(map_unsupported(Error::last_os_error()));
+            }
+
+            let info = &range.info[0];
+            match info.status {
+                FILE_DEDUPE_RANGE_DIFFERS => return Err(Error::new(ErrorKind::InvalidData, "Content of files differs")),
+                status if status < 0 => return Err(map_unsupported(Error::from_raw_os_error(-status))),
+                _ if info.bytes_deduped == 0 => return Err(Error::other("No data was deduplicated")),
+                _ => offset += info.bytes_deduped,
+            }
+        }
+        Ok(())
+    }
+
+    /// _IOWR('f', 11, struct fiemap)
+    const FS_IOC_FIEMAP: u64 = 0xC020_660B;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+    /// Unknown, delayed allocation, inline, tail and unwritten data don't have real place on disk
+    const FIEMAP_EXTENT_NOT_PLACED: u32 = 0x2 | 0x4 | 0x200 | 0x400 | 0x800;
+    /// Number of extents read in one call
+    const FIEMAP_EXTENT_COUNT: usize = 64;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct FiemapExtent {
+        fe_logical: u64,
+        fe_physical: u64,
+        fe_length: u64,
+        fe_reserved64: [u64; 2],
+        fe_flags: u32,
+        fe_reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    struct Fiemap {
+        fm_start: u64,
+        fm_length: u64,
+        fm_flags: u32,
+        fm_mapped_extents: u32,
+        fm_extent_count: u32,
+        fm_reserved: u32,
+        fm_extents: [FiemapExtent; FIEMAP_EXTENT_COUNT],
+    }
+
+    /// `None` is returned when extents cannot be read or some part of file is not shared(also inline, delayed or not yet written data)
+    pub fn get_shared_extents(path: &Path) -> Option<Vec<Extent>> {
+        let file = File::open(path).ok()?;
+        let mut extents = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut fiemap = Fiemap {
+                fm_start: start,
+                fm_length: u64::MAX - start,
+                fm_flags: 0,
+                fm_mapped_extents: 0,
+                fm_extent_count: FIEMAP_EXTENT_COUNT as u32,
+                fm_reserved: 0,
+                fm_extents: [FiemapExtent::default(); FIEMAP_EXTENT_COUNT],
+            };
+            // Struct has layout expected by kernel and contains place for declared number of extents
+            if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut fiemap) } != 0 {
+                return None;
+            }
+            if fiemap.fm_mapped_extents == 0 {
+                break;
+            }
+
+            for extent in &fiemap.fm_extents[..fiemap.fm_mapped_extents as usize] {
+                if extent.fe_flags & FIEMAP_EXTENT_SHARED == 0 || extent.fe_flags & FIEMAP_EXTENT_NOT_PLACED != 0 {
+                    return None;
+                }
+                extents.push(Extent {
+                    logical: extent.fe_logical,
+                    physical: extent.fe_physical,
+                    length: extent.fe_length,
+                });
+                if extent.fe_flags & FIEMAP_EXTENT_LAST != 0 {
+                    return Some(extents);
+                }
+            }
+            let last = &fiemap.fm_extents[fiemap.fm_mapped_extents as usize - 1];
+            start = last.fe_logical + last.fe_length;
+        }
+        if extents.is_empty() {
+            None
+        } else {
+            Some(extents)
+        }
+    }
+
+    /// File systems without shared extents return different errors, so all of them are reported in the same way
+    fn map_unsupported(e: Error) -> Error {
+        match e.raw_os_error() {
+            Some(libc::EOPNOTSUPP) | Some(libc::ENOTTY) | Some(libc::EINVAL) => Error::new(ErrorKind::Unsupported, e),
+            _ => e,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_dedupe_file() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (src, dst, other) = (dir.path().join("src"), dir.path().join("dst"), dir.path().join("other"));
+        fs::write(&src, "data")?;
+        fs::write(&dst, "data")?;
+        fs::write(&other, "date")?;
+
+        // Result depends on file system of temporary folder, but files must never lose their content
+        match dedupe_file(&src, &dst) {
+            Ok(()) => assert!(dedupe_file(&src, &other).is_err()),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::Unsupported),
+        }
+        assert_eq!(fs::read_to_string(&dst)?, "data");
+        assert_eq!(fs::read_to_string(&other)?, "date");
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_extents() {
+        let extent = |logical, physical, length| Extent { logical, physical, length };
+        assert_eq!(merge_extents(&[]), vec![]);
+        assert_eq!(merge_extents(&[extent(0, 4096, 4096), extent(4096, 8192, 4096), extent(8192, 65536, 4096)]), vec![extent(0, 4096, 8192), extent(8192, 65536, 4096)]);
+    }
+
+    #[test]
+    fn test_count_data_copies() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        fs::write(&first, "data")?;
+        fs::write(&second, "data")?;
+
+        // Separately written files never share data, also on copy-on-write file systems
+        assert_eq!(count_data_copies([first.as_path(), second.as_path()].iter().copied()), 2);
+        if dedupe_file(&first, &second).is_ok() {
+            assert_eq!(count_data_copies([first.as_path(), second.as_path()].iter().copied()), 1);
+        }
+        Ok(())
+    }
+}
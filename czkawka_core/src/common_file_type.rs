@@ -0,0 +1,159 @@
+use image::{DynamicImage, ImageResult};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Number of bytes from start of file which are enough to recognize all supported types
+const HEADER_SIZE: usize = 32;
+
+/// General type of file content
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FileType {
+    Image,
+    Audio,
+    Video,
+    Archive,
+}
+
+impl FileType {
+    pub const ALL: [FileType; 4] = [FileType::Image, FileType::Audio, FileType::Video, FileType::Archive];
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FileType::Image => "image",
+            FileType::Audio => "audio",
+            FileType::Video => "video",
+            FileType::Archive => "archive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for FileType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "image" => Ok(FileType::Image),
+            "audio" | "music" => Ok(FileType::Audio),
+            "video" => Ok(FileType::Video),
+            "archive" => Ok(FileType::Archive),
+            _ => Err(format!("Couldn't parse the file type \"{}\" (allowed: image, audio, video, archive)", s.trim())),
+        }
+    }
+}
+
+/// Type of file recognized by its content, extension is the most popular one of such files, so it can be compared with lists of extensions supported by tools
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DetectedType {
+    pub extension: &'static str,
+    pub file_type: FileType,
+}
+
+/// Every part of signature must be found at its offset
+struct Signature {
+    parts: &'static [(usize, &'static [u8])],
+    extension: &'static str,
+    file_type: FileType,
+}
+
+const fn signature(parts: &'static [(usize, &'static [u8])], extension: &'static str, file_type: FileType) -> Signature {
+    Signature { parts, extension, file_type }
+}
+
+// More specific signatures must be placed before more general ones e.g. m4a before mp4
+const SIGNATURES: [Signature; 30] = [
+    signature(&[(0, b"\xFF\xD8\xFF")], "jpg", FileType::Image),
+    signature(&[(0, b"\x89PNG\r\n\x1A\n")], "png", FileType::Image),
+    signature(&[(0, b"GIF87a")], "gif", FileType::Image),
+    signature(&[(0, b"GIF89a")], "gif", FileType::Image),
+    signature(&[(0, b"II*\x00")], "tiff", FileType::Image),
+    signature(&[(0, b"MM\x00*")], "tiff", FileType::Image),
+    signature(&[(0, b"RIFF"), (8, b"WEBP")], "webp", FileType::Image),
+    signature(&[(4, b"ftypavif")], "avif", FileType::Image),
+    signature(&[(4, b"ftypheic")], "heic", FileType::Image),
+    signature(&[(0, b"farbfeld")], "ff", FileType::Image),
+    signature(&[(0, b"\x00\x00\x01\x00")], "ico", FileType::Image),
+    signature(&[(0, b"BM")], "bmp", FileType::Image),
+    signature(&[(0, b"ID3")], "mp3", FileType::Audio),
+    signature(&[(0, b"\xFF\xFB")], "mp3", FileType::Audio),
+    signature(&[(0, b"\xFF\xF3")], "mp3", FileType::Audio),
+    signature(&[(0, b"fLaC")], "flac", FileType::Audio),
+    signature(&[(0, b"OggS")], "ogg", FileType::Audio),
+    signature(&[(0, b"RIFF"), (8, b"WAVE")], "wav", FileType::Audio),
+    signature(&[(4, b"ftypM4A ")], "m4a", FileType::Audio),
+    signature(&[(4, b"ftypqt  ")], "mov", FileType::Video),
+    signature(&[(4, b"ftyp")], "mp4", FileType::Video),
+    signature(&[(0, b"\x1A\x45\xDF\xA3")], "mkv", FileType::Video),
+    signature(&[(0, b"RIFF"), (8, b"AVI ")], "avi", FileType::Video),
+    signature(&[(0, b"FLV\x01")], "flv", FileType::Video),
+    signature(&[(0, b"PK\x03\x04")], "zip", FileType::Archive),
+    signature(&[(0, b"7z\xBC\xAF\x27\x1C")], "7z", FileType::Archive),
+    signature(&[(0, b"Rar!\x1A\x07")], "rar", FileType::Archive),
+    signature(&[(0, b"\xFD7zXZ\x00")], "xz", FileType::Archive),
+    signature(&[(0, b"\x1F\x8B")], "gz", FileType::Archive),
+    signature(&[(0, b"BZh")], "bz2", FileType::Archive),
+];
+
+/// Recognizes type of file by its first bytes(magic numbers), so it works also for files with missing or wrong extension
+pub fn detect_type_from_bytes(header: &[u8]) -> Option<DetectedType> {
+    SIGNATURES
+        .iter()
+        .find(|signature| signature.parts.iter().all(|(offset, magic)| header.get(*offset..offset + magic.len()) == Some(*magic)))
+        .map(|signature| DetectedType {
+            extension: signature.extension,
+            file_type: signature.file_type,
+        })
+}
+
+/// Reads only start of file, `None` is returned also when file cannot be opened
+pub fn detect_type(path: &Path) -> Option<DetectedType> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    File::open(path).ok()?.take(HEADER_SIZE as u64).read_to_end(&mut header).ok()?;
+    detect_type_from_bytes(&header)
+}
+
+/// Unlike `image::open`, format is taken from content when it is recognized, so images with wrong extension can be also opened
+pub(crate) fn open_image(path: &Path) -> ImageResult<DynamicImage> {
+    image::io::Reader::open(path)?.with_guessed_format()?.decode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_type() {
+        let detected = |header: &[u8]| detect_type_from_bytes(header).map(|e| (e.extension, e.file_type));
+        assert_eq!(detected(b"\xFF\xD8\xFF\xE0\x00\x10JFIF"), Some(("jpg", FileType::Image)));
+        assert_eq!(detected(b"RIFF\x24\x00\x00\x00WEBPVP8 "), Some(("webp", FileType::Image)));
+        assert_eq!(detected(b"RIFF\x24\x00\x00\x00WAVEfmt "), Some(("wav", FileType::Audio)));
+        assert_eq!(detected(b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00"), Some(("m4a", FileType::Audio)));
+        assert_eq!(detected(b"\x00\x00\x00\x20ftypisom\x00\x00\x02\x00"), Some(("mp4", FileType::Video)));
+        assert_eq!(detected(b"PK\x03\x04\x14\x00"), Some(("zip", FileType::Archive)));
+        assert_eq!(detected(b"RIFF"), None);
+        assert_eq!(detected(b"plain text"), None);
+        assert_eq!(detected(b""), None);
+
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let camera_dump = dir.path().join("DSC0001.dat");
+        fs::write(&camera_dump, b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR").unwrap();
+        assert_eq!(detect_type(&camera_dump).map(|e| e.extension), Some("png"));
+        assert_eq!(detect_type(&dir.path().join("missing.png")), None);
+    }
+
+    #[test]
+    fn test_parse_file_type() {
+        assert_eq!(FileType::from_str(" Image"), Ok(FileType::Image));
+        assert_eq!(FileType::from_str("music"), Ok(FileType::Audio));
+        assert!(FileType::from_str("document").is_err());
+        for file_type in FileType::ALL.iter() {
+            assert_eq!(FileType::from_str(&file_type.to_string()), Ok(*file_type));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::common_items::glob_to_regex;
+use crate::common_messages::{MessageEntry, Operation};
+use regex::Regex;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Files with ignore rules which are read in every checked folder, rules from later files have bigger priority
+pub const IGNORE_FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".czkawkaignore"];
+
+#[derive(Clone)]
+struct IgnoreRule {
+    regex: Regex,
+    negated: bool,
+    only_folders: bool,
+}
+
+/// Rules loaded from ignore files, in format used by .gitignore
+#[derive(Clone, Default)]
+pub struct IgnorePatterns {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnorePatterns {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Loads rules from file, missing file is silently skipped
+    pub fn load_from_file(&mut self, file: &Path, warnings: &mut Vec<MessageEntry>) {
+        match fs::read_to_string(file) {
+            Ok(content) => self.add_rules(&content, file, warnings),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => warnings.push(MessageEntry::Io {
+                operation: Operation::ReadFile,
+                path: file.to_path_buf(),
+                kind: e.kind(),
+            }),
+        }
+    }
+
+    pub fn add_rules(&mut self, content: &str, file: &Path, warnings: &mut Vec<MessageEntry>) {
+        for line in content.lines() {
+            let mut pattern = line.trim_end();
+            if pattern.is_empty() || pattern.starts_with('#') {
+                continue;
+            }
+
+            let negated = pattern.starts_with('!');
+            // Escaped `!` and `#` at start are treated as normal characters
+            if negated || pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+                pattern = &pattern[1..];
+            }
+
+            let only_folders = pattern.ends_with('/');
+            if only_folders {
+                pattern = pattern.trim_end_matches('/');
+            }
+            if pattern.is_empty() {
+                continue;
+            }
+
+            // Pattern without slash may match file in any subfolder, other patterns are relative to folder with ignore file
+            let glob = if pattern.contains('/') { pattern.trim_start_matches('/').to_string() } else { format!("**/{}", pattern) };
+
+            let regex = match glob_to_regex(&glob, '/', false).map(|e| Regex::new(&e)) {
+                Some(Ok(t)) => t,
+                _ => {
+                    warnings.push(MessageEntry::InvalidPattern {
+                        path: file.to_path_buf(),
+                        pattern: line.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            self.rules.push(IgnoreRule { regex, negated, only_folders });
+        }
+    }
+
+    /// Returns `Some(true)` if path is ignored, `Some(false)` if it is included again by negated rule and `None` when no rule matches
+    fn matches(&self, relative_path: &str, is_folder: bool) -> Option<bool> {
+        self.rules.iter().rev().find(|rule| (is_folder || !rule.only_folders) && rule.regex.is_match(relative_path)).map(|rule| !rule.negated)
+    }
+}
+
+/// Ignore rules of folder together with rules inherited from parent folders
+pub struct IgnoreStack {
+    folder: PathBuf,
+    patterns: IgnorePatterns,
+    parent: Option<Arc<IgnoreStack>>,
+}
+
+impl IgnoreStack {
+    pub fn new(folder: PathBuf, patterns: IgnorePatterns, parent: Option<Arc<IgnoreStack>>) -> Self {
+        Self { folder, patterns, parent }
+    }
+
+    /// Checks rules from the deepest folder to root, first folder with matching rule decides
+    pub fn is_ignored(&self, path: &Path, is_folder: bool) -> bool {
+        let mut current = Some(self);
+        while let Some(stack) = current {
+            if let Ok(relative_path) = path.strip_prefix(&stack.folder) {
+                let relative_path = relative_path.to_string_lossy();
+                #[cfg(target_family = "windows")]
+                let relative_path = relative_path.replace('\\', "/");
+                if let Some(ignored) = stack.patterns.matches(&relative_path, is_folder) {
+                    return ignored;
+                }
+            }
+            current = stack.parent.as_deref();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+
+    fn patterns(content: &str) -> IgnorePatterns {
+        let mut patterns = IgnorePatterns::new();
+        let mut warnings = Vec::new();
+        patterns.add_rules(content, Path::new(".gitignore"), &mut warnings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        patterns
+    }
+
+    #[test]
+    fn test_ignore_patterns() {
+        let stack = IgnoreStack::new(PathBuf::from("/project"), patterns("# Comment\n*.o\n/target\nbuild/\ndocs/*.html\n!keep.o\n"), None);
+        assert!(stack.is_ignored(Path::new("/project/a.o"), false));
+        assert!(stack.is_ignored(Path::new("/project/src/b.o"), false));
+        assert!(!stack.is_ignored(Path::new("/project/src/keep.o"), false));
+        assert!(stack.is_ignored(Path::new("/project/target"), true));
+        assert!(!stack.is_ignored(Path::new("/project/src/target"), true));
+        assert!(stack.is_ignored(Path::new("/project/src/build"), true));
+        assert!(!stack.is_ignored(Path::new("/project/src/build"), false));
+        assert!(stack.is_ignored(Path::new("/project/docs/index.html"), false));
+        assert!(!stack.is_ignored(Path::new("/project/docs/api/index.html"), false));
+        assert!(!stack.is_ignored(Path::new("/other/a.o"), false));
+    }
+
+    #[test]
+    fn test_ignore_hierarchy() {
+        let root = Arc::new(IgnoreStack::new(PathBuf::from("/project"), patterns("*.log\n"), None));
+        let child = IgnoreStack::new(PathBuf::from("/project/logs"), patterns("!important.log\n"), Some(root));
+        assert!(child.is_ignored(Path::new("/project/logs/other.log"), false));
+        assert!(!child.is_ignored(Path::new("/project/logs/important.log"), false));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let mut warnings = Vec::new();
+        IgnorePatterns::new().add_rules("[abc\n", Path::new(".gitignore"), &mut warnings);
+        assert_eq!(warnings.len(), 1);
+    }
+}
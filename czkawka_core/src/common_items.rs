@@ -1,19 +1,34 @@
 use crate::common::Common;
-use crate::common_messages::Messages;
-use std::path::Path;
+use crate::common_ignore_files::IgnorePatterns;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use regex::RegexSet;
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 use std::time::SystemTime;
 
+/// Prefix of excluded item which should be treated as regular expression instead glob
+const REGEX_PREFIX: &str = "regex:";
+
 #[derive(Default)]
 pub struct ExcludedItems {
     pub items: Vec<String>,
+    compiled_items: Option<RegexSet>,
+    negated_items: Vec<bool>,
+    pub exclude_from: IgnorePatterns,
 }
 
 impl ExcludedItems {
     pub fn new() -> Self {
         Default::default()
     }
-    /// Setting excluded items which needs to contains * wildcard
-    /// Are a lot of slower than absolute path, so it should be used to heavy
+    /// Setting excluded items, each item is glob which is checked against full path
+    /// - `*` matches any characters except path separator, `**` matches any number of folders
+    /// - `?` matches single character, `[abc]`, `[a-z]` and `[!abc]` matches character from(or not from) set
+    /// - `{a,b}` matches one of alternatives
+    /// - `!` at start of item includes again paths excluded by previous items, the last matching item wins
+    /// - `regex:` at start of item means that rest of item is regular expression which must match whole path
+    ///
+    /// Old items which contains only `*` wildcard are still supported and in them `*` also matches path separator
     pub fn set_excluded_items(&mut self, excluded_items: Vec<String>, text_messages: &mut Messages) {
         let start_time: SystemTime = SystemTime::now();
 
@@ -23,46 +38,288 @@ impl ExcludedItems {
 
         let expressions: Vec<String> = excluded_items;
         let mut checked_expressions: Vec<String> = Vec::new();
+        let mut regexes: Vec<String> = Vec::new();
+        let mut negated_items: Vec<bool> = Vec::new();
 
         for expression in expressions {
             let expression: String = expression.trim().to_string();
 
             #[cfg(target_family = "windows")]
-            let expression = expression.replace("/", "\\");
+            let expression = if expression.starts_with(REGEX_PREFIX) { expression } else { expression.replace("/", "\\") };
 
             if expression.is_empty() {
                 continue;
             }
-            if expression == "DEFAULT" {
+            let default_expressions: Vec<&str> = if expression == "DEFAULT" {
                 if cfg!(target_family = "unix") {
-                    checked_expressions.push("*/.git/*,*/node_modules/*,*/lost+found/*,*/Trash/*,*/.Trash-*/*,*/snap/*,/home/*/.cache/*".to_string());
+                    vec!["**/.git/**", "**/node_modules/**", "**/lost+found/**", "**/Trash/**", "**/.Trash-*/**", "**/snap/**", "/home/*/.cache/**"]
+                } else if cfg!(target_family = "windows") {
+                    vec!["**\\.git\\**", "**\\node_modules\\**", "**\\lost+found\\**", "*:\\windows\\**"]
+                } else {
+                    Vec::new()
                 }
-                if cfg!(target_family = "windows") {
-                    checked_expressions.push("*\\.git\\*,*\\node_modules\\*,*\\lost+found\\*,*:\\windows\\*".to_string());
+            } else {
+                vec![expression.as_str()]
+            };
+
+            for expression in default_expressions {
+                let (negated, pattern) = match expression.strip_prefix('!') {
+                    Some(t) => (true, t),
+                    None => (false, expression),
+                };
+                let regex = match pattern.strip_prefix(REGEX_PREFIX) {
+                    Some(t) => format!("^(?:{})$", t),
+                    None => match glob_to_regex(pattern, MAIN_SEPARATOR, true) {
+                        Some(t) => t,
+                        None => {
+                            text_messages.warnings.push("Excluded Items Warning: Expression has unclosed [ or {, ignoring ".to_string() + expression);
+                            continue;
+                        }
+                    },
+                };
+                // Checking every regex separately to find which one is invalid
+                if let Err(e) = RegexSet::new([&regex]) {
+                    text_messages.warnings.push(format!("Excluded Items Warning: Failed to compile expression {}, reason {}, ignoring", expression, e));
+                    continue;
                 }
-                continue;
-            }
-            if !expression.contains('*') {
-                text_messages.warnings.push("Excluded Items Warning: Wildcard * is required in expression, ignoring ".to_string() + expression.as_str());
-                continue;
-            }
 
-            checked_expressions.push(expression);
+                checked_expressions.push(expression.to_string());
+                regexes.push(regex);
+                negated_items.push(negated);
+            }
         }
+
+        self.compiled_items = match RegexSet::new(&regexes) {
+            Ok(t) if !regexes.is_empty() => Some(t),
+            _ => None,
+        };
         self.items = checked_expressions;
+        self.negated_items = negated_items;
         Common::print_time(start_time, SystemTime::now(), "set_excluded_items".to_string());
     }
 
+    /// Loads rules in .gitignore format from files, rules are applied relatively to each included directory
+    pub fn set_exclude_from(&mut self, exclude_from: Vec<PathBuf>, text_messages: &mut Messages) {
+        let mut exclude_from_patterns = IgnorePatterns::new();
+        let mut warnings: Vec<MessageEntry> = Vec::new();
+        for file in exclude_from {
+            match fs::read_to_string(&file) {
+                Ok(content) => exclude_from_patterns.add_rules(&content, &file, &mut warnings),
+                Err(e) => warnings.push(MessageEntry::Io {
+                    operation: Operation::ReadFile,
+                    path: file,
+                    kind: e.kind(),
+                }),
+            }
+        }
+        text_messages.extend_warnings(warnings);
+        self.exclude_from = exclude_from_patterns;
+    }
+
     /// Checks whether a specified path is excluded from searching
     pub fn is_excluded(&self, path: impl AsRef<Path>) -> bool {
+        let compiled_items = match &self.compiled_items {
+            Some(t) => t,
+            None => return false,
+        };
+
         #[cfg(target_family = "windows")]
         let path = Common::normalize_windows_path(path);
 
-        for expression in &self.items {
-            if Common::regex_check(expression, &path) {
-                return true;
+        // Last matching item decides if path is excluded or not
+        match compiled_items.matches(&path.as_ref().to_string_lossy()).iter().next_back() {
+            Some(index) => !self.negated_items[index],
+            None => false,
+        }
+    }
+}
+
+/// Converts glob to anchored regular expression, returns `None` when glob is not valid
+/// When `allow_legacy` is set, glob which contains only `*` wildcards is treated like old excluded items where `*` also matches separator
+pub(crate) fn glob_to_regex(glob: &str, separator_char: char, allow_legacy: bool) -> Option<String> {
+    let separator = regex::escape(separator_char.encode_utf8(&mut [0; 4]));
+    let not_separator = format!("[^{}]", separator);
+
+    // Previous excluded items supported only `*` which matched also separators, so such items are still treated in same way
+    let legacy = allow_legacy && !glob.contains("**") && !glob.contains(&['?', '[', '{'][..]);
+
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::with_capacity(glob.len() * 2 + 2);
+    regex.push('^');
+
+    let mut brace_depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let after_separator = i == 0 || chars[i - 1] == separator_char;
+                i += 2;
+                // Skip redundant asterisks
+                while chars.get(i) == Some(&'*') {
+                    i += 1;
+                }
+                if after_separator && chars.get(i) == Some(&separator_char) {
+                    // `**/` matches zero or more folders
+                    regex.push_str(&format!("(?:.*{})?", separator));
+                    i += 1;
+                } else if after_separator && i == chars.len() && regex.ends_with(&separator) {
+                    // `/**` at the end matches also folder itself, so separator before is optional
+                    regex.truncate(regex.len() - separator.len());
+                    regex.push_str(&format!("(?:{}.*)?", separator));
+                } else {
+                    regex.push_str(".*");
+                }
+                continue;
             }
+            '*' => {
+                if legacy {
+                    regex.push_str(".*");
+                } else {
+                    regex.push_str(&not_separator);
+                    regex.push('*');
+                }
+            }
+            '?' => regex.push_str(&not_separator),
+            '[' => {
+                let end = chars[i + 1..].iter().skip(1).position(|e| *e == ']')? + i + 2;
+                let mut class = &chars[i + 1..end];
+                regex.push('[');
+                if let Some('!') | Some('^') = class.first() {
+                    regex.push('^');
+                    class = &class[1..];
+                }
+                push_class_characters(&mut regex, class);
+                regex.push(']');
+                i = end;
+            }
+            '{' => {
+                brace_depth += 1;
+                regex.push_str("(?:");
+            }
+            '}' if brace_depth > 0 => {
+                brace_depth -= 1;
+                regex.push(')');
+            }
+            ',' if brace_depth > 0 => regex.push('|'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
         }
-        false
+        i += 1;
+    }
+    if brace_depth != 0 {
+        return None;
+    }
+
+    regex.push('$');
+    Some(regex)
+}
+
+/// Characters inside `[]` are matched literally, only `-` between two other characters creates range
+/// Regex treats e.g. `&&`, `--` and `~~` as class operations, so such characters are escaped
+fn push_class_characters(regex: &mut String, class: &[char]) {
+    let push_character = |regex: &mut String, c: char| {
+        if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~' | '-') {
+            regex.push('\\');
+        }
+        regex.push(c);
+    };
+
+    let mut j = 0;
+    while j < class.len() {
+        if j + 2 < class.len() && class[j + 1] == '-' && class[j] != '-' && class[j + 2] != '-' {
+            push_character(regex, class[j]);
+            regex.push('-');
+            push_character(regex, class[j + 2]);
+            j += 3;
+        } else {
+            push_character(regex, class[j]);
+            j += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod test {
+    use super::*;
+
+    fn excluded_items(items: &[&str]) -> ExcludedItems {
+        let mut excluded_items = ExcludedItems::new();
+        let mut text_messages = Messages::new();
+        excluded_items.set_excluded_items(items.iter().map(|e| e.to_string()).collect(), &mut text_messages);
+        assert!(text_messages.warnings.is_empty(), "{:?}", text_messages.warnings);
+        excluded_items
+    }
+
+    #[test]
+    fn test_legacy_wildcard() {
+        let ei = excluded_items(&["*/.git/*", "/home/*/.cache"]);
+        assert!(ei.is_excluded("/home/rafal/project/.git/config"));
+        assert!(ei.is_excluded("/home/rafal/.cache"));
+        assert!(ei.is_excluded("/home/rafal/other/.cache"));
+        assert!(!ei.is_excluded("/home/rafal/.gitignore"));
+        assert!(!ei.is_excluded("/home/rafal/.cache/file"));
+    }
+
+    #[test]
+    fn test_glob() {
+        let ei = excluded_items(&["/home/*/tmp/**", "**/*.bak", "/data/**/cache/**", "/photos/img?.[jp]{pg,ng}", "/files/[!a-c]*"]);
+        assert!(ei.is_excluded("/home/rafal/tmp"));
+        assert!(ei.is_excluded("/home/rafal/tmp/a/b"));
+        assert!(!ei.is_excluded("/home/rafal/sub/tmp"));
+        assert!(ei.is_excluded("/a/b/c/file.bak"));
+        assert!(ei.is_excluded("/data/cache"));
+        assert!(ei.is_excluded("/data/a/b/cache"));
+        assert!(ei.is_excluded("/data/a/cache/b/c"));
+        assert!(!ei.is_excluded("/data/a/cachefile"));
+        assert!(ei.is_excluded("/photos/img1.jpg"));
+        assert!(ei.is_excluded("/photos/img2.png"));
+        assert!(!ei.is_excluded("/photos/img10.jpg"));
+        assert!(!ei.is_excluded("/photos/img1.gif"));
+        assert!(ei.is_excluded("/files/document"));
+        assert!(!ei.is_excluded("/files/archive"));
+    }
+
+    #[test]
+    fn test_class_operators_are_literal() {
+        let ei = excluded_items(&["/a/[a&&b]", "/b/[a--b]", "/c/[~~x]", "/d/[a-c-e]", "/e/[!-x]"]);
+        assert!(ei.is_excluded("/a/a"));
+        assert!(ei.is_excluded("/a/&"));
+        assert!(ei.is_excluded("/a/b"));
+        assert!(!ei.is_excluded("/a/c"));
+        assert!(ei.is_excluded("/b/a"));
+        assert!(ei.is_excluded("/b/-"));
+        assert!(ei.is_excluded("/b/b"));
+        assert!(!ei.is_excluded("/b/,"));
+        assert!(ei.is_excluded("/c/~"));
+        assert!(ei.is_excluded("/c/x"));
+        assert!(!ei.is_excluded("/c/y"));
+        assert!(ei.is_excluded("/d/b"));
+        assert!(ei.is_excluded("/d/-"));
+        assert!(ei.is_excluded("/d/e"));
+        assert!(!ei.is_excluded("/d/d"));
+        assert!(ei.is_excluded("/e/a"));
+        assert!(!ei.is_excluded("/e/-"));
+        assert!(!ei.is_excluded("/e/x"));
+    }
+
+    #[test]
+    fn test_negation_and_regex() {
+        let ei = excluded_items(&["**/build", "!**/src/build", "regex:.*/[0-9]+\\.log"]);
+        assert!(ei.is_excluded("/project/build"));
+        assert!(ei.is_excluded("/project/other/build"));
+        assert!(!ei.is_excluded("/project/src/build"));
+        assert!(ei.is_excluded("/var/log/2021.log"));
+        assert!(!ei.is_excluded("/var/log/a2021.log"));
+    }
+
+    #[test]
+    fn test_invalid_items() {
+        let mut ei = ExcludedItems::new();
+        let mut text_messages = Messages::new();
+        ei.set_excluded_items(vec!["/home/[abc".to_string(), "/home/{a,b".to_string(), "regex:(".to_string(), "DEFAULT".to_string()], &mut text_messages);
+        assert_eq!(text_messages.warnings.len(), 3);
+        assert!(ei.is_excluded("/home/rafal/project/.git"));
+        assert!(ei.is_excluded("/home/rafal/.cache/a"));
     }
 }
@@ -1,14 +1,227 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Operation on file system which failed
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Operation {
+    OpenFolder,
+    ReadFolderEntry,
+    ReadMetadata,
+    ReadModificationDate,
+    ReadFile,
+    CalculateHash,
+    CreateFile,
+    WriteFile,
+    RemoveFile,
+    RemoveFolder,
+    MoveToTrash,
+    MoveFile,
+    CreateHardLink,
+    CreateSymlink,
+    Dedupe,
+    ReadCache,
+}
+
+/// Kind of directory set by user
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum DirectoryKind {
+    Included,
+    Excluded,
+    Reference,
+}
+
+/// Reason why directory set by user is ignored
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum DirectoryProblem {
+    Wildcard,
+    Relative,
+    NotExists,
+    NotDirectory,
+}
+
+/// Warning or error connected with specific path, so it can be checked without parsing text.
+/// Display gives the same text as is shown to user
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessageEntry {
+    /// Operation on path failed with io error
+    Io { operation: Operation, path: PathBuf, kind: io::ErrorKind },
+    /// File has modification date before Unix Epoch, so 0 is used instead
+    ModifiedBeforeUnixEpoch { path: PathBuf },
+    /// Symlink is not followed, because it points to its parent folder or is an infinite loop
+    SymlinkLoop { path: PathBuf, to_parent: bool },
+    /// Pattern from file with ignore rules cannot be used
+    InvalidPattern { path: PathBuf, pattern: String },
+    /// Line from cache file contains invalid value, so it is skipped
+    InvalidCacheLine { path: PathBuf, line_number: usize, line: String, value: &'static str },
+    /// File from imported results was removed or modified after saving results, so it is not changed
+    ChangedSinceScan { path: PathBuf, missing: bool },
+    /// File from imported results is not placed inside scanned directories, so it is not changed
+    OutsideScanRoots { path: PathBuf },
+    /// File from imported results has no saved size or modification date, so it cannot be checked and is not changed
+    MissingSavedInfo { path: PathBuf },
+    /// All other files from group of imported results are removed, so this one is kept
+    LastFileInGroup { path: PathBuf },
+    /// File in quarantine was modified after moving it there, so it is not restored
+    ChangedInQuarantine { path: PathBuf },
+    /// Other file already uses original path of file from quarantine, so it is not restored
+    RestoreTargetExists { path: PathBuf },
+    /// Duplicate is not replaced with symlink, because original file is inside other included directory
+    SymlinkAcrossRoots { path: PathBuf },
+    /// File is inside reference directory, so it is never removed or replaced
+    InReferenceDirectory { path: PathBuf },
+    /// Directory set by user cannot be used, so it is ignored
+    InvalidDirectory { path: PathBuf, kind: DirectoryKind, problem: DirectoryProblem },
+    /// Results file or journal cannot be parsed, line is not available when whole file is parsed at once
+    ParseFailed { path: PathBuf, line_number: Option<usize>, reason: String },
+    /// Results in this format cannot be loaded back or saved by JSON export
+    UnsupportedResultsFormat { path: PathBuf, saving: bool },
+    /// Results file doesn't contain scanned directories, so no file from it can be changed
+    MissingScanRoots { path: PathBuf },
+    /// Action chosen by user for file in results is not known
+    UnknownAction { path: PathBuf, action: String },
+}
+
+impl MessageEntry {
+    pub fn path(&self) -> &Path {
+        match self {
+            MessageEntry::Io { path, .. }
+            | MessageEntry::ModifiedBeforeUnixEpoch { path }
+            | MessageEntry::SymlinkLoop { path, .. }
+            | MessageEntry::InvalidPattern { path, .. }
+            | MessageEntry::InvalidCacheLine { path, .. }
+            | MessageEntry::ChangedSinceScan { path, .. }
+            | MessageEntry::OutsideScanRoots { path }
+            | MessageEntry::MissingSavedInfo { path }
+            | MessageEntry::LastFileInGroup { path }
+            | MessageEntry::ChangedInQuarantine { path }
+            | MessageEntry::RestoreTargetExists { path }
+            | MessageEntry::SymlinkAcrossRoots { path }
+            | MessageEntry::InReferenceDirectory { path }
+            | MessageEntry::InvalidDirectory { path, .. }
+            | MessageEntry::ParseFailed { path, .. }
+            | MessageEntry::UnsupportedResultsFormat { path, .. }
+            | MessageEntry::MissingScanRoots { path }
+            | MessageEntry::UnknownAction { path, .. } => path,
+        }
+    }
+
+    /// Kind of io error, available only when some operation on file system failed
+    pub fn io_error_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            MessageEntry::Io { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    pub fn operation(&self) -> Option<Operation> {
+        match self {
+            MessageEntry::Io { operation, .. } => Some(*operation),
+            MessageEntry::InvalidCacheLine { .. } => Some(Operation::ReadCache),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MessageEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageEntry::Io { operation, path, kind } => {
+                let path = path.display();
+                match operation {
+                    Operation::OpenFolder => write!(f, "Cannot open dir {}", path),
+                    Operation::ReadFolderEntry => write!(f, "Cannot read entry in dir {}", path),
+                    Operation::ReadMetadata => write!(f, "Cannot read metadata in dir {}", path),
+                    Operation::ReadModificationDate => write!(f, "Unable to get modification date from file {}", path),
+                    Operation::ReadFile => write!(f, "Cannot read file {}", path),
+                    Operation::CalculateHash => write!(f, "Error happened when checking hash of file {}", path),
+                    Operation::CreateFile => write!(f, "Failed to create file {}", path),
+                    Operation::WriteFile => write!(f, "Failed to save file {} ({})", path, kind),
+                    Operation::RemoveFile => write!(f, "Failed to remove {} ({})", path, kind),
+                    Operation::RemoveFolder => write!(f, "Failed to remove folder {}", path),
+                    Operation::MoveToTrash => write!(f, "Failed to move {} to trash ({})", path, kind),
+                    Operation::MoveFile => write!(f, "Failed to move {} ({})", path, kind),
+                    Operation::CreateHardLink => write!(f, "Failed to replace {} with hard link ({})", path, kind),
+                    Operation::CreateSymlink if *kind == io::ErrorKind::InvalidData => write!(f, "Failed to replace {} with symlink, because it is no longer identical to original file", path),
+                    Operation::CreateSymlink => write!(f, "Failed to replace {} with symlink ({})", path, kind),
+                    Operation::Dedupe if *kind == io::ErrorKind::Unsupported => write!(f, "Failed to deduplicate {}, its file system doesn't support sharing data between files", path),
+                    Operation::Dedupe => write!(f, "Failed to deduplicate {} ({})", path, kind),
+                    Operation::ReadCache => write!(f, "Failed to load line from cache file {}", path),
+                }
+            }
+            MessageEntry::ModifiedBeforeUnixEpoch { path } => write!(f, "File {} seems to be modified before Unix Epoch.", path.display()),
+            MessageEntry::SymlinkLoop { path, to_parent: true } => write!(f, "Symlink {} points to its parent folder, skipping", path.display()),
+            MessageEntry::SymlinkLoop { path, to_parent: false } => write!(f, "Symlink {} is an infinite loop, skipping", path.display()),
+            MessageEntry::InvalidPattern { path, pattern } => write!(f, "Ignore file {} contains invalid pattern {}, ignoring", path.display(), pattern),
+            MessageEntry::InvalidCacheLine { path, line_number, line, value } => write!(f, "Found invalid {} in line {} - ({}) in cache file {}", value, line_number, line, path.display()),
+            MessageEntry::ChangedSinceScan { path, missing: true } => write!(f, "File {} no longer exists, skipping", path.display()),
+            MessageEntry::ChangedSinceScan { path, missing: false } => write!(f, "File {} was changed after saving results, skipping", path.display()),
+            MessageEntry::OutsideScanRoots { path } => write!(f, "File {} is not inside scanned directories, skipping", path.display()),
+            MessageEntry::MissingSavedInfo { path } => write!(f, "File {} has no saved size or modification date, so it is not changed(use JSON results instead)", path.display()),
+            MessageEntry::LastFileInGroup { path } => write!(f, "File {} is last file from its group which would be left, so it is kept", path.display()),
+            MessageEntry::ChangedInQuarantine { path } => write!(f, "File {} was changed in quarantine, skipping", path.display()),
+            MessageEntry::RestoreTargetExists { path } => write!(f, "File {} already exists, so it is not restored from quarantine", path.display()),
+            MessageEntry::SymlinkAcrossRoots { path } => write!(f, "File {} is not replaced with symlink, because original file is inside other included directory", path.display()),
+            MessageEntry::InReferenceDirectory { path } => write!(f, "File {} is inside reference directory, so it is not changed", path.display()),
+            MessageEntry::InvalidDirectory { path, kind, problem } => {
+                let kind = match kind {
+                    DirectoryKind::Included => "Included",
+                    DirectoryKind::Excluded => "Excluded",
+                    DirectoryKind::Reference => "Reference",
+                };
+                let problem = match problem {
+                    DirectoryProblem::Wildcard => "Wildcards in path are not supported",
+                    DirectoryProblem::Relative => "Relative path are not supported",
+                    DirectoryProblem::NotExists => "Provided folder path must exits",
+                    DirectoryProblem::NotDirectory => "Provided path must point at the directory",
+                };
+                write!(f, "{} Directory Warning: {}, ignoring {}", kind, problem, path.display())
+            }
+            MessageEntry::ParseFailed { path, line_number: Some(line_number), reason } => write!(f, "Failed to parse line {} of file {}, reason {}", line_number, path.display(), reason),
+            MessageEntry::ParseFailed { path, line_number: None, reason } => write!(f, "Failed to parse file {}, reason {}", path.display(), reason),
+            MessageEntry::UnsupportedResultsFormat { path, saving: false } => write!(
+                f,
+                "Loading results from file {} is not supported, only JSON(.json) and JSON Lines(.jsonl) results contain size and modification date needed to safely change files",
+                path.display()
+            ),
+            MessageEntry::UnsupportedResultsFormat { path, saving: true } => write!(f, "Saving results to file {} is not supported by JSON export, use .json or .jsonl extension", path.display()),
+            MessageEntry::MissingScanRoots { path } => write!(f, "Results file {} doesn't contain scanned directories, so no file will be changed", path.display()),
+            MessageEntry::UnknownAction { path, action } => write!(f, "Unknown action {} for file {}, ignoring", action, path.display()),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Messages {
     pub messages: Vec<String>,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
+    /// Warnings and errors connected with some path are also saved here.
+    /// Messages without path, like invalid extensions or excluded items, exist only as text, so indexes of both lists may differ
+    pub typed_warnings: Vec<MessageEntry>,
+    pub typed_errors: Vec<MessageEntry>,
 }
 
 impl Messages {
     pub fn new() -> Self {
         Default::default()
     }
+
+    pub fn add_warning(&mut self, entry: MessageEntry) {
+        self.warnings.push(entry.to_string());
+        self.typed_warnings.push(entry);
+    }
+
+    pub fn add_error(&mut self, entry: MessageEntry) {
+        self.errors.push(entry.to_string());
+        self.typed_errors.push(entry);
+    }
+
+    pub fn extend_warnings(&mut self, entries: Vec<MessageEntry>) {
+        self.warnings.extend(entries.iter().map(|e| e.to_string()));
+        self.typed_warnings.extend(entries);
+    }
+
     pub fn print_messages(&self) {
         if !self.messages.is_empty() {
             println!("-------------------------------MESSAGES--------------------------------");
@@ -37,3 +250,32 @@ impl Messages {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_messages() {
+        let mut messages = Messages::new();
+        messages.add_warning(MessageEntry::Io {
+            operation: Operation::OpenFolder,
+            path: PathBuf::from("/home/rafal"),
+            kind: io::ErrorKind::PermissionDenied,
+        });
+        messages.add_error(MessageEntry::ModifiedBeforeUnixEpoch { path: PathBuf::from("/home/rafal/a") });
+        assert_eq!(messages.warnings, vec!["Cannot open dir /home/rafal".to_string()]);
+        assert_eq!(messages.typed_warnings[0].io_error_kind(), Some(io::ErrorKind::PermissionDenied));
+        assert_eq!(messages.typed_warnings[0].operation(), Some(Operation::OpenFolder));
+        assert_eq!(messages.typed_errors[0].path(), Path::new("/home/rafal/a"));
+        assert_eq!(messages.typed_errors[0].io_error_kind(), None);
+
+        messages.add_warning(MessageEntry::InvalidDirectory {
+            path: PathBuf::from("home"),
+            kind: DirectoryKind::Included,
+            problem: DirectoryProblem::Relative,
+        });
+        assert_eq!(messages.warnings[1], "Included Directory Warning: Relative path are not supported, ignoring home");
+        assert_eq!(messages.typed_warnings[1].path(), Path::new("home"));
+    }
+}
@@ -0,0 +1,275 @@
+use futures::channel::mpsc::UnboundedSender;
+use humansize::{file_size_opts as options, FileSize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+
+const LOOP_DURATION: u32 = 200; //in ms
+
+/// Progress of current stage of search, it is sent periodically by all tools
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgressData {
+    /// Short description of what is done in current stage e.g. "Calculating full hash"
+    pub stage_name: &'static str,
+    pub current_stage: u8,
+    pub max_stage: u8,
+    pub items_checked: usize,
+    /// 0 when number of items is not known yet e.g. when collecting files
+    pub items_to_check: usize,
+    pub bytes_checked: u64,
+    /// 0 when number of bytes is not known or stage doesn't read content of files
+    pub bytes_to_check: u64,
+    /// Last file or folder which was checked
+    pub current_path: Option<PathBuf>,
+    /// Available only when size of stage is known and something was already checked
+    pub estimated_time_left: Option<Duration>,
+}
+
+impl ProgressData {
+    /// Part of current stage which is already done, `None` when size of stage is not known
+    pub fn stage_fraction(&self) -> Option<f64> {
+        if self.bytes_to_check != 0 {
+            Some((self.bytes_checked as f64 / self.bytes_to_check as f64).min(1.0))
+        } else if self.items_to_check != 0 {
+            Some((self.items_checked as f64 / self.items_to_check as f64).min(1.0))
+        } else {
+            None
+        }
+    }
+
+    /// Part of whole search which is already done, stages are treated as equally long
+    pub fn all_stages_fraction(&self) -> f64 {
+        (self.current_stage as f64 + self.stage_fraction().unwrap_or(0.0)) / (self.max_stage as f64 + 1.0)
+    }
+}
+
+/// One line summary e.g. "[2/3] Calculating partial hash: 120/500, 1 MiB/4 MiB, about 1m 05s left"
+impl fmt::Display for ProgressData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}/{}] {}: {}", self.current_stage + 1, self.max_stage + 1, self.stage_name, self.items_checked)?;
+        if self.items_to_check != 0 {
+            write!(f, "/{}", self.items_to_check)?;
+        }
+        if self.bytes_checked != 0 || self.bytes_to_check != 0 {
+            write!(f, ", {}", self.bytes_checked.file_size(options::BINARY).unwrap())?;
+            if self.bytes_to_check != 0 {
+                write!(f, "/{}", self.bytes_to_check.file_size(options::BINARY).unwrap())?;
+            }
+        }
+        if let Some(estimated_time_left) = self.estimated_time_left {
+            write!(f, ", about {} left", format_duration(estimated_time_left))?;
+        }
+        Ok(())
+    }
+}
+
+/// Counters updated by tool when checking items, they are read by progress thread
+#[derive(Default)]
+pub struct ProgressCounters {
+    items_checked: AtomicUsize,
+    bytes_checked: AtomicU64,
+    current_path: Mutex<Option<PathBuf>>,
+}
+
+impl ProgressCounters {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add_items(&self, items: usize) {
+        self.items_checked.fetch_add(items, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes_checked.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Path is only informative, so it is not updated when other thread is setting it right now
+    pub fn set_current_path(&self, path: &Path) {
+        if let Ok(mut current_path) = self.current_path.try_lock() {
+            *current_path = Some(path.to_path_buf());
+        }
+    }
+
+    pub fn items_checked(&self) -> usize {
+        self.items_checked.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_checked(&self) -> u64 {
+        self.bytes_checked.load(Ordering::Relaxed)
+    }
+
+    fn current_path(&self) -> Option<PathBuf> {
+        self.current_path.lock().unwrap().clone()
+    }
+}
+
+/// Thread which sends progress of one stage to GUI/CLI every 200ms, it is stopped when dropped
+pub(crate) struct ProgressThread {
+    counters: Arc<ProgressCounters>,
+    thread_run: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ProgressThread {
+    /// `stage` contains stage info and size of stage, rest of fields is filled from counters
+    pub(crate) fn start(progress_sender: Option<&UnboundedSender<ProgressData>>, stage: ProgressData) -> Self {
+        let counters = Arc::new(ProgressCounters::new());
+        let thread_run = Arc::new(AtomicBool::new(true));
+
+        let handle = progress_sender.map(|progress_sender| {
+            let progress_send = progress_sender.clone();
+            let counters = counters.clone();
+            let thread_run = thread_run.clone();
+            let start_time = Instant::now();
+            thread::spawn(move || loop {
+                let mut progress = stage.clone();
+                progress.items_checked = counters.items_checked();
+                progress.bytes_checked = counters.bytes_checked();
+                progress.current_path = counters.current_path();
+                progress.estimated_time_left = if progress.bytes_to_check != 0 {
+                    estimate_time_left(start_time.elapsed(), progress.bytes_checked, progress.bytes_to_check)
+                } else {
+                    estimate_time_left(start_time.elapsed(), progress.items_checked as u64, progress.items_to_check as u64)
+                };
+                progress_send.unbounded_send(progress).unwrap();
+                if !thread_run.load(Ordering::Relaxed) {
+                    break;
+                }
+                sleep(Duration::from_millis(LOOP_DURATION as u64));
+            })
+        });
+
+        Self { counters, thread_run, handle }
+    }
+
+    pub(crate) fn counters(&self) -> &ProgressCounters {
+        &self.counters
+    }
+
+    /// Sends last progress info and waits for end of thread
+    pub(crate) fn stop(self) {
+        // Everything is done in drop, which is also called when tool returns earlier
+    }
+}
+
+impl Drop for ProgressThread {
+    fn drop(&mut self) {
+        self.thread_run.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+/// Assumes that rest of stage will be checked with same speed as already checked part
+pub fn estimate_time_left(elapsed: Duration, done: u64, total: u64) -> Option<Duration> {
+    if done == 0 || total == 0 || done > total {
+        return None;
+    }
+    Some(elapsed.mul_f64((total - done) as f64 / done as f64))
+}
+
+/// Formats duration in short form e.g. 1h 02m 03s, precision greater than seconds is not needed for estimations
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h {:02}m {:02}s", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_estimate_time_left() {
+        assert_eq!(estimate_time_left(Duration::from_secs(10), 0, 100), None);
+        assert_eq!(estimate_time_left(Duration::from_secs(10), 10, 0), None);
+        assert_eq!(estimate_time_left(Duration::from_secs(10), 25, 100), Some(Duration::from_secs(30)));
+        assert_eq!(estimate_time_left(Duration::from_secs(10), 100, 100), Some(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn test_format_progress() {
+        assert_eq!(format_duration(Duration::from_millis(5900)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h 02m 03s");
+
+        let progress = ProgressData {
+            stage_name: "Calculating partial hash",
+            current_stage: 1,
+            max_stage: 2,
+            items_checked: 120,
+            items_to_check: 500,
+            bytes_checked: 1024 * 1024,
+            bytes_to_check: 4 * 1024 * 1024,
+            current_path: None,
+            estimated_time_left: Some(Duration::from_secs(65)),
+        };
+        assert_eq!(progress.to_string(), "[2/3] Calculating partial hash: 120/500, 1 MiB/4 MiB, about 1m 05s left");
+
+        let progress = ProgressData {
+            stage_name: "Scanning folders",
+            items_checked: 7,
+            ..Default::default()
+        };
+        assert_eq!(progress.to_string(), "[1/1] Scanning folders: 7");
+    }
+
+    #[test]
+    fn test_progress_fraction() {
+        let mut progress = ProgressData {
+            current_stage: 1,
+            max_stage: 2,
+            items_checked: 5,
+            items_to_check: 10,
+            ..Default::default()
+        };
+        assert_eq!(progress.stage_fraction(), Some(0.5));
+        assert_eq!(progress.all_stages_fraction(), 0.5);
+
+        // Bytes are more accurate than number of files, so they are preferred
+        progress.bytes_checked = 10;
+        progress.bytes_to_check = 40;
+        assert_eq!(progress.stage_fraction(), Some(0.25));
+
+        progress.bytes_to_check = 0;
+        progress.items_to_check = 0;
+        assert_eq!(progress.stage_fraction(), None);
+    }
+
+    #[test]
+    fn test_progress_thread() {
+        let (progress_sender, mut progress_receiver) = futures::channel::mpsc::unbounded();
+        let progress_thread = ProgressThread::start(
+            Some(&progress_sender),
+            ProgressData {
+                stage_name: "Checking files",
+                items_to_check: 2,
+                bytes_to_check: 100,
+                ..Default::default()
+            },
+        );
+        progress_thread.counters().add_items(2);
+        progress_thread.counters().add_bytes(100);
+        progress_thread.counters().set_current_path(Path::new("/tmp/a"));
+        progress_thread.stop();
+        drop(progress_sender);
+
+        let last_progress = futures::executor::block_on(progress_receiver.by_ref().collect::<Vec<_>>()).pop().unwrap();
+        assert_eq!(last_progress.stage_name, "Checking files");
+        assert_eq!(last_progress.items_checked, 2);
+        assert_eq!(last_progress.bytes_checked, 100);
+        assert_eq!(last_progress.current_path, Some(PathBuf::from("/tmp/a")));
+        assert_eq!(last_progress.estimated_time_left, Some(Duration::from_secs(0)));
+    }
+}
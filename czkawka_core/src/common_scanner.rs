@@ -0,0 +1,192 @@
+use crate::common_directory::Directories;
+use crate::common_extensions::Extensions;
+use crate::common_file_type::FileType;
+use crate::common_items::ExcludedItems;
+use crate::common_messages::Messages;
+use std::path::PathBuf;
+
+/// Options shared by all tools, options which are not supported by tool are ignored by it
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+    pub included_directories: Vec<PathBuf>,
+    pub excluded_directories: Vec<PathBuf>,
+    pub reference_directories: Vec<PathBuf>,
+    pub excluded_items: Vec<String>,
+    pub exclude_from: Vec<PathBuf>,
+    pub allowed_extensions: String,
+    pub excluded_extensions: String,
+    pub recursive_search: bool,
+    pub use_ignore_files: bool,
+    pub max_depth: Option<usize>,
+    pub one_file_system: bool,
+    pub skip_hidden: bool,
+    pub follow_symlinks: bool,
+    pub maximal_file_size: Option<u64>,
+    pub modified_after: Option<u64>,  // Seconds since Unix Epoch
+    pub modified_before: Option<u64>, // Seconds since Unix Epoch
+    pub content_types: Vec<FileType>,
+    pub detect_by_content: bool,
+}
+
+impl ScanOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Directories, excluded items and excluded extensions are checked when options are set in tool, so problems with them are reported before scan
+    pub(crate) fn set_directories_and_items(&mut self, directories: &mut Directories, excluded_items: &mut ExcludedItems, text_messages: &mut Messages) {
+        directories.set_included_directory(self.included_directories.clone(), text_messages);
+        directories.set_excluded_directory(self.excluded_directories.clone(), text_messages);
+        excluded_items.set_excluded_items(self.excluded_items.clone(), text_messages);
+        excluded_items.set_exclude_from(self.exclude_from.clone(), text_messages);
+        self.validate_excluded_extensions(text_messages);
+    }
+
+    /// Excluded extensions are applied by DirTraversal in every tool, so invalid ones are reported here once and only valid ones are left
+    fn validate_excluded_extensions(&mut self, text_messages: &mut Messages) {
+        let mut extensions = Extensions::new();
+        extensions.set_excluded_extensions(self.excluded_extensions.clone(), text_messages);
+        self.excluded_extensions = extensions.excluded_extensions.join(",");
+    }
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            included_directories: vec![],
+            excluded_directories: vec![],
+            reference_directories: vec![],
+            excluded_items: vec![],
+            exclude_from: vec![],
+            allowed_extensions: "".to_string(),
+            excluded_extensions: "".to_string(),
+            recursive_search: true,
+            use_ignore_files: false,
+            max_depth: None,
+            one_file_system: false,
+            skip_hidden: false,
+            follow_symlinks: false,
+            maximal_file_size: None,
+            modified_after: None,
+            modified_before: None,
+            content_types: vec![],
+            detect_by_content: false,
+        }
+    }
+}
+
+/// Basic info about found file or folder, which is available in every tool
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultEntry {
+    pub path: PathBuf,
+    pub size: Option<u64>, // Not all tools read size of files
+    pub modified_date: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanResults {
+    /// Every entry is separate result e.g. big files or empty folders
+    Entries(Vec<ResultEntry>),
+    /// Entries are grouped e.g. duplicates or similar images
+    Groups(Vec<Vec<ResultEntry>>),
+}
+
+impl ScanResults {
+    pub fn number_of_entries(&self) -> usize {
+        match self {
+            ScanResults::Entries(entries) => entries.len(),
+            ScanResults::Groups(groups) => groups.iter().map(Vec::len).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big_file::BigFile;
+    use crate::common_stop::StopToken;
+    use crate::common_traits::Scanner;
+    use crate::empty_files::EmptyFiles;
+    use std::fs::{self, File};
+    use std::io;
+
+    fn run_scan<T: Scanner>(mut tool: T, scan_options: &ScanOptions) -> ScanResults {
+        tool.set_scan_options(scan_options);
+        tool.scan(None, None);
+        assert!(!tool.get_stopped_search());
+        tool.get_results()
+    }
+
+    #[test]
+    fn test_scanner() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::create_dir_all(dir.path().join("sub"))?;
+        File::create(dir.path().join("empty.txt"))?;
+        File::create(dir.path().join("sub/empty.txt"))?;
+        fs::write(dir.path().join("data.txt"), b"data")?;
+
+        let scan_options = ScanOptions {
+            included_directories: vec![dir.path().to_path_buf()],
+            max_depth: Some(0),
+            ..ScanOptions::new()
+        };
+
+        let results = run_scan(EmptyFiles::new(), &scan_options);
+        assert_eq!(results.number_of_entries(), 1);
+        match results {
+            ScanResults::Entries(entries) => assert_eq!(entries[0].path, dir.path().join("empty.txt")),
+            ScanResults::Groups(_) => panic!(),
+        }
+
+        let results = run_scan(BigFile::new(), &scan_options);
+        match results {
+            ScanResults::Entries(entries) => {
+                assert_eq!(entries[0].path, dir.path().join("data.txt"));
+                assert_eq!(entries[0].size, Some(4));
+            }
+            ScanResults::Groups(_) => panic!(),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stopped_scan() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        File::create(dir.path().join("empty.txt"))?;
+        let scan_options = ScanOptions {
+            included_directories: vec![dir.path().to_path_buf()],
+            ..ScanOptions::new()
+        };
+        let stop_token = StopToken::new();
+        stop_token.stop();
+
+        let mut tool = EmptyFiles::new();
+        tool.set_scan_options(&scan_options);
+        tool.scan(Some(&stop_token), None);
+        assert!(tool.get_stopped_search());
+        assert_eq!(tool.get_results().number_of_entries(), 0);
+        Ok(())
+    }
+    #[test]
+    fn test_invalid_excluded_extensions() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        File::create(dir.path().join("empty.iso"))?;
+        File::create(dir.path().join("empty.txt"))?;
+        let scan_options = ScanOptions {
+            included_directories: vec![dir.path().to_path_buf()],
+            excluded_extensions: ".iso, tar.gz".to_string(),
+            ..ScanOptions::new()
+        };
+
+        let mut tool = EmptyFiles::new();
+        tool.set_scan_options(&scan_options);
+        tool.scan(None, None);
+        // Invalid extension is reported only once, when options are set
+        assert_eq!(tool.get_text_messages().warnings, vec![".tar.gz is not valid extension(valid extension doesn't have dot inside)".to_string()]);
+        match tool.get_results() {
+            ScanResults::Entries(entries) => assert_eq!(entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>(), vec![dir.path().join("empty.txt")]),
+            ScanResults::Groups(_) => panic!(),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag used to stop search.
+/// All clones see the same state, so every stage and every thread checking it notices stop, not only the first one
+#[derive(Clone, Debug, Default)]
+pub struct StopToken {
+    stopped: Arc<AtomicBool>,
+}
+
+impl StopToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Asks search to end as soon as possible, calling it more than once is harmless
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Must be called before starting new search with same token, otherwise it ends immediately
+    pub fn reset(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_stop_token() {
+        let stop_token = StopToken::new();
+        let cloned_token = stop_token.clone();
+        assert!(!cloned_token.is_stopped());
+
+        stop_token.stop();
+        assert!(stop_token.is_stopped());
+        assert!(cloned_token.is_stopped());
+        // Unlike message from channel, stop is seen by all workers
+        assert!((0..100).into_par_iter().all(|_| cloned_token.is_stopped()));
+
+        cloned_token.reset();
+        assert!(!stop_token.is_stopped());
+    }
+}
@@ -1,3 +1,9 @@
+use crate::common_messages::Messages;
+use crate::common_progress::ProgressData;
+use crate::common_scanner::{ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
+use futures::channel::mpsc::UnboundedSender;
+
 pub trait DebugPrint {
     fn debug_print(&self);
 }
@@ -7,3 +13,14 @@ pub trait SaveResults {
 pub trait PrintResults {
     fn print_results(&self);
 }
+
+/// Common interface of all tools, so they can be configured, run and checked in same way
+pub trait Scanner {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions);
+    /// Runs search with options set earlier, search can be stopped from other thread by calling `stop` on clone of stop token
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&UnboundedSender<ProgressData>>);
+    /// True when search was stopped, so results are incomplete and shouldn't be used
+    fn get_stopped_search(&self) -> bool;
+    fn get_text_messages(&self) -> &Messages;
+    fn get_results(&self) -> ScanResults;
+}
@@ -1,43 +1,45 @@
-use crossbeam_channel::Receiver;
+use crate::common_stop::StopToken;
 use humansize::{file_size_opts as options, FileSize};
+use std::cmp::min;
 use std::collections::BTreeMap;
 #[cfg(target_family = "unix")]
 use std::collections::HashSet;
-use std::fs::{File, Metadata, OpenOptions};
+use std::fs::{File, OpenOptions};
+use std::io;
 use std::io::prelude::*;
-use std::io::{self, Error, ErrorKind};
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, mem, thread};
+use std::time::SystemTime;
+use std::{fs, mem};
 
 use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_cache::{get_cache_volume_roots, load_cache_from_file, save_cache_to_file, CacheEntry, CacheLocation, CacheUsage, MovedEntries};
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_extensions::Extensions;
+use crate::common_extents::count_data_copies;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressCounters, ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
 use crate::common_traits::*;
-use directories_next::ProjectDirs;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::hash::Hasher;
 use std::io::{BufReader, BufWriter};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::thread::sleep;
 
 const HASH_MB_LIMIT_BYTES: u64 = 1024 * 1024; // 1MB
+const PREHASH_BYTES: u64 = 1024 * 2; // 2KB, read only once
 
-const CACHE_FILE_NAME: &str = "cache_duplicates.txt";
+pub(crate) const CACHE_FILE_NAME: &str = "cache_duplicates.bin";
+const LEGACY_CACHE_FILE_NAME: &str = "cache_duplicates.txt";
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub checking_method: CheckingMethod,
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub files_checked: usize,
-    pub files_to_check: usize,
-}
+/// Cached entries grouped by size and entries which may belong to moved or renamed files
+type LoadedHashes = (BTreeMap<u64, Vec<FileEntry>>, MovedEntries<FileEntry>);
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum CheckingMethod {
@@ -100,14 +102,33 @@ pub enum DeleteMethod {
     OneOldest,
     OneNewest,
     HardLink,
+    SymLink,
+    RelativeSymLink,
+    Dedupe,
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct FileEntry {
     pub path: PathBuf,
     pub size: u64,
     pub modified_date: u64,
     pub hash: String,
+    pub already_deduplicated: bool, // Whole group already shares its data on copy-on-write file system, so it doesn't take additional space
+}
+
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
 }
 
 /// Info struck with helpful information's about results
@@ -121,6 +142,8 @@ pub struct Info {
     pub number_of_duplicated_files_by_name: usize,
     pub lost_space_by_size: u64,
     pub lost_space_by_hash: u64,
+    pub number_of_already_deduplicated_groups: usize,
+    pub number_of_already_deduplicated_files: usize,
     pub bytes_read_when_hashing: u64,
     pub number_of_removed_files: usize,
     pub number_of_failed_to_remove_files: usize,
@@ -143,15 +166,17 @@ pub struct DuplicateFinder {
     directories: Directories,
     allowed_extensions: Extensions,
     excluded_items: ExcludedItems,
-    recursive_search: bool,
+    scan_options: ScanOptions,
     minimal_file_size: u64,
     check_method: CheckingMethod,
     delete_method: DeleteMethod,
+    delete_action: FileAction,
     hash_type: HashType,
     ignore_hard_links: bool,
     dryrun: bool,
     stopped_search: bool,
     use_cache: bool,
+    cache_location: CacheLocation,
     minimal_cache_file_size: u64,
 }
 
@@ -163,44 +188,47 @@ impl DuplicateFinder {
             files_with_identical_names: Default::default(),
             files_with_identical_size: Default::default(),
             files_with_identical_hashes: Default::default(),
-            recursive_search: true,
             allowed_extensions: Extensions::new(),
             check_method: CheckingMethod::None,
             delete_method: DeleteMethod::None,
+            delete_action: FileAction::Delete,
             minimal_file_size: 1024,
             directories: Directories::new(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             stopped_search: false,
             ignore_hard_links: true,
             hash_type: HashType::Blake3,
             dryrun: false,
             use_cache: true,
+            cache_location: CacheLocation::new(),
             minimal_cache_file_size: 2 * 1024 * 1024, // By default cache only >= 1MB files
         }
     }
 
-    pub fn find_duplicates(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
+    pub fn find_duplicates(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
 
         match self.check_method {
             CheckingMethod::Name => {
-                if !self.check_files_name(stop_receiver, progress_sender) {
+                if !self.check_files_name(stop_token, progress_sender) {
                     self.stopped_search = true;
                     return;
                 }
             }
             CheckingMethod::Size => {
-                if !self.check_files_size(stop_receiver, progress_sender) {
+                if !self.check_files_size(stop_token, progress_sender) {
                     self.stopped_search = true;
                     return;
                 }
             }
             CheckingMethod::HashMb | CheckingMethod::Hash => {
-                if !self.check_files_size(stop_receiver, progress_sender) {
+                if !self.check_files_size(stop_token, progress_sender) {
                     self.stopped_search = true;
                     return;
                 }
-                if !self.check_files_hash(stop_receiver, progress_sender) {
+                if !self.check_files_hash(stop_token, progress_sender) {
                     self.stopped_search = true;
                     return;
                 }
@@ -233,6 +261,15 @@ impl DuplicateFinder {
         self.use_cache = use_cache;
     }
 
+    /// `None` means default cache folder
+    pub fn set_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.cache_location.cache_dir = cache_dir;
+    }
+
+    pub fn set_portable_cache(&mut self, portable_cache: bool) {
+        self.cache_location.portable = portable_cache;
+    }
+
     pub const fn get_files_sorted_by_size(&self) -> &BTreeMap<u64, Vec<FileEntry>> {
         &self.files_with_identical_size
     }
@@ -269,6 +306,11 @@ impl DuplicateFinder {
         self.delete_method = delete_method;
     }
 
+    /// Chooses what happens with files removed by delete method(except hard links and symlinks), by default they are permanently deleted
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
         self.minimal_file_size = match minimal_file_size {
             0 => 1,
@@ -277,7 +319,7 @@ impl DuplicateFinder {
     }
 
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
@@ -287,6 +329,11 @@ impl DuplicateFinder {
     pub fn set_excluded_directory(&mut self, excluded_directory: Vec<PathBuf>) {
         self.directories.set_excluded_directory(excluded_directory, &mut self.text_messages);
     }
+
+    /// Files from reference directories are compared with other files, but groups with only such files are not shown and these files are never removed
+    pub fn set_reference_directory(&mut self, reference_directory: Vec<PathBuf>) {
+        self.directories.set_reference_directory(reference_directory, &mut self.text_messages);
+    }
     pub fn set_allowed_extensions(&mut self, allowed_extensions: String) {
         self.allowed_extensions.set_allowed_extensions(allowed_extensions, &mut self.text_messages);
     }
@@ -295,158 +342,58 @@ impl DuplicateFinder {
         self.excluded_items.set_excluded_items(excluded_items, &mut self.text_messages);
     }
 
-    fn check_files_name(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files_name(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: CheckingMethod::Name,
-                        current_stage: 0,
-                        max_stage: 0,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue 'dir;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning names",
+                current_stage: 0,
+                max_stage: 0,
+                ..Default::default()
+            },
+        );
 
-                    if self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_content_types(self.scan_options.content_types.clone());
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        dir_traversal.set_minimal_file_size(self.minimal_file_size);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    // let mut have_valid_extension: bool;
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue 'dir,
-                    }
-                    .to_lowercase();
-
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
-                            continue 'dir;
-                        }
-                    }
-                    // Checking files
-                    if metadata.len() >= self.minimal_file_size {
-                        let current_file_name = current_folder.join(entry_data.file_name());
-                        if self.excluded_items.is_excluded(&current_file_name) {
-                            continue 'dir;
-                        }
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    let key = match fe.path.file_name() {
+                        Some(t) => t.to_string_lossy().to_string(),
+                        None => continue,
+                    };
+                    let fe = FileEntry {
+                        path: fe.path,
+                        size: fe.size,
+                        modified_date: fe.modified_date,
+                        hash: "".to_string(),
+                        already_deduplicated: false,
+                    };
 
-                        // Creating new file entry
-                        let fe: FileEntry = FileEntry {
-                            path: current_file_name.clone(),
-                            size: metadata.len(),
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                    continue 'dir;
-                                } // Permissions Denied
-                            },
-                            hash: "".to_string(),
-                        };
-
-                        // Adding files to BTreeMap
-                        self.files_with_identical_names.entry(entry_data.file_name().to_string_lossy().to_string()).or_insert_with(Vec::new);
-                        self.files_with_identical_names.get_mut(&entry_data.file_name().to_string_lossy().to_string()).unwrap().push(fe);
-                    }
+                    // Adding files to BTreeMap
+                    self.files_with_identical_names.entry(key.clone()).or_insert_with(Vec::new);
+                    self.files_with_identical_names.get_mut(&key).unwrap().push(fe);
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
 
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
-
         // Create new BTreeMap without single size entries(files have not duplicates)
         let mut new_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
 
         for (name, vector) in &self.files_with_identical_names {
-            if vector.len() > 1 {
+            if vector.len() > 1 && !self.directories.all_in_reference_directories(vector.iter().map(|fe| fe.path.as_path())) {
                 self.information.number_of_duplicated_files_by_name += vector.len() - 1;
                 self.information.number_of_groups_by_name += 1;
                 new_map.insert(name.clone(), vector.clone());
@@ -460,158 +407,52 @@ impl DuplicateFinder {
 
     /// Read file length and puts it to different boxes(each for different lengths)
     /// If in box is only 1 result, then it is removed
-    fn check_files_size(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files_size(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let checking_method = self.check_method.clone();
-            let max_stage = match self.check_method {
-                CheckingMethod::Size => 0,
-                CheckingMethod::HashMb | CheckingMethod::Hash => 2,
-                _ => 255,
-            };
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: checking_method.clone(),
-                        current_stage: 0,
-                        max_stage,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue 'dir;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    if self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    // let mut have_valid_extension: bool;
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue 'dir,
-                    }
-                    .to_lowercase();
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning sizes",
+                current_stage: 0,
+                max_stage: match self.check_method {
+                    CheckingMethod::HashMb | CheckingMethod::Hash => 2,
+                    _ => 0,
+                },
+                ..Default::default()
+            },
+        );
 
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_content_types(self.scan_options.content_types.clone());
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        dir_traversal.set_minimal_file_size(self.minimal_file_size);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-                            continue 'dir;
-                        }
-                    }
-                    // Checking files
-                    if metadata.len() >= self.minimal_file_size {
-                        let current_file_name = current_folder.join(entry_data.file_name());
-                        if self.excluded_items.is_excluded(&current_file_name) {
-                            continue 'dir;
-                        }
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    let key = fe.size;
+                    let fe = FileEntry {
+                        path: fe.path,
+                        size: fe.size,
+                        modified_date: fe.modified_date,
+                        hash: "".to_string(),
+                        already_deduplicated: false,
+                    };
 
-                        // Creating new file entry
-                        let fe: FileEntry = FileEntry {
-                            path: current_file_name.clone(),
-                            size: metadata.len(),
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                    continue 'dir;
-                                } // Permissions Denied
-                            },
-                            hash: "".to_string(),
-                        };
-
-                        // Adding files to BTreeMap
-                        self.files_with_identical_size.entry(metadata.len()).or_insert_with(Vec::new);
-                        self.files_with_identical_size.get_mut(&metadata.len()).unwrap().push(fe);
-                    }
+                    // Adding files to BTreeMap
+                    self.files_with_identical_size.entry(key).or_insert_with(Vec::new);
+                    self.files_with_identical_size.get_mut(&key).unwrap().push(fe);
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
 
         // Create new BTreeMap without single size entries(files have not duplicates)
         let mut new_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
@@ -628,7 +469,7 @@ impl DuplicateFinder {
                 vector = vec.clone();
             }
 
-            if vector.len() > 1 {
+            if vector.len() > 1 && !self.directories.all_in_reference_directories(vector.iter().map(|fe| fe.path.as_path())) {
                 self.information.number_of_duplicated_files_by_size += vector.len() - 1;
                 self.information.number_of_groups_by_size += 1;
                 self.information.lost_space_by_size += (vector.len() as u64 - 1) * size;
@@ -642,65 +483,41 @@ impl DuplicateFinder {
     }
 
     /// The slowest checking type, which must be applied after checking for size
-    fn check_files_hash(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files_hash(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let check_type = Arc::new(self.hash_type);
 
         let start_time: SystemTime = SystemTime::now();
-        let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
         let mut pre_checked_map: BTreeMap<u64, Vec<FileEntry>> = Default::default();
 
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = self.files_with_identical_size.iter().map(|e| e.1.len()).sum();
-            let checking_method = self.check_method.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: checking_method.clone(),
-                        current_stage: 1,
-                        max_stage: 2,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-
-        //// PROGRESS THREAD END
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Calculating partial hash",
+                current_stage: 1,
+                max_stage: 2,
+                items_to_check: self.files_with_identical_size.values().map(Vec::len).sum(),
+                bytes_to_check: self.files_with_identical_size.iter().map(|(size, vec_file_entry)| min(*size, PREHASH_BYTES) * vec_file_entry.len() as u64).sum(),
+                ..Default::default()
+            },
+        );
 
         #[allow(clippy::type_complexity)]
-        let pre_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<String>, u64)> = self
+        let pre_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<MessageEntry>, u64)> = self
             .files_with_identical_size
             .par_iter()
             .map(|(size, vec_file_entry)| {
                 let mut hashmap_with_hash: BTreeMap<String, Vec<FileEntry>> = Default::default();
-                let mut errors: Vec<String> = Vec::new();
+                let mut errors: Vec<MessageEntry> = Vec::new();
                 let mut bytes_read: u64 = 0;
-                let mut buffer = [0u8; 1024 * 2];
+                let mut buffer = [0u8; PREHASH_BYTES as usize];
 
-                atomic_file_counter.fetch_add(vec_file_entry.len(), Ordering::Relaxed);
                 for file_entry in vec_file_entry {
-                    if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                        check_was_breaked.store(true, Ordering::Relaxed);
+                    if stop_token.is_stopped() {
                         return None;
                     }
-                    match hash_calculation(&mut buffer, &file_entry, &check_type, 0) {
-                        Ok((hash_string, bytes)) => {
+                    match hash_calculation(&mut buffer, &file_entry, &check_type, 0, stop_token, progress_thread.counters()) {
+                        Ok(None) => return None,
+                        Ok(Some((hash_string, bytes))) => {
                             bytes_read += bytes;
                             hashmap_with_hash.entry(hash_string.clone()).or_insert_with(Vec::new);
                             hashmap_with_hash.get_mut(hash_string.as_str()).unwrap().push(file_entry.clone());
@@ -714,18 +531,17 @@ impl DuplicateFinder {
             .collect();
 
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
 
-        // Check if user aborted search(only from GUI)
-        if check_was_breaked.load(Ordering::Relaxed) {
+        // Workers end early after stop, so results are incomplete
+        if stop_token.is_stopped() {
             return false;
         }
 
         // Check results
-        for (size, hash_map, mut errors, bytes_read) in pre_hash_results {
+        for (size, hash_map, errors, bytes_read) in pre_hash_results {
             self.information.bytes_read_when_hashing += bytes_read;
-            self.text_messages.warnings.append(&mut errors);
+            self.text_messages.extend_warnings(errors);
             for (_hash, mut vec_file_entry) in hash_map {
                 if vec_file_entry.len() > 1 {
                     pre_checked_map.entry(size).or_insert_with(Vec::new);
@@ -739,42 +555,21 @@ impl DuplicateFinder {
 
         /////////////////////////
 
-        //// PROGRESS THREAD START
-        // const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let files_to_check = pre_checked_map.iter().map(|e| e.1.len()).sum();
-            let checking_method = self.check_method.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        checking_method: checking_method.clone(),
-                        current_stage: 2,
-                        max_stage: 2,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        files_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-
-        //// PROGRESS THREAD END
+        let hash_limit = if self.check_method == CheckingMethod::HashMb { HASH_MB_LIMIT_BYTES } else { u64::MAX };
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: if self.check_method == CheckingMethod::Hash { "Calculating full hash" } else { "Calculating hash of first MB" },
+                current_stage: 2,
+                max_stage: 2,
+                items_to_check: pre_checked_map.values().map(Vec::len).sum(),
+                bytes_to_check: pre_checked_map.iter().map(|(size, vec_file_entry)| min(*size, hash_limit) * vec_file_entry.len() as u64).sum(),
+                ..Default::default()
+            },
+        );
 
         #[allow(clippy::type_complexity)]
-        let mut full_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<String>, u64)>;
+        let mut full_hash_results: Vec<(u64, BTreeMap<String, Vec<FileEntry>>, Vec<MessageEntry>, u64)>;
 
         match self.check_method {
             CheckingMethod::HashMb => {
@@ -782,21 +577,22 @@ impl DuplicateFinder {
                     .par_iter()
                     .map(|(size, vec_file_entry)| {
                         let mut hashmap_with_hash: BTreeMap<String, Vec<FileEntry>> = Default::default();
-                        let mut errors: Vec<String> = Vec::new();
+                        let mut errors: Vec<MessageEntry> = Vec::new();
                         let mut bytes_read: u64 = 0;
                         let mut buffer = [0u8; 1024 * 128];
-                        atomic_file_counter.fetch_add(vec_file_entry.len(), Ordering::Relaxed);
                         for file_entry in vec_file_entry {
-                            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                                check_was_breaked.store(true, Ordering::Relaxed);
+                            if stop_token.is_stopped() {
                                 return None;
                             }
 
-                            match hash_calculation(&mut buffer, &file_entry, &check_type, HASH_MB_LIMIT_BYTES) {
-                                Ok((hash_string, bytes)) => {
+                            match hash_calculation(&mut buffer, &file_entry, &check_type, HASH_MB_LIMIT_BYTES, stop_token, progress_thread.counters()) {
+                                Ok(None) => return None,
+                                Ok(Some((hash_string, bytes))) => {
                                     bytes_read += bytes;
-                                    hashmap_with_hash.entry(hash_string.to_string()).or_insert_with(Vec::new);
-                                    hashmap_with_hash.get_mut(hash_string.as_str()).unwrap().push(file_entry.to_owned());
+                                    let mut file_entry = file_entry.clone();
+                                    file_entry.hash = hash_string.clone();
+                                    hashmap_with_hash.entry(hash_string.clone()).or_insert_with(Vec::new);
+                                    hashmap_with_hash.get_mut(hash_string.as_str()).unwrap().push(file_entry);
                                 }
                                 Err(s) => errors.push(s),
                             }
@@ -812,35 +608,26 @@ impl DuplicateFinder {
                 let mut records_already_cached: BTreeMap<u64, Vec<FileEntry>> = Default::default();
                 let mut non_cached_files_to_check: BTreeMap<u64, Vec<FileEntry>> = Default::default();
 
+                let volume_roots = get_cache_volume_roots(&self.cache_location, &self.directories.included_directories);
                 if self.use_cache {
-                    loaded_hash_map = match load_hashes_from_file(&mut self.text_messages, &self.hash_type) {
-                        Some(t) => t,
-                        None => Default::default(),
-                    };
+                    let (loaded_entries, moved_entries) = load_hashes_from_file(&mut self.text_messages, &self.hash_type, &self.cache_location, &volume_roots).unwrap_or_default();
+                    loaded_hash_map = loaded_entries;
 
                     for (size, vec_file_entry) in pre_checked_map {
-                        #[allow(clippy::collapsible_if)]
-                        if !loaded_hash_map.contains_key(&size) {
-                            // If loaded data doesn't contains current info
-                            non_cached_files_to_check.insert(size, vec_file_entry);
-                        } else {
-                            let loaded_vec_file_entry = loaded_hash_map.get(&size).unwrap();
-
-                            for file_entry in vec_file_entry {
-                                let mut found: bool = false;
-                                for loaded_file_entry in loaded_vec_file_entry {
-                                    if file_entry.path == loaded_file_entry.path && file_entry.modified_date == loaded_file_entry.modified_date {
-                                        records_already_cached.entry(file_entry.size).or_insert_with(Vec::new);
-                                        records_already_cached.get_mut(&file_entry.size).unwrap().push(loaded_file_entry.clone());
-                                        found = true;
-                                        break;
-                                    }
-                                }
+                        let empty_vec_file_entry = Vec::new();
+                        let loaded_vec_file_entry = loaded_hash_map.get(&size).unwrap_or(&empty_vec_file_entry);
 
-                                if !found {
-                                    non_cached_files_to_check.entry(file_entry.size).or_insert_with(Vec::new);
-                                    non_cached_files_to_check.get_mut(&file_entry.size).unwrap().push(file_entry);
-                                }
+                        for file_entry in vec_file_entry {
+                            let loaded_file_entry = loaded_vec_file_entry
+                                .iter()
+                                .find(|loaded_file_entry| file_entry.path == loaded_file_entry.path && file_entry.modified_date == loaded_file_entry.modified_date)
+                                .cloned()
+                                // Renamed or moved file still may have valid entry
+                                .or_else(|| moved_entries.find(&file_entry.path, file_entry.size, file_entry.modified_date));
+
+                            match loaded_file_entry {
+                                Some(loaded_file_entry) => records_already_cached.entry(file_entry.size).or_default().push(loaded_file_entry),
+                                None => non_cached_files_to_check.entry(file_entry.size).or_default().push(file_entry),
                             }
                         }
                     }
@@ -849,23 +636,34 @@ impl DuplicateFinder {
                     mem::swap(&mut pre_checked_map, &mut non_cached_files_to_check);
                 }
 
+                let number_of_cached_files: usize = records_already_cached.values().map(Vec::len).sum();
+                let cache_usage = CacheUsage {
+                    loaded_from_cache: number_of_cached_files as u64,
+                    checked_files: (number_of_cached_files + non_cached_files_to_check.values().map(Vec::len).sum::<usize>()) as u64,
+                };
+
+                // Files with hash loaded from cache are not read, so they are treated as already checked
+                for (size, vec_file_entry) in &records_already_cached {
+                    progress_thread.counters().add_items(vec_file_entry.len());
+                    progress_thread.counters().add_bytes(size * vec_file_entry.len() as u64);
+                }
+
                 full_hash_results = non_cached_files_to_check
                     .par_iter()
                     .map(|(size, vec_file_entry)| {
                         let mut hashmap_with_hash: BTreeMap<String, Vec<FileEntry>> = Default::default();
-                        let mut errors: Vec<String> = Vec::new();
+                        let mut errors: Vec<MessageEntry> = Vec::new();
                         let mut bytes_read: u64 = 0;
                         let mut buffer = [0u8; 1024 * 128];
 
-                        atomic_file_counter.fetch_add(vec_file_entry.len(), Ordering::Relaxed);
                         for file_entry in vec_file_entry {
-                            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                                check_was_breaked.store(true, Ordering::Relaxed);
+                            if stop_token.is_stopped() {
                                 return None;
                             }
 
-                            match hash_calculation(&mut buffer, &file_entry, &check_type, u64::MAX) {
-                                Ok((hash_string, bytes)) => {
+                            match hash_calculation(&mut buffer, &file_entry, &check_type, u64::MAX, stop_token, progress_thread.counters()) {
+                                Ok(None) => return None,
+                                Ok(Some((hash_string, bytes))) => {
                                     bytes_read += bytes;
                                     let mut file_entry = file_entry.clone();
                                     file_entry.hash = hash_string.clone();
@@ -915,26 +713,25 @@ impl DuplicateFinder {
                             }
                         }
                     }
-                    save_hashes_to_file(&all_results, &mut self.text_messages, &self.hash_type, self.minimal_cache_file_size);
+                    save_hashes_to_file(&all_results, &mut self.text_messages, &self.hash_type, self.minimal_cache_file_size, &self.cache_location, &volume_roots, cache_usage);
                 }
             }
             _ => panic!("What"),
         }
 
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
 
-        // Check if user aborted search(only from GUI)
-        if check_was_breaked.load(Ordering::Relaxed) {
+        // Workers end early after stop, so results are incomplete
+        if stop_token.is_stopped() {
             return false;
         }
 
-        for (size, hash_map, mut errors, bytes_read) in full_hash_results {
+        for (size, hash_map, errors, bytes_read) in full_hash_results {
             self.information.bytes_read_when_hashing += bytes_read;
-            self.text_messages.warnings.append(&mut errors);
+            self.text_messages.extend_warnings(errors);
             for (_hash, vec_file_entry) in hash_map {
-                if vec_file_entry.len() > 1 {
+                if vec_file_entry.len() > 1 && !self.directories.all_in_reference_directories(vec_file_entry.iter().map(|fe| fe.path.as_path())) {
                     self.files_with_identical_hashes.entry(size).or_insert_with(Vec::new);
                     self.files_with_identical_hashes.get_mut(&size).unwrap().push(vec_file_entry);
                 }
@@ -943,11 +740,20 @@ impl DuplicateFinder {
 
         /////////////////////////
 
-        for (size, vector_vectors) in &self.files_with_identical_hashes {
+        // Reflinked or deduplicated files on copy-on-write file systems take space only once, so only other copies of data are counted as lost space
+        // Groups which already share all their data are kept in results, but are flagged and don't take lost space
+        for (size, vector_vectors) in self.files_with_identical_hashes.iter_mut() {
             for vector in vector_vectors {
+                let data_copies = count_data_copies(vector.iter().map(|fe| fe.path.as_path()));
+                if data_copies == 1 {
+                    self.information.number_of_already_deduplicated_groups += 1;
+                    self.information.number_of_already_deduplicated_files += vector.len();
+                    vector.iter_mut().for_each(|fe| fe.already_deduplicated = true);
+                    continue;
+                }
                 self.information.number_of_duplicated_files_by_hash += vector.len() - 1;
                 self.information.number_of_groups_by_hash += 1;
-                self.information.lost_space_by_hash += (vector.len() as u64 - 1) * size;
+                self.information.lost_space_by_hash += (data_copies as u64 - 1) * *size;
             }
         }
 
@@ -970,7 +776,7 @@ impl DuplicateFinder {
         match self.check_method {
             CheckingMethod::Name => {
                 for vector in self.files_with_identical_names.values() {
-                    let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                    let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &self.delete_action, &self.directories, &mut self.text_messages, self.dryrun);
                     self.information.gained_space += tuple.0;
                     self.information.number_of_removed_files += tuple.1;
                     self.information.number_of_failed_to_remove_files += tuple.2;
@@ -978,8 +784,9 @@ impl DuplicateFinder {
             }
             CheckingMethod::Hash | CheckingMethod::HashMb => {
                 for vector_vectors in self.files_with_identical_hashes.values() {
-                    for vector in vector_vectors.iter() {
-                        let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                    // Files which already share their data are never removed or replaced with links, because it wouldn't free any space
+                    for vector in vector_vectors.iter().filter(|vector| !is_already_deduplicated(vector)) {
+                        let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &self.delete_action, &self.directories, &mut self.text_messages, self.dryrun);
                         self.information.gained_space += tuple.0;
                         self.information.number_of_removed_files += tuple.1;
                         self.information.number_of_failed_to_remove_files += tuple.2;
@@ -988,7 +795,7 @@ impl DuplicateFinder {
             }
             CheckingMethod::Size => {
                 for vector in self.files_with_identical_size.values() {
-                    let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &mut self.text_messages, self.dryrun);
+                    let tuple: (u64, usize, usize) = delete_files(vector, &self.delete_method, &self.delete_action, &self.directories, &mut self.text_messages, self.dryrun);
                     self.information.gained_space += tuple.0;
                     self.information.number_of_removed_files += tuple.1;
                     self.information.number_of_failed_to_remove_files += tuple.2;
@@ -1009,6 +816,46 @@ impl Default for DuplicateFinder {
     }
 }
 
+impl Scanner for DuplicateFinder {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_reference_directory(scan_options.reference_directories.clone());
+        self.set_allowed_extensions(scan_options.allowed_extensions.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_duplicates(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        let to_entries = |files: &Vec<FileEntry>| {
+            files
+                .iter()
+                .map(|e| ResultEntry {
+                    path: e.path.clone(),
+                    size: Some(e.size),
+                    modified_date: e.modified_date,
+                })
+                .collect()
+        };
+        match self.check_method {
+            CheckingMethod::Name => ScanResults::Groups(self.files_with_identical_names.values().map(to_entries).collect()),
+            CheckingMethod::Size => ScanResults::Groups(self.files_with_identical_size.values().map(to_entries).collect()),
+            CheckingMethod::Hash | CheckingMethod::HashMb => ScanResults::Groups(self.files_with_identical_hashes.values().flatten().map(to_entries).collect()),
+            CheckingMethod::None => ScanResults::Groups(Vec::new()),
+        }
+    }
+}
+
 impl DebugPrint for DuplicateFinder {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -1038,6 +885,10 @@ impl DebugPrint for DuplicateFinder {
         );
         println!("Lost space by size - {} ({} bytes)", self.information.lost_space_by_size.file_size(options::BINARY).unwrap(), self.information.lost_space_by_size);
         println!("Lost space by hash - {} ({} bytes)", self.information.lost_space_by_hash.file_size(options::BINARY).unwrap(), self.information.lost_space_by_hash);
+        println!(
+            "Number of already deduplicated files(in groups) - {} ({})",
+            self.information.number_of_already_deduplicated_files, self.information.number_of_already_deduplicated_groups
+        );
         println!(
             "Gained space by removing duplicated entries - {} ({} bytes)",
             self.information.gained_space.file_size(options::BINARY).unwrap(),
@@ -1059,10 +910,11 @@ impl DebugPrint for DuplicateFinder {
         println!("Excluded items - {:?}", self.excluded_items.items);
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Minimum file size - {:?}", self.minimal_file_size);
         println!("Checking Method - {:?}", self.check_method);
         println!("Delete Method - {:?}", self.delete_method);
+        println!("Delete Action - {:?}", self.delete_action);
         println!("-----------------------------------------");
     }
 }
@@ -1074,23 +926,61 @@ impl SaveResults for DuplicateFinder {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let groups: Vec<&Vec<FileEntry>> = match self.check_method {
+                CheckingMethod::Name => self.files_with_identical_names.values().collect(),
+                CheckingMethod::Size => self.files_with_identical_size.values().collect(),
+                CheckingMethod::Hash | CheckingMethod::HashMb => self.files_with_identical_hashes.values().flatten().collect(),
+                CheckingMethod::None => Vec::new(),
+            };
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["group", "size", "modified_date", "path", "hash", "already_deduplicated"],
+                    groups.iter().enumerate().flat_map(|(group_id, group)| {
+                        group.iter().map(move |e| {
+                            vec![
+                                (group_id + 1).to_string(),
+                                e.size.to_string(),
+                                format_csv_date(e.modified_date),
+                                e.path.to_string_lossy().to_string(),
+                                e.hash.clone(),
+                                e.already_deduplicated.to_string(),
+                            ]
+                        })
+                    }),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &groups, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push(format!("Failed to create file {}", file_name));
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
-            "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
-            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+            "Results of searching {:?} with excluded directories {:?} and excluded items {:?} and reference directories {:?}",
+            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items, self.directories.reference_directories
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
         match self.check_method {
@@ -1136,7 +1026,7 @@ impl SaveResults for DuplicateFinder {
                 }
             }
             CheckingMethod::Hash | CheckingMethod::HashMb => {
-                if !self.files_with_identical_hashes.is_empty() {
+                if self.information.number_of_groups_by_hash > 0 {
                     writeln!(writer, "-------------------------------------------------Files with same hashes-------------------------------------------------").unwrap();
                     writeln!(
                         writer,
@@ -1147,7 +1037,7 @@ impl SaveResults for DuplicateFinder {
                     )
                     .unwrap();
                     for (size, vectors_vector) in self.files_with_identical_hashes.iter().rev() {
-                        for vector in vectors_vector {
+                        for vector in vectors_vector.iter().filter(|vector| !is_already_deduplicated(vector)) {
                             writeln!(writer, "\n---- Size {} ({}) - {} files", size.file_size(options::BINARY).unwrap(), size, vector.len()).unwrap();
                             for file_entry in vector {
                                 writeln!(writer, "{}", file_entry.path.display()).unwrap();
@@ -1157,6 +1047,23 @@ impl SaveResults for DuplicateFinder {
                 } else {
                     write!(writer, "Not found any duplicates.").unwrap();
                 }
+                if self.information.number_of_already_deduplicated_groups > 0 {
+                    writeln!(writer, "\n-------------------------------------------------Already deduplicated files-------------------------------------------------").unwrap();
+                    writeln!(
+                        writer,
+                        "Found {} files in {} groups which already share their data, so they don't take additional space.",
+                        self.information.number_of_already_deduplicated_files, self.information.number_of_already_deduplicated_groups
+                    )
+                    .unwrap();
+                    for (size, vectors_vector) in self.files_with_identical_hashes.iter().rev() {
+                        for vector in vectors_vector.iter().filter(|vector| is_already_deduplicated(vector)) {
+                            writeln!(writer, "\n---- Size {} ({}) - {} files", size.file_size(options::BINARY).unwrap(), size, vector.len()).unwrap();
+                            for file_entry in vector {
+                                writeln!(writer, "{}", file_entry.path.display()).unwrap();
+                            }
+                        }
+                    }
+                }
             }
             CheckingMethod::None => {
                 panic!();
@@ -1191,7 +1098,7 @@ impl PrintResults for DuplicateFinder {
             }
             CheckingMethod::Hash | CheckingMethod::HashMb => {
                 for (_size, vector) in self.files_with_identical_hashes.iter() {
-                    for j in vector {
+                    for j in vector.iter().filter(|j| !is_already_deduplicated(j)) {
                         number_of_files += j.len() as u64;
                         number_of_groups += 1;
                     }
@@ -1200,10 +1107,10 @@ impl PrintResults for DuplicateFinder {
                     "Found {} duplicated files in {} groups with same content which took {}:",
                     number_of_files,
                     number_of_groups,
-                    self.information.lost_space_by_size.file_size(options::BINARY).unwrap()
+                    self.information.lost_space_by_hash.file_size(options::BINARY).unwrap()
                 );
                 for (size, vector) in self.files_with_identical_hashes.iter().rev() {
-                    for j in vector {
+                    for j in vector.iter().filter(|j| !is_already_deduplicated(j)) {
                         println!("Size - {} ({}) - {} files ", size.file_size(options::BINARY).unwrap(), size, j.len());
                         for k in j {
                             println!("{}", k.path.display());
@@ -1212,6 +1119,21 @@ impl PrintResults for DuplicateFinder {
                     }
                     println!();
                 }
+                if self.information.number_of_already_deduplicated_groups > 0 {
+                    println!(
+                        "Found {} files in {} groups which already share their data, so they don't take additional space:",
+                        self.information.number_of_already_deduplicated_files, self.information.number_of_already_deduplicated_groups
+                    );
+                    for (size, vector) in self.files_with_identical_hashes.iter().rev() {
+                        for j in vector.iter().filter(|j| is_already_deduplicated(j)) {
+                            println!("Size - {} ({}) - {} files ", size.file_size(options::BINARY).unwrap(), size, j.len());
+                            for k in j {
+                                println!("{}", k.path.display());
+                            }
+                            println!("----");
+                        }
+                    }
+                }
             }
             CheckingMethod::Size => {
                 for i in &self.files_with_identical_size {
@@ -1242,7 +1164,8 @@ impl PrintResults for DuplicateFinder {
 
 /// Functions to remove slice(vector) of files with provided method
 /// Returns size of removed elements, number of deleted and failed to delete files and modified warning list
-fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, text_messages: &mut Messages, dryrun: bool) -> (u64, usize, usize) {
+/// Files from reference directories are never removed, when group contains such file, then it is kept instead of the newest or oldest one
+fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, delete_action: &FileAction, directories: &Directories, text_messages: &mut Messages, dryrun: bool) -> (u64, usize, usize) {
     assert!(vector.len() > 1, "Vector length must be bigger than 1(This should be done in previous steps).");
     let mut gained_space: u64 = 0;
     let mut removed_files: usize = 0;
@@ -1250,54 +1173,47 @@ fn delete_files(vector: &[FileEntry], delete_method: &DeleteMethod, text_message
     let mut values = vector.iter().enumerate();
     let q_index = match delete_method {
         DeleteMethod::OneOldest | DeleteMethod::AllExceptNewest => values.max_by(|(_, l), (_, r)| l.modified_date.cmp(&r.modified_date)),
-        DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::HardLink => values.min_by(|(_, l), (_, r)| l.modified_date.cmp(&r.modified_date)),
+        DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::HardLink | DeleteMethod::SymLink | DeleteMethod::RelativeSymLink | DeleteMethod::Dedupe => values.min_by(|(_, l), (_, r)| l.modified_date.cmp(&r.modified_date)),
         DeleteMethod::None => values.next(),
     };
-    let q_index = q_index.map(|t| t.0).unwrap_or(0);
+    let q_index = match vector.iter().position(|fe| directories.is_in_reference_directory(&fe.path)) {
+        Some(reference_index) => reference_index,
+        None => q_index.map(|t| t.0).unwrap_or(0),
+    };
     let n = match delete_method {
         DeleteMethod::OneNewest | DeleteMethod::OneOldest => 1,
-        DeleteMethod::AllExceptNewest | DeleteMethod::AllExceptOldest | DeleteMethod::None | DeleteMethod::HardLink => usize::MAX,
+        DeleteMethod::AllExceptNewest | DeleteMethod::AllExceptOldest | DeleteMethod::None | DeleteMethod::HardLink | DeleteMethod::SymLink | DeleteMethod::RelativeSymLink | DeleteMethod::Dedupe => usize::MAX,
     };
     for (index, file) in vector.iter().enumerate() {
-        if q_index == index {
+        if q_index == index || directories.is_in_reference_directory(&file.path) {
             continue;
         } else if removed_files + failed_to_remove_files >= n {
             break;
         }
 
-        let r = match delete_method {
-            DeleteMethod::OneOldest | DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::AllExceptNewest => {
-                if dryrun {
-                    Ok(Some(format!("Delete {}", file.path.display())))
-                } else {
-                    fs::remove_file(&file.path).map(|_| None)
+        let action = match delete_method {
+            DeleteMethod::OneOldest | DeleteMethod::OneNewest | DeleteMethod::AllExceptOldest | DeleteMethod::AllExceptNewest => delete_action.clone(),
+            DeleteMethod::HardLink => FileAction::HardLink(vector[q_index].path.clone()),
+            DeleteMethod::SymLink | DeleteMethod::RelativeSymLink => {
+                if !directories.is_in_same_included_directory(&file.path, &vector[q_index].path) {
+                    text_messages.add_warning(MessageEntry::SymlinkAcrossRoots { path: file.path.clone() });
+                    failed_to_remove_files += 1;
+                    continue;
                 }
-            }
-            DeleteMethod::HardLink => {
-                let src = &vector[q_index].path;
-                if dryrun {
-                    Ok(Some(format!("Replace file {} with hard link to {}", file.path.display(), src.display())))
-                } else {
-                    make_hard_link(&src, &file.path).map(|_| None)
+                FileAction::SymLink {
+                    original: vector[q_index].path.clone(),
+                    relative: *delete_method == DeleteMethod::RelativeSymLink,
                 }
             }
-            DeleteMethod::None => Ok(None),
+            DeleteMethod::Dedupe => FileAction::Dedupe(vector[q_index].path.clone()),
+            DeleteMethod::None => continue,
         };
 
-        match r {
-            Err(e) => {
-                failed_to_remove_files += 1;
-                text_messages.warnings.push(format!("Failed to remove {} ({})", file.path.display(), e));
-            }
-            Ok(Some(msg)) => {
-                text_messages.messages.push(msg);
-                removed_files += 1;
-                gained_space += file.size;
-            }
-            Ok(None) => {
-                removed_files += 1;
-                gained_space += file.size;
-            }
+        if action.apply_and_report(&file.path, directories, dryrun, text_messages).is_failed() {
+            failed_to_remove_files += 1;
+        } else {
+            removed_files += 1;
+            gained_space += file.size;
         }
     }
     (gained_space, removed_files, failed_to_remove_files)
@@ -1323,51 +1239,27 @@ fn filter_hard_links(vec_file_entry: &[FileEntry]) -> Vec<FileEntry> {
     identical
 }
 
-pub fn make_hard_link(src: &Path, dst: &Path) -> io::Result<()> {
-    let dst_dir = dst.parent().ok_or_else(|| Error::new(ErrorKind::Other, "No parent"))?;
-    let temp = tempfile::Builder::new().tempfile_in(dst_dir)?;
-    fs::rename(dst, temp.path())?;
-    let result = fs::hard_link(src, dst);
-    if result.is_err() {
-        fs::rename(temp.path(), dst)?;
-    }
-    result
+fn save_hashes_to_file(hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, type_of_hash: &HashType, minimal_cache_file_size: u64, cache_location: &CacheLocation, volume_roots: &[PathBuf], cache_usage: CacheUsage) {
+    // Only cache bigger than 5MB files
+    let entries: Vec<&FileEntry> = hashmap.values().filter(|file_entry| file_entry.size >= minimal_cache_file_size).collect();
+    save_cache_to_file(
+        &entries,
+        cache_location,
+        volume_roots,
+        &get_cache_file_name(CACHE_FILE_NAME, type_of_hash),
+        &get_cache_file_name(LEGACY_CACHE_FILE_NAME, type_of_hash),
+        cache_usage,
+        text_messages,
+    );
 }
 
-fn save_hashes_to_file(hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, type_of_hash: &HashType, minimal_cache_file_size: u64) {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        if cache_dir.exists() {
-            if !cache_dir.is_dir() {
-                text_messages.messages.push(format!("Config dir {} is a file!", cache_dir.display()));
-                return;
-            }
-        } else if fs::create_dir_all(&cache_dir).is_err() {
-            text_messages.messages.push(format!("Cannot create config dir {}", cache_dir.display()));
-            return;
-        }
-        let cache_file = cache_dir.join(CACHE_FILE_NAME.replace(".", format!("_{:?}.", type_of_hash).as_str()));
-        let file_handler = match OpenOptions::new().truncate(true).write(true).create(true).open(&cache_file) {
-            Ok(t) => t,
-            Err(_) => {
-                text_messages.messages.push(format!("Cannot create or open cache file {}", cache_file.display()));
-                return;
-            }
-        };
-        let mut writer = BufWriter::new(file_handler);
-
-        for file_entry in hashmap.values() {
-            // Only cache bigger than 5MB files
-            if file_entry.size >= minimal_cache_file_size {
-                let string: String = format!("{}//{}//{}//{}", file_entry.path.display(), file_entry.size, file_entry.modified_date, file_entry.hash);
+/// Each hash type has its own cache file e.g. cache_duplicates_Blake3.bin
+fn get_cache_file_name(cache_file_name: &str, type_of_hash: &HashType) -> String {
+    cache_file_name.replace(".", format!("_{:?}.", type_of_hash).as_str())
+}
 
-                if writeln!(writer, "{}", string).is_err() {
-                    text_messages.messages.push(format!("Failed to save some data to cache file {}", cache_file.display()));
-                    return;
-                };
-            }
-        }
-    }
+pub(crate) fn get_all_cache_file_names() -> Vec<String> {
+    [HashType::Blake3, HashType::Crc32, HashType::Xxh3].iter().map(|hash_type| get_cache_file_name(CACHE_FILE_NAME, hash_type)).collect()
 }
 
 pub trait MyHasher {
@@ -1375,148 +1267,164 @@ pub trait MyHasher {
     fn finalize(&self) -> String;
 }
 
-fn hash_calculation(buffer: &mut [u8], file_entry: &FileEntry, hash_type: &HashType, limit: u64) -> Result<(String, u64), String> {
-    let mut file_handler = match File::open(&file_entry.path) {
-        Ok(t) => t,
-        Err(_) => return Err(format!("Unable to check hash of file {}", file_entry.path.display())),
+/// Returns hash and number of read bytes, `None` when search was stopped before whole file(or `limit` bytes) was read
+fn hash_calculation(buffer: &mut [u8], file_entry: &FileEntry, hash_type: &HashType, limit: u64, stop_token: &StopToken, progress: &ProgressCounters) -> Result<Option<(String, u64)>, MessageEntry> {
+    let hash_error = |e: io::Error| MessageEntry::Io {
+        operation: Operation::CalculateHash,
+        path: file_entry.path.clone(),
+        kind: e.kind(),
     };
+    progress.set_current_path(&file_entry.path);
+    let mut file_handler = File::open(&file_entry.path).map_err(hash_error)?;
     let hasher = &mut *hash_type.hasher();
     let mut current_file_read_bytes: u64 = 0;
     loop {
+        // Big files are read for a long time, so stop must be checked also here
+        if stop_token.is_stopped() {
+            return Ok(None);
+        }
         let n = match file_handler.read(buffer) {
             Ok(0) => break,
             Ok(t) => t,
-            Err(_) => return Err(format!("Error happened when checking hash of file {}", file_entry.path.display())),
+            Err(e) => return Err(hash_error(e)),
         };
 
         current_file_read_bytes += n as u64;
+        progress.add_bytes(n as u64);
         hasher.update(&buffer[..n]);
 
         if current_file_read_bytes >= limit {
             break;
         }
     }
-    Ok((hasher.finalize(), current_file_read_bytes))
+    progress.add_items(1);
+    Ok(Some((hasher.finalize(), current_file_read_bytes)))
 }
 
-fn load_hashes_from_file(text_messages: &mut Messages, type_of_hash: &HashType) -> Option<BTreeMap<u64, Vec<FileEntry>>> {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        let cache_file = cache_dir.join(CACHE_FILE_NAME.replace(".", format!("_{:?}.", type_of_hash).as_str()));
-        let file_handler = match OpenOptions::new().read(true).open(&cache_file) {
+/// Whole group is flagged, so it is enough to check its first file
+pub fn is_already_deduplicated(group: &[FileEntry]) -> bool {
+    matches!(group.first(), Some(fe) if fe.already_deduplicated)
+}
+
+fn load_hashes_from_file(text_messages: &mut Messages, type_of_hash: &HashType, cache_location: &CacheLocation, volume_roots: &[PathBuf]) -> Option<LoadedHashes> {
+    let (loaded_entries, moved_entries) = load_cache_from_file(
+        cache_location,
+        volume_roots,
+        &get_cache_file_name(CACHE_FILE_NAME, type_of_hash),
+        &get_cache_file_name(LEGACY_CACHE_FILE_NAME, type_of_hash),
+        load_hashes_from_legacy_file,
+        text_messages,
+    )?;
+
+    let mut hashmap_loaded_entries: BTreeMap<u64, Vec<FileEntry>> = Default::default();
+    for file_entry in loaded_entries {
+        hashmap_loaded_entries.entry(file_entry.size).or_default().push(file_entry);
+    }
+    Some((hashmap_loaded_entries, moved_entries))
+}
+
+/// Loads cache saved in text format by older versions of Czkawka
+fn load_hashes_from_legacy_file(cache_file: &Path, text_messages: &mut Messages) -> Option<Vec<FileEntry>> {
+    let file_handler = match OpenOptions::new().read(true).open(cache_file) {
+        Ok(t) => t,
+        Err(_) => return None,
+    };
+
+    let reader = BufReader::new(file_handler);
+
+    let mut loaded_entries: Vec<FileEntry> = Vec::new();
+
+    // Read the file line by line using the lines() iterator from std::io::BufRead.
+    for (index, line) in reader.lines().enumerate() {
+        let line = match line {
             Ok(t) => t,
-            Err(_) => {
-                // text_messages.messages.push(format!("Cannot find or open cache file {}", cache_file.display())); // This shouldn't be write to output
+            Err(e) => {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::ReadCache,
+                    path: cache_file.to_path_buf(),
+                    kind: e.kind(),
+                });
                 return None;
             }
         };
-
-        let reader = BufReader::new(file_handler);
-
-        let mut hashmap_loaded_entries: BTreeMap<u64, Vec<FileEntry>> = Default::default();
-
-        // Read the file line by line using the lines() iterator from std::io::BufRead.
-        for (index, line) in reader.lines().enumerate() {
-            let line = match line {
+        let uuu = line.split("//").collect::<Vec<&str>>();
+        if uuu.len() != 4 {
+            text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                path: cache_file.to_path_buf(),
+                line_number: index + 1,
+                line: line.clone(),
+                value: "data(too much or too low amount of data)",
+            });
+            continue;
+        }
+        loaded_entries.push(FileEntry {
+            path: PathBuf::from(uuu[0]),
+            size: match uuu[1].parse::<u64>() {
                 Ok(t) => t,
                 Err(_) => {
-                    text_messages.warnings.push(format!("Failed to load line number {} from cache file {}", index + 1, cache_file.display()));
-                    return None;
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "size value",
+                    });
+                    continue;
                 }
-            };
-            let uuu = line.split("//").collect::<Vec<&str>>();
-            if uuu.len() != 4 {
-                text_messages
-                    .warnings
-                    .push(format!("Found invalid data(too much or too low amount of data) in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                continue;
-            }
-            // Don't load cache data if destination file not exists
-            if Path::new(uuu[0]).exists() {
-                let file_entry = FileEntry {
-                    path: PathBuf::from(uuu[0]),
-                    size: match uuu[1].parse::<u64>() {
-                        Ok(t) => t,
-                        Err(_) => {
-                            text_messages.warnings.push(format!("Found invalid size value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                            continue;
-                        }
-                    },
-                    modified_date: match uuu[2].parse::<u64>() {
-                        Ok(t) => t,
-                        Err(_) => {
-                            text_messages.warnings.push(format!("Found invalid modified date value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                            continue;
-                        }
-                    },
-                    hash: uuu[3].to_string(),
-                };
-                hashmap_loaded_entries.entry(file_entry.size).or_insert_with(Vec::new);
-                hashmap_loaded_entries.get_mut(&file_entry.size).unwrap().push(file_entry);
-            }
-        }
-
-        return Some(hashmap_loaded_entries);
+            },
+            modified_date: match uuu[2].parse::<u64>() {
+                Ok(t) => t,
+                Err(_) => {
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "modified date value",
+                    });
+                    continue;
+                }
+            },
+            hash: uuu[3].to_string(),
+            already_deduplicated: false,
+        });
     }
 
-    text_messages.messages.push("Cannot find or open system config dir to save cache file".to_string());
-    None
+    Some(loaded_entries)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::{read_dir, File};
+    use std::fs::File;
     use std::io;
-    #[cfg(target_family = "windows")]
-    use std::os::fs::MetadataExt;
-    #[cfg(target_family = "unix")]
-    use std::os::unix::fs::MetadataExt;
-
-    #[cfg(target_family = "unix")]
-    fn assert_inode(before: &Metadata, after: &Metadata) {
-        assert_eq!(before.ino(), after.ino());
-    }
-    #[cfg(target_family = "windows")]
-    fn assert_inode(_: &Metadata, _: &Metadata) {}
 
     #[test]
-    fn test_make_hard_link() -> io::Result<()> {
+    fn test_delete_files_reference_directory() -> io::Result<()> {
         let dir = tempfile::Builder::new().tempdir()?;
-        let (src, dst) = (dir.path().join("a"), dir.path().join("b"));
-        File::create(&src)?;
-        let metadata = fs::metadata(&src)?;
-        File::create(&dst)?;
-
-        make_hard_link(&src, &dst)?;
-
-        assert_inode(&metadata, &fs::metadata(&dst)?);
-        assert_eq!(metadata.permissions(), fs::metadata(&dst)?.permissions());
-        assert_eq!(metadata.modified()?, fs::metadata(&dst)?.modified()?);
-        assert_inode(&metadata, &fs::metadata(&src)?);
-        assert_eq!(metadata.permissions(), fs::metadata(&src)?.permissions());
-        assert_eq!(metadata.modified()?, fs::metadata(&src)?.modified()?);
-
-        let mut actual = read_dir(&dir)?.map(|e| e.unwrap().path()).collect::<Vec<PathBuf>>();
-        actual.sort();
-        assert_eq!(vec![src, dst], actual);
-        Ok(())
-    }
-
-    #[test]
-    fn test_make_hard_link_fails() -> io::Result<()> {
-        let dir = tempfile::Builder::new().tempdir()?;
-        let (src, dst) = (dir.path().join("a"), dir.path().join("b"));
-        File::create(&dst)?;
-        let metadata = fs::metadata(&dst)?;
-
-        assert!(make_hard_link(&src, &dst).is_err());
+        fs::create_dir_all(dir.path().join("reference"))?;
+        let files: Vec<FileEntry> = ["reference/a", "reference/b", "c", "d"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let path = dir.path().join(name);
+                File::create(&path).unwrap();
+                FileEntry {
+                    path,
+                    modified_date: index as u64,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut directories = Directories::new();
+        directories.set_reference_directory(vec![dir.path().join("reference")], &mut Messages::new());
 
-        assert_inode(&metadata, &fs::metadata(&dst)?);
-        assert_eq!(metadata.permissions(), fs::metadata(&dst)?.permissions());
-        assert_eq!(metadata.modified()?, fs::metadata(&dst)?.modified()?);
+        let mut text_messages = Messages::new();
+        let (_, removed_files, failed_to_remove_files) = delete_files(&files, &DeleteMethod::AllExceptOldest, &FileAction::Delete, &directories, &mut text_messages, false);
+        assert_eq!((removed_files, failed_to_remove_files), (2, 0));
+        assert!(files[0].path.exists() && files[1].path.exists());
+        assert!(!files[2].path.exists() && !files[3].path.exists());
 
-        assert_eq!(vec![dst], read_dir(&dir)?.map(|e| e.unwrap().path()).collect::<Vec<PathBuf>>());
+        assert!(directories.all_in_reference_directories(files[..2].iter().map(|fe| fe.path.as_path())));
+        assert!(!directories.all_in_reference_directories(files.iter().map(|fe| fe.path.as_path())));
         Ok(())
     }
 
@@ -1561,7 +1469,7 @@ mod tests {
         let mut file = File::create(&src)?;
         file.write_all(b"aa")?;
         let e = FileEntry { path: src, ..Default::default() };
-        let r = hash_calculation(&mut buf, &e, &HashType::Blake3, 0).unwrap();
+        let r = hash_calculation(&mut buf, &e, &HashType::Blake3, 0, &StopToken::new(), &ProgressCounters::new()).unwrap().unwrap();
         assert_eq!(2, r.1);
         assert!(!r.0.is_empty());
         Ok(())
@@ -1575,22 +1483,155 @@ mod tests {
         let mut file = File::create(&src)?;
         file.write_all(b"aa")?;
         let e = FileEntry { path: src, ..Default::default() };
-        let r1 = hash_calculation(&mut buf, &e, &HashType::Blake3, 1).unwrap();
-        let r2 = hash_calculation(&mut buf, &e, &HashType::Blake3, 2).unwrap();
-        let r3 = hash_calculation(&mut buf, &e, &HashType::Blake3, u64::MAX).unwrap();
+        let r1 = hash_calculation(&mut buf, &e, &HashType::Blake3, 1, &StopToken::new(), &ProgressCounters::new()).unwrap().unwrap();
+        let r2 = hash_calculation(&mut buf, &e, &HashType::Blake3, 2, &StopToken::new(), &ProgressCounters::new()).unwrap().unwrap();
+        let r3 = hash_calculation(&mut buf, &e, &HashType::Blake3, u64::MAX, &StopToken::new(), &ProgressCounters::new()).unwrap().unwrap();
         assert_ne!(r1, r2);
         assert_eq!(r2, r3);
         Ok(())
     }
 
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn test_delete_files_relative_symlinks() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let (project, other) = (dir.path().join("project"), dir.path().join("other"));
+        fs::create_dir_all(project.join("docs"))?;
+        fs::create_dir_all(&other)?;
+        let files: Vec<FileEntry> = [project.join("a"), project.join("docs/b"), other.join("c")]
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                fs::write(path, "data").unwrap();
+                FileEntry {
+                    path: path.clone(),
+                    modified_date: index as u64,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut directories = Directories::new();
+        directories.included_directories = vec![project.clone(), other.clone()];
+
+        // Link to other included directory is never created
+        let mut text_messages = Messages::new();
+        let (_, removed_files, failed_to_remove_files) = delete_files(&files, &DeleteMethod::RelativeSymLink, &FileAction::Delete, &directories, &mut text_messages, false);
+        assert_eq!((removed_files, failed_to_remove_files), (1, 1));
+        assert_eq!(fs::read_link(project.join("docs/b"))?, PathBuf::from("../a"));
+        assert_eq!(fs::read_to_string(project.join("docs/b"))?, "data");
+        assert_eq!(text_messages.typed_warnings, vec![MessageEntry::SymlinkAcrossRoots { path: other.join("c") }]);
+        assert!(fs::symlink_metadata(other.join("c"))?.is_file());
+
+        // File changed after search is not replaced
+        fs::remove_file(project.join("docs/b"))?;
+        fs::write(project.join("docs/b"), "date")?;
+        let (_, removed_files, failed_to_remove_files) = delete_files(&files[..2], &DeleteMethod::SymLink, &FileAction::Delete, &directories, &mut text_messages, false);
+        assert_eq!((removed_files, failed_to_remove_files), (0, 1));
+        assert_eq!(text_messages.typed_warnings[1].io_error_kind(), Some(io::ErrorKind::InvalidData));
+        assert_eq!(fs::read_to_string(project.join("docs/b"))?, "date");
+
+        // Reference directory is separate included directory, but files inside it are never changed, so they may be linked
+        directories.reference_directories = vec![other.clone()];
+        let (_, removed_files, failed_to_remove_files) = delete_files(&[files[2].clone(), files[0].clone()], &DeleteMethod::SymLink, &FileAction::Delete, &directories, &mut text_messages, false);
+        assert_eq!((removed_files, failed_to_remove_files), (1, 0));
+        assert_eq!(fs::read_link(project.join("a"))?, other.join("c"));
+        Ok(())
+    }
+
     #[test]
     fn test_hash_calculation_invalid_file() -> io::Result<()> {
         let dir = tempfile::Builder::new().tempdir()?;
         let mut buf = [0u8; 1 << 10];
         let src = dir.path().join("a");
+        let e = FileEntry { path: src.clone(), ..Default::default() };
+        let r = hash_calculation(&mut buf, &e, &HashType::Blake3, 0, &StopToken::new(), &ProgressCounters::new()).unwrap_err();
+        assert!(!r.to_string().is_empty());
+        assert_eq!(r.path(), src);
+        assert_eq!(r.operation(), Some(Operation::CalculateHash));
+        assert_eq!(r.io_error_kind(), Some(io::ErrorKind::NotFound));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_calculation_stopped() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let mut buf = [0u8; 1 << 10];
+        let src = dir.path().join("a");
+        let mut file = File::create(&src)?;
+        file.write_all(b"aa")?;
         let e = FileEntry { path: src, ..Default::default() };
-        let r = hash_calculation(&mut buf, &e, &HashType::Blake3, 0).unwrap_err();
-        assert!(!r.is_empty());
+        let stop_token = StopToken::new();
+        stop_token.stop();
+        let progress = ProgressCounters::new();
+        let r = hash_calculation(&mut buf, &e, &HashType::Blake3, u64::MAX, &stop_token, &progress).unwrap();
+        assert_eq!(r, None);
+        assert_eq!(progress.items_checked(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_hash_mb_results() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        fs::write(dir.path().join("a"), "data")?;
+        fs::write(dir.path().join("b"), "data")?;
+
+        let mut finder = DuplicateFinder::new();
+        finder.set_scan_options(&ScanOptions {
+            included_directories: vec![dir.path().to_path_buf()],
+            ..ScanOptions::new()
+        });
+        finder.set_check_method(CheckingMethod::HashMb);
+        finder.set_minimal_file_size(1);
+        finder.set_use_cache(false);
+        finder.find_duplicates(None, None);
+
+        let hash = blake3::hash(b"data").to_hex().to_string();
+        let json_file = dir.path().join("results.json");
+        assert!(finder.save_results_to_file(&json_file.to_string_lossy()));
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_file)?).unwrap();
+        let group = saved["results"][0].as_array().unwrap();
+        assert_eq!(group.len(), 2);
+        assert!(group.iter().all(|e| e["hash"] == hash.as_str()));
+
+        let csv_file = dir.path().join("results.csv");
+        assert!(finder.save_results_to_file(&csv_file.to_string_lossy()));
+        let csv = fs::read_to_string(&csv_file)?;
+        assert_eq!(csv.lines().skip(1).filter(|l| l.ends_with(&format!("{},false", hash))).count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_already_deduplicated_group() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let group: Vec<FileEntry> = ["a", "b"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, "data").unwrap();
+                FileEntry {
+                    path,
+                    size: 4,
+                    already_deduplicated: true,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        let mut finder = DuplicateFinder::new();
+        finder.set_check_method(CheckingMethod::Hash);
+        finder.set_delete_method(DeleteMethod::AllExceptOldest);
+        finder.files_with_identical_hashes.insert(4, vec![group]);
+
+        // Flagged group is still in results, but its files are never removed
+        finder.delete_files();
+        assert!(dir.path().join("a").exists() && dir.path().join("b").exists());
+        assert_eq!(finder.get_information().number_of_removed_files, 0);
+        assert_eq!(finder.get_results().number_of_entries(), 2);
+
+        let json_file = dir.path().join("results.json");
+        assert!(finder.save_results_to_file(&json_file.to_string_lossy()));
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_file)?).unwrap();
+        assert!(saved["results"][0].as_array().unwrap().iter().all(|e| e["already_deduplicated"] == true));
         Ok(())
     }
 }
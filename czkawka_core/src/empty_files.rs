@@ -1,27 +1,22 @@
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
+use std::time::SystemTime;
 
 use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
 use crate::common_traits::*;
-use crossbeam_channel::Receiver;
+use serde::Serialize;
 use std::io::BufWriter;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub files_checked: usize,
-}
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum DeleteMethod {
@@ -29,9 +24,10 @@ pub enum DeleteMethod {
     Delete,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct FileEntry {
     pub path: PathBuf,
+    pub size: u64,
     pub modified_date: u64,
 }
 
@@ -56,8 +52,9 @@ pub struct EmptyFiles {
     directories: Directories,
     allowed_extensions: Extensions,
     excluded_items: ExcludedItems,
-    recursive_search: bool,
+    scan_options: ScanOptions,
     delete_method: DeleteMethod,
+    delete_action: FileAction,
     stopped_search: bool,
 }
 
@@ -66,20 +63,22 @@ impl EmptyFiles {
         Self {
             text_messages: Messages::new(),
             information: Info::new(),
-            recursive_search: true,
             allowed_extensions: Extensions::new(),
             directories: Directories::new(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             empty_files: vec![],
             delete_method: DeleteMethod::None,
+            delete_action: FileAction::Delete,
             stopped_search: false,
         }
     }
 
     /// Finding empty files, save results to internal struct variables
-    pub fn find_empty_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
-        if !self.check_files(stop_receiver, progress_sender) {
+    pub fn find_empty_files(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
+        if !self.check_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -107,8 +106,13 @@ impl EmptyFiles {
         self.delete_method = delete_method;
     }
 
+    /// Chooses what happens with found files when they are deleted, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
@@ -127,140 +131,43 @@ impl EmptyFiles {
     }
 
     /// Check files for any with size == 0
-    fn check_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning files",
+                current_stage: 0,
+                max_stage: 0,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        dir_traversal.set_file_filter(|fe| fe.size == 0);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 0,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                self.empty_files = entries
+                    .into_iter()
+                    .map(|fe| FileEntry {
+                        path: fe.path,
+                        size: fe.size,
+                        modified_date: fe.modified_date,
                     })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    }
-                    .to_lowercase();
-
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
-                            continue 'dir;
-                        }
-                    }
-                    // Checking files
-                    if metadata.len() == 0 {
-                        let current_file_name = current_folder.join(entry_data.file_name());
-                        if self.excluded_items.is_excluded(&current_file_name) {
-                            continue 'dir;
-                        }
-
-                        // Creating new file entry
-                        let fe: FileEntry = FileEntry {
-                            path: current_file_name.clone(),
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                    continue;
-                                } // Permissions Denied
-                            },
-                        };
-
-                        // Adding files to Vector
-                        self.empty_files.push(fe);
-                    }
-                }
+                    .collect();
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
         self.information.number_of_empty_files = self.empty_files.len();
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
 
         Common::print_time(start_time, SystemTime::now(), "check_files_size".to_string());
         true
@@ -273,9 +180,7 @@ impl EmptyFiles {
         match self.delete_method {
             DeleteMethod::Delete => {
                 for file_entry in &self.empty_files {
-                    if fs::remove_file(file_entry.path.clone()).is_err() {
-                        self.text_messages.warnings.push(file_entry.path.display().to_string());
-                    }
+                    self.delete_action.apply_and_report(&file_entry.path, &self.directories, false, &mut self.text_messages);
                 }
             }
             DeleteMethod::None => {
@@ -292,6 +197,39 @@ impl Default for EmptyFiles {
     }
 }
 
+impl Scanner for EmptyFiles {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_allowed_extensions(scan_options.allowed_extensions.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_empty_files(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Entries(
+            self.empty_files
+                .iter()
+                .map(|e| ResultEntry {
+                    path: e.path.clone(),
+                    size: Some(0),
+                    modified_date: e.modified_date,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for EmptyFiles {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -317,8 +255,9 @@ impl DebugPrint for EmptyFiles {
         println!("Excluded items - {:?}", self.excluded_items.items);
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Delete Method - {:?}", self.delete_method);
+        println!("Delete Action - {:?}", self.delete_action);
         println!("-----------------------------------------");
     }
 }
@@ -330,23 +269,44 @@ impl SaveResults for EmptyFiles {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["modified_date", "path"],
+                    self.empty_files.iter().map(|e| vec![format_csv_date(e.modified_date), e.path.to_string_lossy().to_string()]),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.empty_files, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push(format!("Failed to create file {}", file_name));
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
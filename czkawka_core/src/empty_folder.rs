@@ -1,53 +1,42 @@
 use crate::common::Common;
+use crate::common_actions::FileAction;
+pub use crate::common_dir_traversal::FolderEntry;
+use crate::common_dir_traversal::{Collect, DirTraversal, DirTraversalResult, FolderEmptiness};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
-use crossbeam_channel::Receiver;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
+use crate::common_traits::{DebugPrint, PrintResults, SaveResults, Scanner};
+use serde::Serialize;
 use std::collections::BTreeMap;
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub folders_checked: usize,
-}
-
-/// Enum with values which show if folder is empty.
-/// In function "optimize_folders" automatically "Maybe" is changed to "Yes", so it is not necessary to put it here
-#[derive(Eq, PartialEq, Copy, Clone)]
-enum FolderEmptiness {
-    No,
-    Maybe,
-}
-
-/// Struct assigned to each checked folder with parent path(used to ignore parent if children are not empty) and flag which shows if folder is empty
-#[derive(Clone)]
-pub struct FolderEntry {
-    parent_path: Option<PathBuf>, // Usable only when finding
-    is_empty: FolderEmptiness,
-    pub modified_date: u64,
-}
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 /// Struct to store most basics info about all folder
 pub struct EmptyFolder {
     information: Info,
     delete_folders: bool,
+    delete_action: FileAction,
     text_messages: Messages,
     excluded_items: ExcludedItems,
+    scan_options: ScanOptions,
     empty_folder_list: BTreeMap<PathBuf, FolderEntry>, // Path, FolderEntry
     directories: Directories,
     stopped_search: bool,
 }
 
+/// Empty folder as it is saved in JSON results
+#[derive(Serialize)]
+struct ExportedFolder<'a> {
+    path: &'a PathBuf,
+    modified_date: u64,
+}
+
 /// Info struck with helpful information's about results
 #[derive(Default)]
 pub struct Info {
@@ -66,8 +55,10 @@ impl EmptyFolder {
         Self {
             information: Default::default(),
             delete_folders: false,
+            delete_action: FileAction::Delete,
             text_messages: Messages::new(),
             excluded_items: Default::default(),
+            scan_options: ScanOptions::new(),
             empty_folder_list: Default::default(),
             directories: Directories::new(),
             stopped_search: false,
@@ -97,9 +88,10 @@ impl EmptyFolder {
         self.directories.set_excluded_directory(excluded_directory, &mut self.text_messages);
     }
     /// Public function used by CLI to search for empty folders
-    pub fn find_empty_folders(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+    pub fn find_empty_folders(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
         self.directories.optimize_directories(true, &mut self.text_messages);
-        if !self.check_for_empty_folders(stop_receiver, progress_sender) {
+        if !self.check_for_empty_folders(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -114,6 +106,11 @@ impl EmptyFolder {
         self.delete_folders = delete_folder;
     }
 
+    /// Chooses what happens with empty folders when they are deleted, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     /// Clean directory tree
     /// If directory contains only 2 empty folders, then this directory should be removed instead two empty folders inside because it will produce another empty folder.
     fn optimize_folders(&mut self) {
@@ -137,131 +134,38 @@ impl EmptyFolder {
 
     /// Function to check if folder are empty.
     /// Parameter initial_checking for second check before deleting to be sure that checked folder is still empty
-    fn check_for_empty_folders(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_for_empty_folders(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-        let mut folders_checked: BTreeMap<PathBuf, FolderEntry> = Default::default();
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_folder_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_folder_counter = atomic_folder_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 0,
-                        folders_checked: atomic_folder_counter.load(Ordering::Relaxed) as usize,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_checked.insert(
-                id.clone(),
-                FolderEntry {
-                    parent_path: None,
-                    is_empty: FolderEmptiness::Maybe,
-                    modified_date: 0,
-                },
-            );
-            folders_to_check.push(id.clone());
-        }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning folders",
+                current_stage: 0,
+                max_stage: 0,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_collect(Collect::EmptyFolders);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-            // Checked folder may be deleted or we may not have permissions to open it so we assume that this folder is not be empty
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    folders_checked.get_mut(&current_folder).unwrap().is_empty = FolderEmptiness::No;
-                    continue;
-                }
-            };
-
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        set_as_not_empty_folder(&mut folders_checked, &current_folder);
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        set_as_not_empty_folder(&mut folders_checked, &current_folder);
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                // If child is dir, still folder may be considered as empty if all children are only directories.
-                if metadata.is_dir() {
-                    atomic_folder_counter.fetch_add(1, Ordering::Relaxed);
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.excluded_items.is_excluded(&next_folder) || self.directories.is_excluded(&next_folder) {
-                        set_as_not_empty_folder(&mut folders_checked, &current_folder);
-                        continue 'dir;
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFolders { folder_entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                // We need to set empty folder list
+                for (name, folder_entry) in folder_entries {
+                    if folder_entry.is_empty != FolderEmptiness::No {
+                        self.empty_folder_list.insert(name, folder_entry);
                     }
-                    folders_to_check.push(next_folder.clone());
-                    folders_checked.insert(
-                        next_folder.clone(),
-                        FolderEntry {
-                            parent_path: Some(current_folder.clone()),
-                            is_empty: FolderEmptiness::Maybe,
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("Folder {} seems to be modified before Unix Epoch.", current_folder.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Failed to read modification date of folder {}", current_folder.display()));
-                                    // Can't read data, so assuming that is not empty
-                                    set_as_not_empty_folder(&mut folders_checked, &current_folder);
-                                    continue 'dir;
-                                }
-                            },
-                        },
-                    );
-                } else {
-                    set_as_not_empty_folder(&mut folders_checked, &current_folder)
                 }
             }
-        }
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
-
-        // We need to set empty folder list
-        #[allow(unused_mut)] // Used is later by Windows build
-        for (mut name, folder_entry) in folders_checked {
-            if folder_entry.is_empty != FolderEmptiness::No {
-                self.empty_folder_list.insert(name, folder_entry);
-            }
+            DirTraversalResult::SuccessFiles { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
 
         Common::print_time(start_time, SystemTime::now(), "check_for_empty_folder".to_string());
@@ -271,12 +175,9 @@ impl EmptyFolder {
     /// Deletes earlier found empty folders
     fn delete_empty_folders(&mut self) {
         let start_time: SystemTime = SystemTime::now();
-        // Folders may be deleted or require too big privileges
+        // Folders may be deleted or require too big privileges, also folders which got new files after search are not removed
         for name in self.empty_folder_list.keys() {
-            match fs::remove_dir_all(name) {
-                Ok(_) => (),
-                Err(_) => self.text_messages.warnings.push(format!("Failed to remove folder {}", name.display())),
-            };
+            self.delete_action.apply_and_report(name, &self.directories, false, &mut self.text_messages);
         }
 
         Common::print_time(start_time, SystemTime::now(), "delete_files".to_string());
@@ -288,28 +189,44 @@ impl EmptyFolder {
     }
 }
 
-fn set_as_not_empty_folder(folders_checked: &mut BTreeMap<PathBuf, FolderEntry>, current_folder: &Path) {
-    // Not folder so it may be a file or symbolic link so it isn't empty
-    folders_checked.get_mut(current_folder).unwrap().is_empty = FolderEmptiness::No;
-    let mut d = folders_checked.get_mut(current_folder).unwrap();
-    // Loop to recursively set as non empty this and all his parent folders
-    loop {
-        d.is_empty = FolderEmptiness::No;
-        if d.parent_path != None {
-            let cf = d.parent_path.clone().unwrap();
-            d = folders_checked.get_mut(&cf).unwrap();
-        } else {
-            break;
-        }
-    }
-}
-
 impl Default for EmptyFolder {
     fn default() -> Self {
         Self::new()
     }
 }
 
+impl Scanner for EmptyFolder {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_empty_folders(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Entries(
+            self.empty_folder_list
+                .iter()
+                .map(|(path, e)| ResultEntry {
+                    path: path.clone(),
+                    size: None,
+                    modified_date: e.modified_date,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for EmptyFolder {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -333,17 +250,41 @@ impl SaveResults for EmptyFolder {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let folders: Vec<ExportedFolder> = self.empty_folder_list.iter().map(|(path, entry)| ExportedFolder { path, modified_date: entry.modified_date }).collect();
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["modified_date", "path"],
+                    folders.iter().map(|e| vec![format_csv_date(e.modified_date), e.path.to_string_lossy().to_string()]),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &folders, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push("Failed to create file ".to_string() + file_name.as_str());
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(writer, "Results of searching {:?} with excluded directories {:?}", self.directories.included_directories, self.directories.excluded_directories).is_err() {
-            self.text_messages.errors.push("Failed to save results to file ".to_string() + file_name.as_str());
+        if let Err(e) = writeln!(writer, "Results of searching {:?} with excluded directories {:?}", self.directories.included_directories, self.directories.excluded_directories) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
@@ -1,27 +1,22 @@
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_dir_traversal::{Collect, DirTraversal, DirTraversalResult, MAX_NUMBER_OF_SYMLINK_JUMPS};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
 use crate::common_extensions::Extensions;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
 use crate::common_traits::*;
-use crossbeam_channel::Receiver;
+use serde::Serialize;
 use std::io::BufWriter;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub files_checked: usize,
-}
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum DeleteMethod {
@@ -29,15 +24,13 @@ pub enum DeleteMethod {
     Delete,
 }
 
-const MAX_NUMBER_OF_SYMLINK_JUMPS: i32 = 20;
-
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub enum ErrorType {
     InfiniteRecursion,
     NonExistentFile,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct FileEntry {
     pub symlink_path: PathBuf,
     pub destination_path: PathBuf,
@@ -66,8 +59,9 @@ pub struct InvalidSymlinks {
     directories: Directories,
     allowed_extensions: Extensions,
     excluded_items: ExcludedItems,
-    recursive_search: bool,
+    scan_options: ScanOptions,
     delete_method: DeleteMethod,
+    delete_action: FileAction,
     stopped_search: bool,
 }
 
@@ -76,19 +70,21 @@ impl InvalidSymlinks {
         Self {
             text_messages: Messages::new(),
             information: Info::new(),
-            recursive_search: true,
             allowed_extensions: Extensions::new(),
             directories: Directories::new(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             invalid_symlinks: vec![],
             delete_method: DeleteMethod::None,
+            delete_action: FileAction::Delete,
             stopped_search: false,
         }
     }
 
-    pub fn find_invalid_links(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
-        if !self.check_files(stop_receiver, progress_sender) {
+    pub fn find_invalid_links(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
+        if !self.check_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -116,8 +112,13 @@ impl InvalidSymlinks {
         self.delete_method = delete_method;
     }
 
+    /// Chooses what happens with found files when they are deleted, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
@@ -136,178 +137,45 @@ impl InvalidSymlinks {
     }
 
     /// Check files for any with size == 0
-    fn check_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 0,
-                        files_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                } else if metadata.file_type().is_symlink() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    }
-                    .to_lowercase();
-
-                    // Checking allowed extensions
-                    if !self.allowed_extensions.file_extensions.is_empty() {
-                        let allowed = self.allowed_extensions.file_extensions.iter().any(|e| file_name_lowercase.ends_with((".".to_string() + e.to_lowercase().as_str()).as_str()));
-                        if !allowed {
-                            // Not an allowed extension, ignore it.
-                            continue 'dir;
-                        }
-                    }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Scanning files",
+                current_stage: 0,
+                max_stage: 0,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_allowed_extensions(&self.allowed_extensions);
+        dir_traversal.set_collect(Collect::InvalidSymlinks);
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-                    // Checking files
-                    let current_file_name = current_folder.join(entry_data.file_name());
-                    if self.excluded_items.is_excluded(&current_file_name) {
-                        continue 'dir;
-                    }
-
-                    let mut destination_path = PathBuf::new();
-                    let type_of_error;
-
-                    match current_file_name.read_link() {
-                        Ok(t) => {
-                            destination_path.push(t);
-                            let mut number_of_loop = 0;
-                            let mut current_path = current_file_name.clone();
-                            loop {
-                                if number_of_loop == 0 && !current_path.exists() {
-                                    type_of_error = ErrorType::NonExistentFile;
-                                    break;
-                                }
-                                if number_of_loop == MAX_NUMBER_OF_SYMLINK_JUMPS {
-                                    type_of_error = ErrorType::InfiniteRecursion;
-                                    break;
-                                }
-
-                                current_path = match current_path.read_link() {
-                                    Ok(t) => t,
-                                    Err(_) => {
-                                        // Looks that some next symlinks are broken, but we do nothing with it
-                                        continue 'dir;
-                                    }
-                                };
-
-                                number_of_loop += 1;
-                            }
-                        }
-                        Err(_) => {
-                            // Failed to load info about it
-                            type_of_error = ErrorType::NonExistentFile;
-                        }
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    if let Some((destination_path, type_of_error)) = check_symlink(&fe.path) {
+                        self.invalid_symlinks.push(FileEntry {
+                            symlink_path: fe.path,
+                            destination_path,
+                            type_of_error,
+                            modified_date: fe.modified_date,
+                        });
                     }
-
-                    // Creating new file entry
-                    let fe: FileEntry = FileEntry {
-                        symlink_path: current_file_name.clone(),
-                        destination_path,
-                        type_of_error,
-                        modified_date: match metadata.modified() {
-                            Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                Ok(d) => d.as_secs(),
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                    0
-                                }
-                            },
-                            Err(_) => {
-                                self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                continue;
-                            } // Permissions Denied
-                        },
-                    };
-
-                    // Adding files to Vector
-                    self.invalid_symlinks.push(fe);
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
         self.information.number_of_invalid_symlinks = self.invalid_symlinks.len();
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
 
         Common::print_time(start_time, SystemTime::now(), "check_files_size".to_string());
         true
@@ -320,9 +188,7 @@ impl InvalidSymlinks {
         match self.delete_method {
             DeleteMethod::Delete => {
                 for file_entry in &self.invalid_symlinks {
-                    if fs::remove_file(file_entry.symlink_path.clone()).is_err() {
-                        self.text_messages.warnings.push(file_entry.symlink_path.display().to_string());
-                    }
+                    self.delete_action.apply_and_report(&file_entry.symlink_path, &self.directories, false, &mut self.text_messages);
                 }
             }
             DeleteMethod::None => {
@@ -333,12 +199,85 @@ impl InvalidSymlinks {
         Common::print_time(start_time, SystemTime::now(), "delete_files".to_string());
     }
 }
+/// Returns destination of symlink and type of error, or `None` when symlink is valid
+fn check_symlink(symlink_path: &Path) -> Option<(PathBuf, ErrorType)> {
+    let mut destination_path = PathBuf::new();
+    let type_of_error;
+
+    match symlink_path.read_link() {
+        Ok(t) => {
+            destination_path.push(t);
+            let mut number_of_loop = 0;
+            let mut current_path = symlink_path.to_path_buf();
+            loop {
+                if number_of_loop == 0 && !current_path.exists() {
+                    type_of_error = ErrorType::NonExistentFile;
+                    break;
+                }
+                if number_of_loop == MAX_NUMBER_OF_SYMLINK_JUMPS {
+                    type_of_error = ErrorType::InfiniteRecursion;
+                    break;
+                }
+
+                current_path = match current_path.read_link() {
+                    Ok(t) => t,
+                    Err(_) => {
+                        // Looks that some next symlinks are broken, but we do nothing with it
+                        return None;
+                    }
+                };
+
+                number_of_loop += 1;
+            }
+        }
+        Err(_) => {
+            // Failed to load info about it
+            type_of_error = ErrorType::NonExistentFile;
+        }
+    }
+
+    Some((destination_path, type_of_error))
+}
+
 impl Default for InvalidSymlinks {
     fn default() -> Self {
         Self::new()
     }
 }
 
+impl Scanner for InvalidSymlinks {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_allowed_extensions(scan_options.allowed_extensions.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_invalid_links(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Entries(
+            self.invalid_symlinks
+                .iter()
+                .map(|e| ResultEntry {
+                    path: e.symlink_path.clone(),
+                    size: None,
+                    modified_date: e.modified_date,
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for InvalidSymlinks {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -364,8 +303,9 @@ impl DebugPrint for InvalidSymlinks {
         println!("Excluded items - {:?}", self.excluded_items.items);
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Delete Method - {:?}", self.delete_method);
+        println!("Delete Action - {:?}", self.delete_action);
         println!("-----------------------------------------");
     }
 }
@@ -377,23 +317,55 @@ impl SaveResults for InvalidSymlinks {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["modified_date", "path", "destination_path", "type_of_error"],
+                    self.invalid_symlinks.iter().map(|e| {
+                        let type_of_error = match e.type_of_error {
+                            ErrorType::InfiniteRecursion => "Infinite Recursion",
+                            ErrorType::NonExistentFile => "Non Existent File",
+                        };
+                        vec![
+                            format_csv_date(e.modified_date),
+                            e.symlink_path.to_string_lossy().to_string(),
+                            e.destination_path.to_string_lossy().to_string(),
+                            type_of_error.to_string(),
+                        ]
+                    }),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.invalid_symlinks, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push(format!("Failed to create file {}", file_name));
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
             "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
             self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
@@ -7,16 +7,28 @@ pub mod duplicate;
 pub mod empty_files;
 pub mod empty_folder;
 pub mod invalid_symlinks;
+pub mod quarantine;
+pub mod results_import;
 pub mod same_music;
 pub mod similar_images;
 pub mod temporary;
 pub mod zeroed;
 
 pub mod common;
+pub mod common_actions;
+pub mod common_cache;
+pub mod common_dir_traversal;
 pub mod common_directory;
+pub mod common_export;
 pub mod common_extensions;
+pub mod common_extents;
+pub mod common_file_type;
+pub mod common_ignore_files;
 pub mod common_items;
 pub mod common_messages;
+pub mod common_progress;
+pub mod common_scanner;
+pub mod common_stop;
 pub mod common_traits;
 
 pub const CZKAWKA_VERSION: &str = env!("CARGO_PKG_VERSION");
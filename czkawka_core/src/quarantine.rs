@@ -0,0 +1,432 @@
+use crate::common::Common;
+use crate::common_actions::{get_free_path, move_path};
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_traits::{DebugPrint, PrintResults};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Every run which moves files to quarantine saves them in its own journal inside quarantine folder
+pub const JOURNAL_FILE_PREFIX: &str = "czkawka_journal_";
+
+/// Single file moved to quarantine, journal contains one entry in JSON in each line
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub original_path: PathBuf,
+    pub new_path: PathBuf,
+    pub size: u64,
+    pub hash: String, // Blake3, empty for folders and symlinks
+    pub timestamp: u64,
+}
+
+/// Folder to which files are moved instead of removing them, files keep their full original path inside it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quarantine {
+    directory: PathBuf,
+    journal_file: PathBuf,
+}
+
+impl Quarantine {
+    /// Journal name contains current time, so every run can be undone separately
+    /// Relative directory is resolved from current directory, so journal always contains absolute paths
+    pub fn new(directory: PathBuf) -> Self {
+        let directory = match directory.canonicalize() {
+            Ok(t) => t,
+            Err(_) if directory.is_relative() => std::env::current_dir().map(|d| d.join(&directory)).unwrap_or(directory),
+            Err(_) => directory,
+        };
+        let journal_file = directory.join(format!("{}{}.jsonl", JOURNAL_FILE_PREFIX, Local::now().format("%Y-%m-%d_%H-%M-%S")));
+        Self { directory, journal_file }
+    }
+
+    pub fn get_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn get_journal_file(&self) -> &Path {
+        &self.journal_file
+    }
+
+    /// e.g. `/home/rafal/a.txt` is moved to `<quarantine>/home/rafal/a.txt`, drive letter on Windows is used as first folder
+    pub fn get_quarantine_path(&self, original_path: &Path) -> PathBuf {
+        let mut new_path = self.directory.clone();
+        for component in original_path.components() {
+            match component {
+                Component::Prefix(prefix) => new_path.push(prefix.as_os_str().to_string_lossy().replace(|c: char| !c.is_alphanumeric(), "")),
+                Component::Normal(name) => new_path.push(name),
+                Component::RootDir | Component::CurDir | Component::ParentDir => {}
+            }
+        }
+        new_path
+    }
+
+    /// Moves file or folder to quarantine and saves it in journal, when journal cannot be saved file is moved back
+    pub(crate) fn move_to_quarantine(&self, path: &Path) -> io::Result<PathBuf> {
+        let original_path = get_absolute_path(path)?;
+        let metadata = fs::symlink_metadata(&original_path)?;
+        let (size, hash) = if metadata.is_file() { (metadata.len(), hash_file(&original_path)?) } else { (0, String::new()) };
+
+        let new_path = get_free_path(self.get_quarantine_path(&original_path));
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_path(&original_path, &new_path)?;
+
+        let entry = JournalEntry {
+            original_path,
+            new_path,
+            size,
+            hash,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+        if let Err(e) = self.save_entry(&entry) {
+            let _ = move_path(&entry.new_path, &entry.original_path);
+            return Err(e);
+        }
+        Ok(entry.new_path)
+    }
+
+    fn save_entry(&self, entry: &JournalEntry) -> io::Result<()> {
+        let mut journal = OpenOptions::new().create(true).append(true).open(&self.journal_file)?;
+        writeln!(journal, "{}", serde_json::to_string(entry)?)
+    }
+}
+
+/// Parent folder is canonicalized, so path doesn't contain `..` and symlink itself is not followed
+fn get_absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Path doesn't contain file name"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(parent.canonicalize()?.join(file_name))
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Info struck with helpful information's about restored files
+#[derive(Default)]
+pub struct Info {
+    pub number_of_entries: usize,
+    pub number_of_restored_files: usize,
+    pub number_of_failed_to_restore_files: usize,
+    pub number_of_skipped_files: usize,
+}
+impl Info {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// Moves files saved in journal back to their original place
+/// File is restored only when it wasn't changed inside quarantine and its original path is not used by other file
+pub struct UndoQuarantine {
+    text_messages: Messages,
+    information: Info,
+    journal_file: PathBuf,
+    entries: Vec<JournalEntry>,
+    dryrun: bool,
+}
+
+impl UndoQuarantine {
+    pub fn new() -> Self {
+        Self {
+            text_messages: Messages::new(),
+            information: Info::new(),
+            journal_file: PathBuf::new(),
+            entries: vec![],
+            dryrun: false,
+        }
+    }
+
+    pub const fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    pub const fn get_information(&self) -> &Info {
+        &self.information
+    }
+
+    pub const fn get_entries(&self) -> &Vec<JournalEntry> {
+        &self.entries
+    }
+
+    pub fn set_dryrun(&mut self, dryrun: bool) {
+        self.dryrun = dryrun;
+    }
+
+    pub fn load_journal(&mut self, journal_file: &str) -> bool {
+        let start_time: SystemTime = SystemTime::now();
+        self.journal_file = PathBuf::from(journal_file);
+        self.entries.clear();
+        let content = match fs::read_to_string(journal_file) {
+            Ok(t) => t,
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::ReadFile,
+                    path: self.journal_file.clone(),
+                    kind: e.kind(),
+                });
+                return false;
+            }
+        };
+
+        for (line_number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => self.entries.push(entry),
+                Err(e) => {
+                    self.text_messages.add_error(MessageEntry::ParseFailed {
+                        path: self.journal_file.clone(),
+                        line_number: Some(line_number + 1),
+                        reason: e.to_string(),
+                    });
+                    return false;
+                }
+            }
+        }
+        self.information.number_of_entries = self.entries.len();
+        Common::print_time(start_time, SystemTime::now(), "load_journal".to_string());
+        true
+    }
+
+    /// Files are restored in reverse order, journal is updated to contain only files which are still in quarantine
+    pub fn restore_files(&mut self) {
+        let start_time: SystemTime = SystemTime::now();
+        let mut remaining_entries = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if !can_be_restored(entry, &mut self.text_messages) {
+                self.information.number_of_skipped_files += 1;
+                remaining_entries.push(entry.clone());
+                continue;
+            }
+
+            if self.dryrun {
+                self.text_messages.messages.push(format!("Restore {} to {}", entry.new_path.display(), entry.original_path.display()));
+                self.information.number_of_restored_files += 1;
+                continue;
+            }
+
+            let result = match entry.original_path.parent() {
+                Some(parent) => fs::create_dir_all(parent),
+                None => Ok(()),
+            }
+            .and_then(|_| move_path(&entry.new_path, &entry.original_path));
+            match result {
+                Ok(()) => {
+                    self.information.number_of_restored_files += 1;
+                    remove_empty_parents(&entry.new_path, self.journal_file.parent().unwrap_or_else(|| Path::new("")));
+                }
+                Err(e) => {
+                    self.text_messages.add_warning(MessageEntry::Io {
+                        operation: Operation::MoveFile,
+                        path: entry.new_path.clone(),
+                        kind: e.kind(),
+                    });
+                    self.information.number_of_failed_to_restore_files += 1;
+                    remaining_entries.push(entry.clone());
+                }
+            }
+        }
+
+        if !self.dryrun {
+            remaining_entries.reverse();
+            self.save_remaining_entries(&remaining_entries);
+        }
+        Common::print_time(start_time, SystemTime::now(), "restore_files".to_string());
+    }
+
+    /// Journal without entries is removed
+    fn save_remaining_entries(&mut self, remaining_entries: &[JournalEntry]) {
+        let result = if remaining_entries.is_empty() {
+            fs::remove_file(&self.journal_file)
+        } else {
+            let mut content = String::new();
+            for entry in remaining_entries {
+                // Entries were loaded from JSON, so they can always be serialized back
+                content += &serde_json::to_string(entry).unwrap();
+                content.push('\n');
+            }
+            fs::write(&self.journal_file, content)
+        };
+        if let Err(e) = result {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: self.journal_file.clone(),
+                kind: e.kind(),
+            });
+        }
+    }
+}
+
+/// File must be unchanged in quarantine and its original path must be free
+fn can_be_restored(entry: &JournalEntry, text_messages: &mut Messages) -> bool {
+    let metadata = match fs::symlink_metadata(&entry.new_path) {
+        Ok(t) => t,
+        Err(e) => {
+            if e.kind() == ErrorKind::NotFound {
+                text_messages.add_warning(MessageEntry::ChangedSinceScan { path: entry.new_path.clone(), missing: true });
+            } else {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::ReadMetadata,
+                    path: entry.new_path.clone(),
+                    kind: e.kind(),
+                });
+            }
+            return false;
+        }
+    };
+    if metadata.is_file() {
+        let hash = match hash_file(&entry.new_path) {
+            Ok(t) => t,
+            Err(e) => {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::CalculateHash,
+                    path: entry.new_path.clone(),
+                    kind: e.kind(),
+                });
+                return false;
+            }
+        };
+        if metadata.len() != entry.size || hash != entry.hash {
+            text_messages.add_warning(MessageEntry::ChangedInQuarantine { path: entry.new_path.clone() });
+            return false;
+        }
+    }
+    if fs::symlink_metadata(&entry.original_path).is_ok() {
+        text_messages.add_warning(MessageEntry::RestoreTargetExists { path: entry.original_path.clone() });
+        return false;
+    }
+    true
+}
+
+/// Folders created inside quarantine for restored file are removed when nothing else is inside them
+fn remove_empty_parents(path: &Path, quarantine_directory: &Path) {
+    for parent in path.ancestors().skip(1) {
+        if !parent.starts_with(quarantine_directory) || parent == quarantine_directory || fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
+
+impl Default for UndoQuarantine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugPrint for UndoQuarantine {
+    #[allow(dead_code)]
+    #[allow(unreachable_code)]
+    /// Debugging printing - only available on debug build
+    fn debug_print(&self) {
+        #[cfg(not(debug_assertions))]
+        {
+            return;
+        }
+        println!("---------------DEBUG PRINT---------------");
+        println!("### Information's");
+
+        println!("Errors size - {}", self.text_messages.errors.len());
+        println!("Warnings size - {}", self.text_messages.warnings.len());
+        println!("Messages size - {}", self.text_messages.messages.len());
+        println!("Number of entries - {}", self.information.number_of_entries);
+        println!("Number of restored files - {}", self.information.number_of_restored_files);
+        println!("Number of failed to restore files - {}", self.information.number_of_failed_to_restore_files);
+        println!("Number of skipped files - {}", self.information.number_of_skipped_files);
+
+        println!("### Other");
+
+        println!("Journal file - {}", self.journal_file.display());
+        println!("Dry run - {}", self.dryrun);
+        println!("-----------------------------------------");
+    }
+}
+
+impl PrintResults for UndoQuarantine {
+    fn print_results(&self) {
+        println!("Loaded {} files from journal {}.", self.information.number_of_entries, self.journal_file.display());
+        println!(
+            "Restored {} files, failed to restore {} files, skipped {} files.",
+            self.information.number_of_restored_files, self.information.number_of_failed_to_restore_files, self.information.number_of_skipped_files
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use crate::common_actions::{ActionOutcome, FileAction};
+    use crate::common_directory::Directories;
+
+    #[test]
+    fn test_quarantine_and_undo() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let root = dir.path().canonicalize()?.join("root");
+        fs::create_dir_all(root.join("photos"))?;
+        let (photo, document) = (root.join("photos/photo.jpg"), root.join("document.txt"));
+        fs::write(&photo, "photo")?;
+        fs::write(&document, "document")?;
+
+        let quarantine = Quarantine::new(dir.path().canonicalize()?.join("quarantine"));
+        let action = FileAction::MoveToQuarantine(quarantine.clone());
+        for path in [&photo, &document].iter() {
+            let result = action.apply(path, &Directories::new(), false);
+            assert_eq!(
+                result.outcome,
+                ActionOutcome::Done {
+                    new_path: Some(quarantine.get_quarantine_path(path))
+                }
+            );
+            assert!(!path.exists());
+        }
+        assert!(quarantine.get_quarantine_path(&photo).ends_with("root/photos/photo.jpg"));
+
+        let mut undo = UndoQuarantine::new();
+        assert!(undo.load_journal(&quarantine.get_journal_file().to_string_lossy()));
+        assert_eq!(undo.get_entries().len(), 2);
+        assert_eq!(undo.get_entries()[0].original_path, photo);
+        assert_eq!(undo.get_entries()[0].size, 5);
+
+        // Changed file is left in quarantine together with its entry in journal
+        fs::write(quarantine.get_quarantine_path(&document), "changed")?;
+        undo.restore_files();
+        assert_eq!(undo.get_information().number_of_restored_files, 1);
+        assert_eq!(undo.get_information().number_of_skipped_files, 1);
+        assert_eq!(
+            undo.get_text_messages().typed_warnings,
+            vec![MessageEntry::ChangedInQuarantine {
+                path: quarantine.get_quarantine_path(&document)
+            }]
+        );
+        assert_eq!(fs::read_to_string(&photo)?, "photo");
+        assert!(!quarantine.get_directory().join(root.strip_prefix("/").unwrap()).join("photos").exists());
+
+        let mut undo = UndoQuarantine::new();
+        assert!(undo.load_journal(&quarantine.get_journal_file().to_string_lossy()));
+        assert_eq!(undo.get_entries().len(), 1);
+        assert_eq!(undo.get_entries()[0].original_path, document);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quarantine_relative_directory() -> io::Result<()> {
+        let quarantine = Quarantine::new(PathBuf::from("not_existing_quarantine"));
+        assert!(quarantine.get_directory().is_absolute());
+        assert_eq!(quarantine.get_directory(), std::env::current_dir()?.join("not_existing_quarantine"));
+        assert!(quarantine.get_quarantine_path(Path::new("/home/a.txt")).is_absolute());
+        Ok(())
+    }
+}
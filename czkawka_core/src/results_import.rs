@@ -0,0 +1,532 @@
+use crate::common::Common;
+use crate::common_actions::FileAction;
+use crate::common_directory::Directories;
+use crate::common_export::ExportFormat;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_traits::{DebugPrint, PrintResults};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Action chosen by user for file by editing saved results
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImportedAction {
+    None,
+    Keep,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedEntry {
+    pub path: PathBuf,
+    pub size: Option<u64>,          // May be missing in edited results
+    pub modified_date: Option<u64>, // May be missing in edited results
+    pub action: ImportedAction,
+    pub group: Option<usize>, // Index of group in tools which group files e.g. duplicates
+}
+
+/// Info struck with helpful information's about results
+#[derive(Default)]
+pub struct Info {
+    pub number_of_entries: usize,
+    pub number_of_entries_to_delete: usize,
+    pub number_of_removed_files: usize,
+    pub number_of_failed_to_remove_files: usize,
+    pub number_of_skipped_files: usize,
+}
+impl Info {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// Loads results saved earlier by any tool and applies actions chosen by user
+/// Before changing file, it is checked that it is still inside scanned directories and wasn't modified since saving results
+/// Files inside reference directories(saved in results or set by user) are never changed and at least one file from every group is kept
+pub struct ResultsImport {
+    text_messages: Messages,
+    information: Info,
+    directories: Directories,
+    entries: Vec<ImportedEntry>,
+    number_of_groups: usize,
+    delete_action: FileAction,
+    dryrun: bool,
+}
+
+impl ResultsImport {
+    pub fn new() -> Self {
+        Self {
+            text_messages: Messages::new(),
+            information: Info::new(),
+            directories: Directories::new(),
+            entries: vec![],
+            number_of_groups: 0,
+            delete_action: FileAction::Delete,
+            dryrun: false,
+        }
+    }
+
+    pub const fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    pub const fn get_information(&self) -> &Info {
+        &self.information
+    }
+
+    pub const fn get_entries(&self) -> &Vec<ImportedEntry> {
+        &self.entries
+    }
+
+    pub const fn get_included_directories(&self) -> &Vec<PathBuf> {
+        &self.directories.included_directories
+    }
+
+    pub const fn get_reference_directories(&self) -> &Vec<PathBuf> {
+        &self.directories.reference_directories
+    }
+
+    /// Adds reference directories to these saved in results file
+    pub fn set_reference_directory(&mut self, reference_directory: Vec<PathBuf>) {
+        self.directories.set_reference_directory(reference_directory, &mut self.text_messages);
+    }
+
+    pub fn set_dryrun(&mut self, dryrun: bool) {
+        self.dryrun = dryrun;
+    }
+
+    /// Chooses what happens with files marked to delete, by default they are permanently removed
+    pub fn set_delete_action(&mut self, delete_action: FileAction) {
+        self.delete_action = delete_action;
+    }
+
+    /// Loads results in JSON or JSON Lines format, format is chosen by extension of file
+    /// Text and CSV results don't contain size and modification date needed to check files before changing them, so they are refused
+    pub fn load_results(&mut self, file_name: &str) -> bool {
+        let start_time: SystemTime = SystemTime::now();
+        let content = match fs::read_to_string(file_name) {
+            Ok(t) => t,
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::ReadFile,
+                    path: PathBuf::from(file_name),
+                    kind: e.kind(),
+                });
+                return false;
+            }
+        };
+
+        self.directories.included_directories.clear();
+        self.entries.clear();
+        self.number_of_groups = 0;
+        let loaded = match ExportFormat::from_file_name(file_name) {
+            ExportFormat::Json => self.load_json(&content, file_name),
+            ExportFormat::JsonLines => self.load_json_lines(&content, file_name),
+            ExportFormat::Text | ExportFormat::Csv => {
+                self.text_messages.add_error(MessageEntry::UnsupportedResultsFormat { path: PathBuf::from(file_name), saving: false });
+                false
+            }
+        };
+        if !loaded {
+            return false;
+        }
+
+        if self.directories.included_directories.is_empty() {
+            self.text_messages.add_warning(MessageEntry::MissingScanRoots { path: PathBuf::from(file_name) });
+        }
+        self.information.number_of_entries = self.entries.len();
+        self.information.number_of_entries_to_delete = self.entries.iter().filter(|e| e.action == ImportedAction::Delete).count();
+        Common::print_time(start_time, SystemTime::now(), "load_results".to_string());
+        true
+    }
+
+    fn load_json(&mut self, content: &str, file_name: &str) -> bool {
+        let value: Value = match serde_json::from_str(content) {
+            Ok(t) => t,
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::ParseFailed {
+                    path: PathBuf::from(file_name),
+                    line_number: None,
+                    reason: e.to_string(),
+                });
+                return false;
+            }
+        };
+        match value {
+            Value::Object(ref object) if object.contains_key("results") => {
+                self.read_directories(&value);
+                self.collect_json_results(&object["results"]);
+            }
+            _ => self.collect_json_results(&value),
+        }
+        true
+    }
+
+    fn load_json_lines(&mut self, content: &str, file_name: &str) -> bool {
+        for (line_number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = match serde_json::from_str(line) {
+                Ok(t) => t,
+                Err(e) => {
+                    self.text_messages.add_error(MessageEntry::ParseFailed {
+                        path: PathBuf::from(file_name),
+                        line_number: Some(line_number + 1),
+                        reason: e.to_string(),
+                    });
+                    return false;
+                }
+            };
+            if value.get("included_directories").is_some() {
+                self.read_directories(&value);
+            } else {
+                self.collect_json_result(&value);
+            }
+        }
+        true
+    }
+
+    fn read_directories(&mut self, value: &Value) {
+        self.directories.included_directories = read_paths(value, "included_directories");
+        self.directories.reference_directories.extend(read_paths(value, "reference_directories"));
+    }
+
+    fn collect_json_results(&mut self, value: &Value) {
+        if let Value::Array(array) = value {
+            array.iter().for_each(|e| self.collect_json_result(e));
+        }
+    }
+
+    /// Each result is saved as single entry or group(array) of entries
+    fn collect_json_result(&mut self, value: &Value) {
+        match value {
+            Value::Array(array) => {
+                let group = self.number_of_groups;
+                self.number_of_groups += 1;
+                array.iter().for_each(|e| self.collect_json_entry(e, Some(group)));
+            }
+            _ => self.collect_json_entry(value, None),
+        }
+    }
+
+    fn collect_json_entry(&mut self, value: &Value, group: Option<usize>) {
+        let object = match value {
+            Value::Object(object) => object,
+            _ => return,
+        };
+        // Invalid symlinks tool saves path of symlink in other field
+        let path = match object.get("path").or_else(|| object.get("symlink_path")).and_then(Value::as_str) {
+            Some(t) => PathBuf::from(t),
+            None => return,
+        };
+        let action = match object.get("action").and_then(Value::as_str).map(str::to_lowercase).as_deref() {
+            Some("delete") => ImportedAction::Delete,
+            Some("keep") => ImportedAction::Keep,
+            None => ImportedAction::None,
+            Some(action) => {
+                self.text_messages.add_warning(MessageEntry::UnknownAction {
+                    path: path.clone(),
+                    action: action.to_string(),
+                });
+                ImportedAction::None
+            }
+        };
+        self.entries.push(ImportedEntry {
+            path,
+            size: object.get("size").and_then(Value::as_u64),
+            modified_date: object.get("modified_date").and_then(Value::as_u64),
+            action,
+            group,
+        });
+    }
+
+    /// Deletes files marked by user, files which were changed after saving results or which don't have saved size and modification date are skipped
+    pub fn apply_actions(&mut self) {
+        let start_time: SystemTime = SystemTime::now();
+        let included_directories: Vec<PathBuf> = self.directories.included_directories.iter().filter_map(|d| fs::canonicalize(d).ok()).collect();
+        // Groups in which at least one file is left, file is added when it is not removed
+        let mut groups_with_kept_file: HashSet<usize> = self.entries.iter().filter(|e| e.action != ImportedAction::Delete && e.path.exists()).filter_map(|e| e.group).collect();
+        for entry in self.entries.iter().filter(|e| e.action == ImportedAction::Delete) {
+            if !is_inside_directories(&entry.path, &included_directories) {
+                self.text_messages.add_warning(MessageEntry::OutsideScanRoots { path: entry.path.clone() });
+                self.information.number_of_skipped_files += 1;
+                continue;
+            }
+
+            let (size, modified_date) = match (entry.size, entry.modified_date) {
+                (Some(size), Some(modified_date)) => (size, modified_date),
+                _ => {
+                    self.text_messages.add_warning(MessageEntry::MissingSavedInfo { path: entry.path.clone() });
+                    self.information.number_of_skipped_files += 1;
+                    continue;
+                }
+            };
+
+            let metadata = match fs::symlink_metadata(&entry.path) {
+                Ok(t) => t,
+                Err(e) => {
+                    if e.kind() == ErrorKind::NotFound {
+                        self.text_messages.add_warning(MessageEntry::ChangedSinceScan { path: entry.path.clone(), missing: true });
+                    } else {
+                        self.text_messages.add_warning(MessageEntry::Io {
+                            operation: Operation::ReadMetadata,
+                            path: entry.path.clone(),
+                            kind: e.kind(),
+                        });
+                    }
+                    self.information.number_of_skipped_files += 1;
+                    continue;
+                }
+            };
+            // Size is checked only for files, because folders and symlinks are saved with size of 0 or without it
+            let size_changed = metadata.is_file() && size != metadata.len();
+            let date_changed = Some(modified_date) != metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs());
+            if size_changed || date_changed {
+                self.text_messages.add_warning(MessageEntry::ChangedSinceScan { path: entry.path.clone(), missing: false });
+                self.information.number_of_skipped_files += 1;
+                continue;
+            }
+
+            if let Some(group) = entry.group {
+                if groups_with_kept_file.insert(group) {
+                    self.text_messages.add_warning(MessageEntry::LastFileInGroup { path: entry.path.clone() });
+                    self.information.number_of_skipped_files += 1;
+                    continue;
+                }
+            }
+
+            // Only empty folders can be removed, so folder with new content is never removed
+            if self.delete_action.apply_and_report(&entry.path, &self.directories, self.dryrun, &mut self.text_messages).is_failed() {
+                self.information.number_of_failed_to_remove_files += 1;
+            } else {
+                self.information.number_of_removed_files += 1;
+            }
+        }
+        Common::print_time(start_time, SystemTime::now(), "apply_actions".to_string());
+    }
+}
+
+impl Default for ResultsImport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugPrint for ResultsImport {
+    #[allow(dead_code)]
+    #[allow(unreachable_code)]
+    /// Debugging printing - only available on debug build
+    fn debug_print(&self) {
+        #[cfg(not(debug_assertions))]
+        {
+            return;
+        }
+        println!("---------------DEBUG PRINT---------------");
+        println!("### Information's");
+
+        println!("Errors size - {}", self.text_messages.errors.len());
+        println!("Warnings size - {}", self.text_messages.warnings.len());
+        println!("Messages size - {}", self.text_messages.messages.len());
+        println!("Number of entries - {}", self.information.number_of_entries);
+        println!("Number of entries to delete - {}", self.information.number_of_entries_to_delete);
+        println!("Number of removed files - {}", self.information.number_of_removed_files);
+        println!("Number of failed to remove files - {}", self.information.number_of_failed_to_remove_files);
+        println!("Number of skipped files - {}", self.information.number_of_skipped_files);
+
+        println!("### Other");
+
+        println!("Included directories - {:?}", self.directories.included_directories);
+        println!("Reference directories - {:?}", self.directories.reference_directories);
+        println!("Delete action - {:?}", self.delete_action);
+        println!("Dry run - {}", self.dryrun);
+        println!("-----------------------------------------");
+    }
+}
+
+impl PrintResults for ResultsImport {
+    fn print_results(&self) {
+        println!("Loaded {} files, {} of them are marked to delete.", self.information.number_of_entries, self.information.number_of_entries_to_delete);
+        println!(
+            "Removed {} files, failed to remove {} files, skipped {} files.",
+            self.information.number_of_removed_files, self.information.number_of_failed_to_remove_files, self.information.number_of_skipped_files
+        );
+    }
+}
+
+fn read_paths(value: &Value, key: &str) -> Vec<PathBuf> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|directories| directories.iter().filter_map(Value::as_str).map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+/// Path must be absolute and cannot contain `..`, so it is not possible to leave scanned directories.
+/// Symlinks in parent folders are resolved, so file reachable by symlink to folder outside scanned directories is also outside, directories must be already canonicalized
+fn is_inside_directories(path: &Path, directories: &[PathBuf]) -> bool {
+    if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        return false;
+    }
+    let path = match (path.parent().map(fs::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(file_name)) => parent.join(file_name),
+        _ => return false,
+    };
+    directories.iter().any(|d| path.starts_with(d))
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io;
+
+    #[test]
+    fn test_import_json_results() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let root = dir.path().join("root");
+        fs::create_dir(&root)?;
+        for name in ["delete", "keep", "changed"].iter() {
+            fs::write(root.join(name), b"data")?;
+        }
+        let outside = dir.path().join("outside");
+        File::create(&outside)?;
+
+        // Size and modification date are taken from other file when saved path doesn't exist
+        let entry = |path: &Path, metadata_path: &Path, action: &str| {
+            let modified_date = fs::metadata(metadata_path).unwrap().modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            format!(r#"{{"path": "{}", "size": 4, "modified_date": {}, "action": "{}"}}"#, path.display(), modified_date, action)
+        };
+        let content = format!(
+            r#"{{"included_directories": ["{}"], "results": [[{}, {}], [{}, {}, {}, {}]]}}"#,
+            root.display(),
+            entry(&root.join("delete"), &root.join("delete"), "delete"),
+            entry(&root.join("keep"), &root.join("keep"), "keep"),
+            entry(&root.join("changed"), &root.join("changed"), "delete"),
+            entry(&outside, &root.join("keep"), "delete"),
+            entry(&root.join("../outside"), &root.join("keep"), "delete"),
+            entry(&root.join("missing"), &root.join("keep"), "delete"),
+        );
+        fs::write(root.join("changed"), b"new data")?;
+        let results_file = dir.path().join("results.json");
+        fs::write(&results_file, content)?;
+
+        let mut results_import = ResultsImport::new();
+        assert!(results_import.load_results(&results_file.to_string_lossy()));
+        assert_eq!(results_import.get_included_directories(), &vec![root.clone()]);
+        assert_eq!(results_import.get_information().number_of_entries, 6);
+        assert_eq!(results_import.get_information().number_of_entries_to_delete, 5);
+        results_import.apply_actions();
+
+        assert!(!root.join("delete").exists());
+        assert!(root.join("keep").exists());
+        assert!(root.join("changed").exists());
+        assert!(outside.exists());
+        assert_eq!(results_import.get_information().number_of_removed_files, 1);
+        assert_eq!(results_import.get_information().number_of_skipped_files, 4);
+        let warnings = &results_import.get_text_messages().typed_warnings;
+        assert_eq!(warnings[0], MessageEntry::ChangedSinceScan { path: root.join("changed"), missing: false });
+        assert_eq!(warnings[1], MessageEntry::OutsideScanRoots { path: outside.clone() });
+        assert_eq!(warnings[3], MessageEntry::ChangedSinceScan { path: root.join("missing"), missing: true });
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_text_results() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        File::create(dir.path().join("b"))?;
+        let content = format!("Results of searching [{:?}] with excluded directories [] and excluded items []\ndelete: {}/b\n", dir.path(), dir.path().display());
+        let results_file = dir.path().join("results.txt");
+        fs::write(&results_file, content)?;
+
+        // Text results don't contain size and modification date, so they cannot be used to change files
+        let mut results_import = ResultsImport::new();
+        assert!(!results_import.load_results(&results_file.to_string_lossy()));
+        assert!(results_import.get_entries().is_empty());
+        assert_eq!(results_import.get_text_messages().typed_errors, vec![MessageEntry::UnsupportedResultsFormat { path: results_file, saving: false }]);
+        assert!(dir.path().join("b").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_reference_directories() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let reference = dir.path().join("reference");
+        fs::create_dir(&reference)?;
+        File::create(reference.join("a"))?;
+        let modified_date = fs::metadata(reference.join("a"))?.modified()?.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let content = format!(
+            "{{\"included_directories\": [\"{0}\"], \"reference_directories\": [\"{1}\"]}}\n{{\"path\": \"{1}/a\", \"size\": 0, \"modified_date\": {2}, \"action\": \"delete\"}}\n",
+            dir.path().display(),
+            reference.display(),
+            modified_date
+        );
+        let results_file = dir.path().join("results.jsonl");
+        fs::write(&results_file, content)?;
+
+        let mut results_import = ResultsImport::new();
+        assert!(results_import.load_results(&results_file.to_string_lossy()));
+        assert_eq!(results_import.get_reference_directories(), &vec![reference.clone()]);
+        results_import.apply_actions();
+        assert!(reference.join("a").exists());
+        assert_eq!(results_import.get_information().number_of_failed_to_remove_files, 1);
+        assert_eq!(results_import.get_text_messages().typed_warnings, vec![MessageEntry::InReferenceDirectory { path: reference.join("a") }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_symlinked_folder_outside_root() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let root = dir.path().join("root");
+        let outside = dir.path().join("outside");
+        fs::create_dir(&root)?;
+        fs::create_dir(&outside)?;
+        fs::write(outside.join("a"), b"data")?;
+        std::os::unix::fs::symlink(&outside, root.join("link"))?;
+        let modified_date = fs::metadata(outside.join("a"))?.modified()?.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let content = format!(
+            r#"{{"included_directories": ["{}"], "results": [{{"path": "{}", "size": 4, "modified_date": {}, "action": "delete"}}]}}"#,
+            root.display(),
+            root.join("link/a").display(),
+            modified_date
+        );
+        let results_file = dir.path().join("results.json");
+        fs::write(&results_file, content)?;
+
+        let mut results_import = ResultsImport::new();
+        assert!(results_import.load_results(&results_file.to_string_lossy()));
+        results_import.apply_actions();
+        assert!(outside.join("a").exists());
+        assert_eq!(results_import.get_information().number_of_skipped_files, 1);
+        assert_eq!(results_import.get_text_messages().typed_warnings, vec![MessageEntry::OutsideScanRoots { path: root.join("link/a") }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_keeps_last_file_in_group() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let entry = |name: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, b"data").unwrap();
+            let modified_date = fs::metadata(&path).unwrap().modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            format!(r#"{{"path": "{}", "size": 4, "modified_date": {}, "action": "delete"}}"#, path.display(), modified_date)
+        };
+        let content = format!(r#"{{"included_directories": ["{}"], "results": [[{}, {}]]}}"#, dir.path().display(), entry("a"), entry("b"));
+        let results_file = dir.path().join("results.json");
+        fs::write(&results_file, content)?;
+
+        let mut results_import = ResultsImport::new();
+        assert!(results_import.load_results(&results_file.to_string_lossy()));
+        results_import.apply_actions();
+        // First file from group which would be removed is kept
+        assert!(dir.path().join("a").exists());
+        assert!(!dir.path().join("b").exists());
+        assert_eq!(results_import.get_information().number_of_removed_files, 1);
+        assert_eq!(results_import.get_text_messages().typed_warnings, vec![MessageEntry::LastFileInGroup { path: dir.path().join("a") }]);
+        Ok(())
+    }
+}
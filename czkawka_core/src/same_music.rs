@@ -1,30 +1,25 @@
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, thread};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::common::Common;
+use crate::common_dir_traversal;
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
+use crate::common_file_type::detect_type;
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
 use crate::common_traits::*;
-use audiotags::Tag;
-use crossbeam_channel::Receiver;
+use audiotags::{Tag, TagType};
 use rayon::prelude::*;
+use serde::Serialize;
 use std::collections::BTreeMap;
 use std::io::BufWriter;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub music_checked: usize,
-    pub music_to_check: usize,
-}
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum DeleteMethod {
@@ -47,7 +42,7 @@ bitflags! {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct FileEntry {
     pub size: u64,
 
@@ -87,8 +82,8 @@ pub struct SameMusic {
     duplicated_music_entries: Vec<Vec<FileEntry>>,
     directories: Directories,
     excluded_items: ExcludedItems,
+    scan_options: ScanOptions,
     minimal_file_size: u64,
-    recursive_search: bool,
     delete_method: DeleteMethod,
     music_similarity: MusicSimilarity,
     stopped_search: bool,
@@ -99,9 +94,9 @@ impl SameMusic {
         Self {
             text_messages: Messages::new(),
             information: Info::new(),
-            recursive_search: true,
             directories: Directories::new(),
             excluded_items: ExcludedItems::new(),
+            scan_options: ScanOptions::new(),
             music_entries: Vec::with_capacity(2048),
             delete_method: DeleteMethod::None,
             music_similarity: MusicSimilarity::NONE,
@@ -112,17 +107,18 @@ impl SameMusic {
         }
     }
 
-    pub fn find_same_music(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
-        self.directories.optimize_directories(self.recursive_search, &mut self.text_messages);
-        if !self.check_files(stop_receiver, progress_sender) {
+    pub fn find_same_music(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
+        self.directories.optimize_directories(self.scan_options.recursive_search, &mut self.text_messages);
+        if !self.check_files(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
-        if !self.check_records_multithreaded(stop_receiver, progress_sender) {
+        if !self.check_records_multithreaded(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
-        if !self.check_for_duplicates(stop_receiver, progress_sender) {
+        if !self.check_for_duplicates(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -154,7 +150,7 @@ impl SameMusic {
     }
 
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_included_directory(&mut self, included_directory: Vec<PathBuf>) -> bool {
@@ -165,6 +161,11 @@ impl SameMusic {
         self.directories.set_excluded_directory(excluded_directory, &mut self.text_messages);
     }
 
+    /// Files from reference directories are compared with other files, but groups with only such files are not shown
+    pub fn set_reference_directory(&mut self, reference_directory: Vec<PathBuf>) {
+        self.directories.set_reference_directory(reference_directory, &mut self.text_messages);
+    }
+
     pub fn set_excluded_items(&mut self, excluded_items: Vec<String>) {
         self.excluded_items.set_excluded_items(excluded_items, &mut self.text_messages);
     }
@@ -174,192 +175,85 @@ impl SameMusic {
     }
 
     /// Check files for any with size == 0
-    fn check_files(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_files(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
-
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Collecting music files",
+                current_stage: 0,
+                max_stage: 2,
+                ..Default::default()
+            },
+        );
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_minimal_file_size(self.minimal_file_size);
+        dir_traversal.set_file_filter(if self.scan_options.detect_by_content { is_music_content } else { is_music_file });
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 2,
-                        music_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        music_to_check: 0,
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                self.music_to_check = entries
+                    .into_iter()
+                    .map(|fe| FileEntry {
+                        size: fe.size,
+                        path: fe.path,
+                        modified_date: fe.modified_date,
+                        title: "".to_string(),
+
+                        artist: "".to_string(),
+                        album_title: "".to_string(),
+                        album_artist: "".to_string(),
+                        year: 0,
                     })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue 'dir;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue 'dir;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) || self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                    // Checking files
-                    if metadata.len() >= self.minimal_file_size {
-                        let current_file_name = current_folder.join(entry_data.file_name());
-                        if self.excluded_items.is_excluded(&current_file_name) {
-                            continue 'dir;
-                        }
-
-                        let allowed_extensions = [".mp3", ".flac", ".m4a"];
-
-                        if !allowed_extensions.iter().any(|r| current_file_name.to_string_lossy().ends_with(r)) {
-                            continue 'dir;
-                        }
-
-                        // Creating new file entry
-                        let file_entry: FileEntry = FileEntry {
-                            size: metadata.len(),
-                            path: current_file_name.clone(),
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                    continue 'dir;
-                                } // Permissions Denied
-                            },
-                            title: "".to_string(),
-
-                            artist: "".to_string(),
-                            album_title: "".to_string(),
-                            album_artist: "".to_string(),
-                            year: 0,
-                        };
-
-                        // Adding files to Vector
-                        self.music_to_check.push(file_entry);
-                    }
-                }
+                    .collect();
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
         self.information.number_of_music_entries = self.music_entries.len();
 
         Common::print_time(start_time, SystemTime::now(), "check_files".to_string());
         true
     }
 
-    fn check_records_multithreaded(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_records_multithreaded(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
 
-        let check_was_breaked = AtomicBool::new(false); // Used for breaking from GUI and ending check thread
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let music_to_check = self.music_to_check.len();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 1,
-                        max_stage: 2,
-                        music_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        music_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Reading tags",
+                current_stage: 1,
+                max_stage: 2,
+                items_to_check: self.music_to_check.len(),
+                ..Default::default()
+            },
+        );
 
         let vec_file_entry = self
             .music_to_check
             .par_iter()
             .map(|file_entry| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    check_was_breaked.store(true, Ordering::Relaxed);
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     return None;
                 }
                 let mut file_entry = file_entry.clone();
+                progress_thread.counters().set_current_path(&file_entry.path);
 
-                let tag = match Tag::new().read_from_path(&file_entry.path) {
+                let mut tag_reader = Tag::new();
+                if self.scan_options.detect_by_content {
+                    if let Some(tag_type) = get_tag_type_by_content(&file_entry.path) {
+                        tag_reader = tag_reader.with_tag_type(tag_type);
+                    }
+                }
+                let tag = match tag_reader.read_from_path(&file_entry.path) {
                     Ok(t) => t,
                     Err(_) => return Some(None), // Data not in utf-8, etc.
                 };
@@ -390,11 +284,10 @@ impl SameMusic {
             .collect::<Vec<_>>();
 
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
 
-        // Check if user aborted search(only from GUI)
-        if check_was_breaked.load(Ordering::Relaxed) {
+        // Workers end early after stop, so results are incomplete
+        if stop_token.is_stopped() {
             return false;
         }
 
@@ -408,53 +301,31 @@ impl SameMusic {
 
         true
     }
-    fn check_for_duplicates(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_for_duplicates(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         if MusicSimilarity::NONE == self.music_similarity {
             panic!("This can't be none");
         }
         let start_time: SystemTime = SystemTime::now();
 
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let music_to_check = self.music_to_check.len();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 2,
-                        max_stage: 2,
-                        music_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        music_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Comparing tags",
+                current_stage: 2,
+                max_stage: 2,
+                ..Default::default()
+            },
+        );
 
         let mut old_duplicates: Vec<Vec<FileEntry>> = vec![self.music_entries.clone()];
         let mut new_duplicates: Vec<Vec<FileEntry>> = Vec::new();
 
         if (self.music_similarity & MusicSimilarity::TITLE) == MusicSimilarity::TITLE {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_thread.stop();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
@@ -477,11 +348,10 @@ impl SameMusic {
 
         if (self.music_similarity & MusicSimilarity::ARTIST) == MusicSimilarity::ARTIST {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_thread.stop();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
@@ -504,11 +374,10 @@ impl SameMusic {
 
         if (self.music_similarity & MusicSimilarity::ALBUM_TITLE) == MusicSimilarity::ALBUM_TITLE {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_thread.stop();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
@@ -531,11 +400,10 @@ impl SameMusic {
 
         if (self.music_similarity & MusicSimilarity::ALBUM_ARTIST) == MusicSimilarity::ALBUM_ARTIST {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_thread.stop();
                     return false;
                 }
                 let mut hash_map: BTreeMap<String, Vec<FileEntry>> = Default::default();
@@ -558,11 +426,10 @@ impl SameMusic {
 
         if (self.music_similarity & MusicSimilarity::YEAR) == MusicSimilarity::YEAR {
             for vec_file_entry in old_duplicates {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+                progress_thread.counters().add_items(1);
+                if stop_token.is_stopped() {
                     // End thread which send info to gui
-                    progress_thread_run.store(false, Ordering::Relaxed);
-                    progress_thread_handle.join().unwrap();
+                    progress_thread.stop();
                     return false;
                 }
                 let mut hash_map: BTreeMap<i32, Vec<FileEntry>> = Default::default();
@@ -583,14 +450,14 @@ impl SameMusic {
             // new_duplicates = Vec::new();
         }
 
+        old_duplicates.retain(|vec| !self.directories.all_in_reference_directories(vec.iter().map(|fe| fe.path.as_path())));
         self.duplicated_music_entries = old_duplicates;
 
         for vec in &self.duplicated_music_entries {
             self.information.number_of_duplicates_music_files += vec.len() - 1;
         }
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
 
         Common::print_time(start_time, SystemTime::now(), "check_for_duplicates".to_string());
 
@@ -627,12 +494,71 @@ impl SameMusic {
         Common::print_time(start_time, SystemTime::now(), "delete_files".to_string());
     }
 }
+fn is_music_file(fe: &common_dir_traversal::FileEntry) -> bool {
+    let allowed_extensions = [".mp3", ".flac", ".m4a"];
+    let file_name_lowercase = fe.path.to_string_lossy().to_lowercase();
+    allowed_extensions.iter().any(|r| file_name_lowercase.ends_with(r))
+}
+
+/// Files with music extension are also checked, because their content may not be recognized
+fn is_music_content(fe: &common_dir_traversal::FileEntry) -> bool {
+    is_music_file(fe) || get_tag_type_by_content(&fe.path).is_some()
+}
+
+/// Library with tags chooses format by extension, so it must be given directly when file may have wrong extension
+fn get_tag_type_by_content(path: &Path) -> Option<TagType> {
+    match detect_type(path)?.extension {
+        "mp3" => Some(TagType::Id3v2),
+        "flac" => Some(TagType::Flac),
+        "m4a" => Some(TagType::Mp4),
+        _ => None,
+    }
+}
+
 impl Default for SameMusic {
     fn default() -> Self {
         Self::new()
     }
 }
 
+impl Scanner for SameMusic {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_reference_directory(scan_options.reference_directories.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_same_music(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Groups(
+            self.duplicated_music_entries
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|e| ResultEntry {
+                            path: e.path.clone(),
+                            size: Some(e.size),
+                            modified_date: e.modified_date,
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for SameMusic {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -660,7 +586,7 @@ impl DebugPrint for SameMusic {
         println!("Found duplicated files music - {}", self.duplicated_music_entries.len());
         println!("Included directories - {:?}", self.directories.included_directories);
         println!("Excluded directories - {:?}", self.directories.excluded_directories);
-        println!("Recursive search - {}", self.recursive_search.to_string());
+        println!("Scan options - {:?}", self.scan_options);
         println!("Delete Method - {:?}", self.delete_method);
         println!("-----------------------------------------");
     }
@@ -673,23 +599,58 @@ impl SaveResults for SameMusic {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["group", "size", "modified_date", "path", "title", "artist", "album_title", "album_artist", "year"],
+                    self.duplicated_music_entries.iter().enumerate().flat_map(|(group_id, group)| {
+                        group.iter().map(move |e| {
+                            vec![
+                                (group_id + 1).to_string(),
+                                e.size.to_string(),
+                                format_csv_date(e.modified_date),
+                                e.path.to_string_lossy().to_string(),
+                                e.title.clone(),
+                                e.artist.clone(),
+                                e.album_title.clone(),
+                                e.album_artist.clone(),
+                                e.year.to_string(),
+                            ]
+                        })
+                    }),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.duplicated_music_entries, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push(format!("Failed to create file {}", file_name));
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
-            "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
-            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+            "Results of searching {:?} with excluded directories {:?} and excluded items {:?} and reference directories {:?}",
+            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items, self.directories.reference_directories
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
@@ -1,41 +1,38 @@
 use crate::common::Common;
+use crate::common_cache::{get_cache_volume_roots, load_cache_from_file, save_cache_to_file, CacheEntry, CacheLocation, CacheUsage, MovedEntries};
+use crate::common_dir_traversal;
+use crate::common_dir_traversal::{DirTraversal, DirTraversalResult};
 use crate::common_directory::Directories;
+use crate::common_export::{format_csv_date, save_results_to_csv, save_results_to_json, ExportFormat};
+use crate::common_file_type::{detect_type, open_image};
 use crate::common_items::ExcludedItems;
-use crate::common_messages::Messages;
-use crate::common_traits::{DebugPrint, PrintResults, SaveResults};
+use crate::common_messages::{MessageEntry, Messages, Operation};
+use crate::common_progress::{ProgressData, ProgressThread};
+use crate::common_scanner::{ResultEntry, ScanOptions, ScanResults};
+use crate::common_stop::StopToken;
+use crate::common_traits::{DebugPrint, PrintResults, SaveResults, Scanner};
 use bk_tree::BKTree;
-use crossbeam_channel::Receiver;
-use directories_next::ProjectDirs;
 use humansize::{file_size_opts as options, FileSize};
 use image::GenericImageView;
 use img_hash::HasherConfig;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fs::File;
 use std::fs::OpenOptions;
-use std::fs::{File, Metadata};
 use std::io::Write;
 use std::io::*;
+use std::mem;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::{fs, mem, thread};
+use std::time::SystemTime;
 
 /// Type to store for each entry in the similarity BK-tree.
 type Node = [u8; 8];
 
-const CACHE_FILE_NAME: &str = "cache_similar_image.txt";
+pub(crate) const CACHE_FILE_NAME: &str = "cache_similar_image.bin";
+const LEGACY_CACHE_FILE_NAME: &str = "cache_similar_image.txt";
 
-#[derive(Debug)]
-pub struct ProgressData {
-    pub current_stage: u8,
-    pub max_stage: u8,
-    pub images_checked: usize,
-    pub images_to_check: usize,
-}
-
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Serialize, Deserialize)]
 pub enum Similarity {
     None,
     Minimal,
@@ -46,7 +43,7 @@ pub enum Similarity {
     VeryHigh,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileEntry {
     pub path: PathBuf,
     pub size: u64,
@@ -56,6 +53,21 @@ pub struct FileEntry {
     pub similarity: Similarity,
 }
 
+impl CacheEntry for FileEntry {
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+    fn get_size(&self) -> u64 {
+        self.size
+    }
+    fn get_modified_date(&self) -> u64 {
+        self.modified_date
+    }
+}
+
 /// Distance metric to use with the BK-tree.
 struct Hamming;
 
@@ -71,15 +83,16 @@ pub struct SimilarImages {
     text_messages: Messages,
     directories: Directories,
     excluded_items: ExcludedItems,
+    scan_options: ScanOptions,
     bktree: BKTree<Node, Hamming>,
     similar_vectors: Vec<Vec<FileEntry>>,
-    recursive_search: bool,
     minimal_file_size: u64,
     image_hashes: BTreeMap<Node, Vec<FileEntry>>, // Hashmap with image hashes and Vector with names of files
     stopped_search: bool,
     similarity: Similarity,
     images_to_check: BTreeMap<String, FileEntry>,
     use_cache: bool,
+    cache_location: CacheLocation,
 }
 
 /// Info struck with helpful information's about results
@@ -104,15 +117,16 @@ impl SimilarImages {
             text_messages: Messages::new(),
             directories: Directories::new(),
             excluded_items: Default::default(),
+            scan_options: ScanOptions::new(),
             bktree: BKTree::new(Hamming),
             similar_vectors: vec![],
-            recursive_search: true,
             minimal_file_size: 1024 * 16, // 16 KB should be enough to exclude too small images from search
             image_hashes: Default::default(),
             stopped_search: false,
             similarity: Similarity::High,
             images_to_check: Default::default(),
             use_cache: true,
+            cache_location: CacheLocation::new(),
         }
     }
 
@@ -136,8 +150,17 @@ impl SimilarImages {
         self.use_cache = use_cache;
     }
 
+    /// `None` means default cache folder
+    pub fn set_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.cache_location.cache_dir = cache_dir;
+    }
+
+    pub fn set_portable_cache(&mut self, portable_cache: bool) {
+        self.cache_location.portable = portable_cache;
+    }
+
     pub fn set_recursive_search(&mut self, recursive_search: bool) {
-        self.recursive_search = recursive_search;
+        self.scan_options.recursive_search = recursive_search;
     }
 
     pub fn set_minimal_file_size(&mut self, minimal_file_size: u64) {
@@ -151,13 +174,14 @@ impl SimilarImages {
     }
 
     /// Public function used by CLI to search for empty folders
-    pub fn find_similar_images(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+    pub fn find_similar_images(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        let stop_token = &stop_token.cloned().unwrap_or_default();
         self.directories.optimize_directories(true, &mut self.text_messages);
-        if !self.check_for_similar_images(stop_receiver, progress_sender) {
+        if !self.check_for_similar_images(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
-        if !self.sort_images(stop_receiver, progress_sender) {
+        if !self.sort_images(stop_token, progress_sender) {
             self.stopped_search = true;
             return;
         }
@@ -173,146 +197,46 @@ impl SimilarImages {
 
     /// Function to check if folder are empty.
     /// Parameter initial_checking for second check before deleting to be sure that checked folder is still empty
-    fn check_for_similar_images(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn check_for_similar_images(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let start_time: SystemTime = SystemTime::now();
-        let mut folders_to_check: Vec<PathBuf> = Vec::with_capacity(1024 * 2); // This should be small enough too not see to big difference and big enough to store most of paths without needing to resize vector
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Collecting images",
+                current_stage: 0,
+                max_stage: 1,
+                ..Default::default()
+            },
+        );
+
+        let mut dir_traversal = DirTraversal::new(&self.directories, &self.excluded_items);
+        dir_traversal.set_scan_options(&self.scan_options);
+        dir_traversal.set_minimal_file_size(self.minimal_file_size);
+        dir_traversal.set_file_filter(if self.scan_options.detect_by_content { is_image_content } else { is_image_file });
+        let dir_traversal_result = dir_traversal.run(stop_token, progress_thread.counters());
 
-        // Add root folders for finding
-        for id in &self.directories.included_directories {
-            folders_to_check.push(id.clone());
-        }
-
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 0,
-                        max_stage: 1,
-                        images_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        images_to_check: 0,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
-
-        while !folders_to_check.is_empty() {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                // End thread which send info to gui
-                progress_thread_run.store(false, Ordering::Relaxed);
-                progress_thread_handle.join().unwrap();
-                return false;
-            }
-            let current_folder = folders_to_check.pop().unwrap();
-
-            // Read current dir, if permission are denied just go to next
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    self.text_messages.warnings.push(format!("Cannot open dir {}", current_folder.display()));
-                    continue;
-                } // Permissions denied
-            };
-
-            // Check every sub folder/file/link etc.
-            'dir: for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read entry in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        self.text_messages.warnings.push(format!("Cannot read metadata in dir {}", current_folder.display()));
-                        continue;
-                    } //Permissions denied
-                };
-                if metadata.is_dir() {
-                    if !self.recursive_search {
-                        continue;
-                    }
-
-                    let next_folder = current_folder.join(entry_data.file_name());
-                    if self.directories.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    if self.excluded_items.is_excluded(&next_folder) {
-                        continue 'dir;
-                    }
-
-                    folders_to_check.push(next_folder);
-                } else if metadata.is_file() {
-                    atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-
-                    let file_name_lowercase: String = match entry_data.file_name().into_string() {
-                        Ok(t) => t,
-                        Err(_) => continue,
-                    }
-                    .to_lowercase();
-
-                    // Checking allowed image extensions
-                    let allowed_image_extensions = [".jpg", ".jpeg", ".png", ".bmp", ".tiff", ".tif", ".pnm", ".tga", ".ff", ".gif", ".jif", ".jfi"];
-                    if !allowed_image_extensions.iter().any(|e| file_name_lowercase.ends_with(e)) {
-                        continue 'dir;
-                    }
-
-                    // Checking files
-                    if metadata.len() >= self.minimal_file_size {
-                        let current_file_name = current_folder.join(entry_data.file_name());
-                        if self.excluded_items.is_excluded(&current_file_name) {
-                            continue 'dir;
-                        }
-
-                        let fe: FileEntry = FileEntry {
-                            path: current_file_name.clone(),
-                            size: metadata.len(),
-                            dimensions: "".to_string(),
-                            modified_date: match metadata.modified() {
-                                Ok(t) => match t.duration_since(UNIX_EPOCH) {
-                                    Ok(d) => d.as_secs(),
-                                    Err(_) => {
-                                        self.text_messages.warnings.push(format!("File {} seems to be modified before Unix Epoch.", current_file_name.display()));
-                                        0
-                                    }
-                                },
-                                Err(_) => {
-                                    self.text_messages.warnings.push(format!("Unable to get modification date from file {}", current_file_name.display()));
-                                    continue;
-                                } // Permissions Denied
-                            },
-
-                            hash: [0; 8],
-                            similarity: Similarity::None,
-                        };
-
-                        self.images_to_check.insert(current_file_name.to_string_lossy().to_string(), fe);
-                    }
+        // End thread which send info to gui
+        progress_thread.stop();
+
+        match dir_traversal_result {
+            DirTraversalResult::SuccessFiles { entries, warnings } => {
+                self.text_messages.extend_warnings(warnings);
+                for fe in entries {
+                    let fe = FileEntry {
+                        path: fe.path,
+                        size: fe.size,
+                        dimensions: "".to_string(),
+                        modified_date: fe.modified_date,
+
+                        hash: [0; 8],
+                        similarity: Similarity::None,
+                    };
+                    self.images_to_check.insert(fe.path.to_string_lossy().to_string(), fe);
                 }
             }
+            DirTraversalResult::SuccessFolders { .. } => unreachable!(),
+            DirTraversalResult::Stopped => return false,
         }
-        // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
         Common::print_time(start_time, SystemTime::now(), "check_for_similar_images".to_string());
         true
     }
@@ -324,25 +248,33 @@ impl SimilarImages {
     // - Join already read hashes with hashes which were read from file
     // - Join all hashes and save it to file
 
-    fn sort_images(&mut self, stop_receiver: Option<&Receiver<()>>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
+    fn sort_images(&mut self, stop_token: &StopToken, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) -> bool {
         let hash_map_modification = SystemTime::now();
 
-        let loaded_hash_map;
+        let mut loaded_hash_map;
 
         let mut records_already_cached: BTreeMap<String, FileEntry> = Default::default();
         let mut non_cached_files_to_check: BTreeMap<String, FileEntry> = Default::default();
 
+        let volume_roots = get_cache_volume_roots(&self.cache_location, &self.directories.included_directories);
         if self.use_cache {
-            loaded_hash_map = match load_hashes_from_file(&mut self.text_messages) {
-                Some(t) => t,
-                None => Default::default(),
-            };
+            let (loaded_entries, moved_entries) = load_hashes_from_file(&mut self.text_messages, &self.cache_location, &volume_roots).unwrap_or_default();
+            loaded_hash_map = loaded_entries;
 
             for (name, file_entry) in &self.images_to_check {
                 #[allow(clippy::if_same_then_else)]
                 if !loaded_hash_map.contains_key(name) {
-                    // If loaded data doesn't contains current image info
-                    non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                    // Renamed or moved file still may have valid entry, it is also saved with new path
+                    match moved_entries.find(&file_entry.path, file_entry.size, file_entry.modified_date) {
+                        Some(moved_entry) => {
+                            records_already_cached.insert(name.clone(), moved_entry.clone());
+                            loaded_hash_map.insert(name.clone(), moved_entry);
+                        }
+                        // If loaded data doesn't contains current image info
+                        None => {
+                            non_cached_files_to_check.insert(name.clone(), file_entry.clone());
+                        }
+                    }
                 } else if file_entry.size != loaded_hash_map.get(name).unwrap().size || file_entry.modified_date != loaded_hash_map.get(name).unwrap().modified_date {
                     // When size or modification date of image changed, then it is clear that is different image
                     non_cached_files_to_check.insert(name.clone(), file_entry.clone());
@@ -356,50 +288,37 @@ impl SimilarImages {
             mem::swap(&mut self.images_to_check, &mut non_cached_files_to_check);
         }
 
+        let cache_usage = CacheUsage {
+            loaded_from_cache: records_already_cached.len() as u64,
+            checked_files: (records_already_cached.len() + non_cached_files_to_check.len()) as u64,
+        };
+
         Common::print_time(hash_map_modification, SystemTime::now(), "sort_images - reading data from cache and preparing them".to_string());
         let hash_map_modification = SystemTime::now();
 
-        //// PROGRESS THREAD START
-        const LOOP_DURATION: u32 = 200; //in ms
-        let progress_thread_run = Arc::new(AtomicBool::new(true));
-
-        let atomic_file_counter = Arc::new(AtomicUsize::new(0));
-
-        let progress_thread_handle;
-        if let Some(progress_sender) = progress_sender {
-            let progress_send = progress_sender.clone();
-            let progress_thread_run = progress_thread_run.clone();
-            let atomic_file_counter = atomic_file_counter.clone();
-            let images_to_check = non_cached_files_to_check.len();
-            progress_thread_handle = thread::spawn(move || loop {
-                progress_send
-                    .unbounded_send(ProgressData {
-                        current_stage: 1,
-                        max_stage: 1,
-                        images_checked: atomic_file_counter.load(Ordering::Relaxed) as usize,
-                        images_to_check,
-                    })
-                    .unwrap();
-                if !progress_thread_run.load(Ordering::Relaxed) {
-                    break;
-                }
-                sleep(Duration::from_millis(LOOP_DURATION as u64));
-            });
-        } else {
-            progress_thread_handle = thread::spawn(|| {});
-        }
-        //// PROGRESS THREAD END
+        let progress_thread = ProgressThread::start(
+            progress_sender,
+            ProgressData {
+                stage_name: "Hashing images",
+                current_stage: 1,
+                max_stage: 1,
+                items_to_check: non_cached_files_to_check.len(),
+                bytes_to_check: non_cached_files_to_check.values().map(|fe| fe.size).sum(),
+                ..Default::default()
+            },
+        );
         let mut vec_file_entry: Vec<(FileEntry, Node)> = non_cached_files_to_check
             .par_iter()
             .map(|file_entry| {
-                atomic_file_counter.fetch_add(1, Ordering::Relaxed);
-                if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
-                    // This will not break
+                if stop_token.is_stopped() {
                     return None;
                 }
                 let mut file_entry = file_entry.1.clone();
+                progress_thread.counters().add_items(1);
+                progress_thread.counters().add_bytes(file_entry.size);
+                progress_thread.counters().set_current_path(&file_entry.path);
 
-                let image = match image::open(file_entry.path.clone()) {
+                let image = match open_image(&file_entry.path) {
                     Ok(t) => t,
                     Err(_) => return Some(None), // Something is wrong with image
                 };
@@ -421,8 +340,12 @@ impl SimilarImages {
             .collect::<Vec<(FileEntry, Node)>>();
 
         // End thread which send info to gui
-        progress_thread_run.store(false, Ordering::Relaxed);
-        progress_thread_handle.join().unwrap();
+        progress_thread.stop();
+
+        // Workers end early after stop, so results are incomplete
+        if stop_token.is_stopped() {
+            return false;
+        }
 
         Common::print_time(hash_map_modification, SystemTime::now(), "sort_images - reading data from files in parallel".to_string());
         let hash_map_modification = SystemTime::now();
@@ -444,7 +367,7 @@ impl SimilarImages {
             for (file_entry, _hash) in vec_file_entry {
                 all_results.insert(file_entry.path.to_string_lossy().to_string(), file_entry);
             }
-            save_hashes_to_file(&all_results, &mut self.text_messages);
+            save_hashes_to_file(&all_results, &mut self.text_messages, &self.cache_location, &volume_roots, cache_usage);
         }
 
         Common::print_time(hash_map_modification, SystemTime::now(), "sort_images - saving data to files".to_string());
@@ -473,7 +396,7 @@ impl SimilarImages {
         let mut new_vector: Vec<Vec<FileEntry>> = Vec::new();
         let mut non_cached_files_to_check = self.image_hashes.clone();
         for (hash, vec_file_entry) in &self.image_hashes {
-            if stop_receiver.is_some() && stop_receiver.unwrap().try_recv().is_ok() {
+            if stop_token.is_stopped() {
                 return false;
             }
             if !non_cached_files_to_check.contains_key(hash) {
@@ -532,7 +455,7 @@ impl SimilarImages {
                     non_cached_files_to_check.remove(*similar_hash);
                 }
             }
-            if vector_of_similar_images.len() > 1 {
+            if vector_of_similar_images.len() > 1 && !self.directories.all_in_reference_directories(vector_of_similar_images.iter().map(|fe| fe.path.as_path())) {
                 // Not sure why it may happens
                 new_vector.push((*vector_of_similar_images).to_owned());
             }
@@ -559,6 +482,11 @@ impl SimilarImages {
         self.directories.set_excluded_directory(excluded_directory, &mut self.text_messages);
     }
 
+    /// Files from reference directories are compared with other files, but groups with only such files are not shown
+    pub fn set_reference_directory(&mut self, reference_directory: Vec<PathBuf>) {
+        self.directories.set_reference_directory(reference_directory, &mut self.text_messages);
+    }
+
     pub fn set_excluded_items(&mut self, excluded_items: Vec<String>) {
         self.excluded_items.set_excluded_items(excluded_items, &mut self.text_messages);
     }
@@ -569,6 +497,44 @@ impl Default for SimilarImages {
     }
 }
 
+impl Scanner for SimilarImages {
+    fn set_scan_options(&mut self, scan_options: &ScanOptions) {
+        self.scan_options = scan_options.clone();
+        self.scan_options.set_directories_and_items(&mut self.directories, &mut self.excluded_items, &mut self.text_messages);
+        self.set_reference_directory(scan_options.reference_directories.clone());
+    }
+
+    fn scan(&mut self, stop_token: Option<&StopToken>, progress_sender: Option<&futures::channel::mpsc::UnboundedSender<ProgressData>>) {
+        self.find_similar_images(stop_token, progress_sender);
+    }
+
+    fn get_stopped_search(&self) -> bool {
+        self.stopped_search
+    }
+
+    fn get_text_messages(&self) -> &Messages {
+        &self.text_messages
+    }
+
+    fn get_results(&self) -> ScanResults {
+        ScanResults::Groups(
+            self.similar_vectors
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .map(|e| ResultEntry {
+                            path: e.path.clone(),
+                            size: Some(e.size),
+                            modified_date: e.modified_date,
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
 impl DebugPrint for SimilarImages {
     #[allow(dead_code)]
     #[allow(unreachable_code)]
@@ -591,23 +557,55 @@ impl SaveResults for SimilarImages {
             k => k.to_string(),
         };
 
+        let format = ExportFormat::from_file_name(&file_name);
+        if format != ExportFormat::Text {
+            let saved = match format {
+                ExportFormat::Csv => save_results_to_csv(
+                    &["group", "size", "modified_date", "path", "dimensions", "similarity"],
+                    self.similar_vectors.iter().enumerate().flat_map(|(group_id, group)| {
+                        group.iter().map(move |e| {
+                            vec![
+                                (group_id + 1).to_string(),
+                                e.size.to_string(),
+                                format_csv_date(e.modified_date),
+                                e.path.to_string_lossy().to_string(),
+                                e.dimensions.clone(),
+                                get_string_from_similarity(&e.similarity).to_string(),
+                            ]
+                        })
+                    }),
+                    &file_name,
+                    &mut self.text_messages,
+                ),
+                _ => save_results_to_json(&self.directories, &self.similar_vectors, &file_name, format, &mut self.text_messages),
+            };
+            Common::print_time(start_time, SystemTime::now(), "save_results_to_file".to_string());
+            return saved;
+        }
+
         let file_handler = match File::create(&file_name) {
             Ok(t) => t,
-            Err(_) => {
-                self.text_messages.errors.push("Failed to create file ".to_string() + file_name.as_str());
+            Err(e) => {
+                self.text_messages.add_error(MessageEntry::Io {
+                    operation: Operation::CreateFile,
+                    path: PathBuf::from(&file_name),
+                    kind: e.kind(),
+                });
                 return false;
             }
         };
         let mut writer = BufWriter::new(file_handler);
 
-        if writeln!(
+        if let Err(e) = writeln!(
             writer,
-            "Results of searching {:?} with excluded directories {:?} and excluded items {:?}",
-            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items
-        )
-        .is_err()
-        {
-            self.text_messages.errors.push(format!("Failed to save results to file {}", file_name));
+            "Results of searching {:?} with excluded directories {:?} and excluded items {:?} and reference directories {:?}",
+            self.directories.included_directories, self.directories.excluded_directories, self.excluded_items.items, self.directories.reference_directories
+        ) {
+            self.text_messages.add_error(MessageEntry::Io {
+                operation: Operation::WriteFile,
+                path: PathBuf::from(&file_name),
+                kind: e.kind(),
+            });
             return false;
         }
 
@@ -659,6 +657,19 @@ impl PrintResults for SimilarImages {
     }
 }
 
+const ALLOWED_IMAGE_EXTENSIONS: [&str; 12] = [".jpg", ".jpeg", ".png", ".bmp", ".tiff", ".tif", ".pnm", ".tga", ".ff", ".gif", ".jif", ".jfi"];
+
+/// Checking allowed image extensions
+fn is_image_file(fe: &common_dir_traversal::FileEntry) -> bool {
+    let file_name_lowercase = fe.path.to_string_lossy().to_lowercase();
+    ALLOWED_IMAGE_EXTENSIONS.iter().any(|e| file_name_lowercase.ends_with(e))
+}
+
+/// Checking if content of file is image in one of allowed formats, files with image extension are also checked, because their content may not be recognized
+fn is_image_content(fe: &common_dir_traversal::FileEntry) -> bool {
+    is_image_file(fe) || matches!(detect_type(&fe.path), Some(detected_type) if ALLOWED_IMAGE_EXTENSIONS.iter().any(|e| e[1..] == *detected_type.extension))
+}
+
 fn get_string_from_similarity(similarity: &Similarity) -> &str {
     match similarity {
         Similarity::Minimal => "Minimal",
@@ -671,137 +682,136 @@ fn get_string_from_similarity(similarity: &Similarity) -> &str {
     }
 }
 
-fn save_hashes_to_file(hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages) {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        // Lin: /home/username/.cache/czkawka
-        // Win: C:\Users\Username\AppData\Local\Qarmin\Czkawka\cache
-        // Mac: /Users/Username/Library/Caches/pl.Qarmin.Czkawka
-
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        if cache_dir.exists() {
-            if !cache_dir.is_dir() {
-                text_messages.messages.push(format!("Config dir {} is a file!", cache_dir.display()));
-                return;
-            }
-        } else if fs::create_dir_all(&cache_dir).is_err() {
-            text_messages.messages.push(format!("Cannot create config dir {}", cache_dir.display()));
-            return;
-        }
-        let cache_file = cache_dir.join(CACHE_FILE_NAME);
-        let file_handler = match OpenOptions::new().truncate(true).write(true).create(true).open(&cache_file) {
-            Ok(t) => t,
-            Err(_) => {
-                text_messages.messages.push(format!("Cannot create or open cache file {}", cache_file.display()));
-                return;
-            }
-        };
-        let mut writer = BufWriter::new(file_handler);
+fn save_hashes_to_file(hashmap: &BTreeMap<String, FileEntry>, text_messages: &mut Messages, cache_location: &CacheLocation, volume_roots: &[PathBuf], cache_usage: CacheUsage) {
+    let entries: Vec<&FileEntry> = hashmap.values().collect();
+    save_cache_to_file(&entries, cache_location, volume_roots, CACHE_FILE_NAME, LEGACY_CACHE_FILE_NAME, cache_usage, text_messages);
+}
 
-        for file_entry in hashmap.values() {
-            let mut string: String = "".to_string();
+fn load_hashes_from_file(text_messages: &mut Messages, cache_location: &CacheLocation, volume_roots: &[PathBuf]) -> Option<(BTreeMap<String, FileEntry>, MovedEntries<FileEntry>)> {
+    let (loaded_entries, moved_entries) = load_cache_from_file(cache_location, volume_roots, CACHE_FILE_NAME, LEGACY_CACHE_FILE_NAME, load_hashes_from_legacy_file, text_messages)?;
 
-            string += format!("{}//{}//{}//{}//", file_entry.path.display(), file_entry.size, file_entry.dimensions, file_entry.modified_date).as_str();
+    Some((loaded_entries.into_iter().map(|file_entry| (file_entry.path.to_string_lossy().to_string(), file_entry)).collect(), moved_entries))
+}
 
-            for i in 0..file_entry.hash.len() - 1 {
-                string += format!("{}//", file_entry.hash[i]).as_str();
-            }
-            string += file_entry.hash[file_entry.hash.len() - 1].to_string().as_str();
+/// Loads cache saved in text format by older versions of Czkawka
+fn load_hashes_from_legacy_file(cache_file: &Path, text_messages: &mut Messages) -> Option<Vec<FileEntry>> {
+    let file_handler = match OpenOptions::new().read(true).open(cache_file) {
+        Ok(t) => t,
+        Err(_) => return None,
+    };
 
-            if writeln!(writer, "{}", string).is_err() {
-                text_messages.messages.push(format!("Failed to save some data to cache file {}", cache_file.display()));
-                return;
-            };
-        }
-    }
-}
-fn load_hashes_from_file(text_messages: &mut Messages) -> Option<BTreeMap<String, FileEntry>> {
-    if let Some(proj_dirs) = ProjectDirs::from("pl", "Qarmin", "Czkawka") {
-        let cache_dir = PathBuf::from(proj_dirs.cache_dir());
-        let cache_file = cache_dir.join(CACHE_FILE_NAME);
-        let file_handler = match OpenOptions::new().read(true).open(&cache_file) {
+    let reader = BufReader::new(file_handler);
+
+    let mut loaded_entries: Vec<FileEntry> = Vec::new();
+
+    // Read the file line by line using the lines() iterator from std::io::BufRead.
+    for (index, line) in reader.lines().enumerate() {
+        let line = match line {
             Ok(t) => t,
-            Err(_) => {
-                // text_messages.messages.push(format!("Cannot find or open cache file {}", cache_file.display())); // This shouldn't be write to output
+            Err(e) => {
+                text_messages.add_warning(MessageEntry::Io {
+                    operation: Operation::ReadCache,
+                    path: cache_file.to_path_buf(),
+                    kind: e.kind(),
+                });
                 return None;
             }
         };
-
-        let reader = BufReader::new(file_handler);
-
-        let mut hashmap_loaded_entries: BTreeMap<String, FileEntry> = Default::default();
-
-        // Read the file line by line using the lines() iterator from std::io::BufRead.
-        for (index, line) in reader.lines().enumerate() {
-            let line = match line {
+        let uuu = line.split("//").collect::<Vec<&str>>();
+        if uuu.len() != 12 {
+            text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                path: cache_file.to_path_buf(),
+                line_number: index + 1,
+                line: line.clone(),
+                value: "data",
+            });
+            continue;
+        }
+        let mut hash: Node = [0u8; 8];
+        for i in 0..hash.len() {
+            hash[i] = match uuu[4 + i].parse::<u8>() {
                 Ok(t) => t,
                 Err(_) => {
-                    text_messages.warnings.push(format!("Failed to load line number {} from cache file {}", index + 1, cache_file.display()));
-                    return None;
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "hash value",
+                    });
+                    continue;
                 }
             };
-            let uuu = line.split("//").collect::<Vec<&str>>();
-            if uuu.len() != 12 {
-                text_messages.warnings.push(format!("Found invalid data in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                continue;
-            }
-            // Don't load cache data if destination file not exists
-            if Path::new(uuu[0]).exists() {
-                let mut hash: Node = [0u8; 8];
-                for i in 0..hash.len() {
-                    hash[i] = match uuu[4 + i].parse::<u8>() {
-                        Ok(t) => t,
-                        Err(_) => {
-                            text_messages.warnings.push(format!("Found invalid hash value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                            continue;
-                        }
-                    };
-                }
+        }
 
-                #[cfg(debug_assertions)]
-                {
-                    let mut have_at_least: u8 = 0;
-                    for i in hash.iter() {
-                        if *i == 0 {
-                            have_at_least += 1;
-                        }
-                    }
-                    if have_at_least == hash.len() as u8 {
-                        println!("ERROR START - {}", line);
-                        println!("have_at_least == hash.len() as u8");
-                        println!("ERROR END hash.len() - {} == have_at_least - {}", hash.len(), have_at_least);
-                        continue; // Just skip this entry, it is very very unlikelly that something have this hash, but if it has, then just ignore it
-                    }
+        #[cfg(debug_assertions)]
+        {
+            let mut have_at_least: u8 = 0;
+            for i in hash.iter() {
+                if *i == 0 {
+                    have_at_least += 1;
                 }
-
-                hashmap_loaded_entries.insert(
-                    uuu[0].to_string(),
-                    FileEntry {
-                        path: PathBuf::from(uuu[0]),
-                        size: match uuu[1].parse::<u64>() {
-                            Ok(t) => t,
-                            Err(_) => {
-                                text_messages.warnings.push(format!("Found invalid size value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                                continue;
-                            }
-                        },
-                        dimensions: uuu[2].to_string(),
-                        modified_date: match uuu[3].parse::<u64>() {
-                            Ok(t) => t,
-                            Err(_) => {
-                                text_messages.warnings.push(format!("Found invalid modified date value in line {} - ({}) in cache file {}", index + 1, line, cache_file.display()));
-                                continue;
-                            }
-                        },
-                        hash,
-                        similarity: Similarity::None,
-                    },
-                );
+            }
+            if have_at_least == hash.len() as u8 {
+                println!("ERROR START - {}", line);
+                println!("have_at_least == hash.len() as u8");
+                println!("ERROR END hash.len() - {} == have_at_least - {}", hash.len(), have_at_least);
+                continue; // Just skip this entry, it is very very unlikelly that something have this hash, but if it has, then just ignore it
             }
         }
 
-        return Some(hashmap_loaded_entries);
+        loaded_entries.push(FileEntry {
+            path: PathBuf::from(uuu[0]),
+            size: match uuu[1].parse::<u64>() {
+                Ok(t) => t,
+                Err(_) => {
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "size value",
+                    });
+                    continue;
+                }
+            },
+            dimensions: uuu[2].to_string(),
+            modified_date: match uuu[3].parse::<u64>() {
+                Ok(t) => t,
+                Err(_) => {
+                    text_messages.add_warning(MessageEntry::InvalidCacheLine {
+                        path: cache_file.to_path_buf(),
+                        line_number: index + 1,
+                        line: line.clone(),
+                        value: "modified date value",
+                    });
+                    continue;
+                }
+            },
+            hash,
+            similarity: Similarity::None,
+        });
     }
 
-    text_messages.messages.push("Cannot find or open system config dir to save cache file".to_string());
-    None
+    Some(loaded_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io;
+
+    #[test]
+    fn test_is_image_content() -> io::Result<()> {
+        let dir = tempfile::Builder::new().tempdir()?;
+        let entry = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            common_dir_traversal::FileEntry { path, size: 0, modified_date: 0 }
+        };
+        assert!(is_image_content(&entry("photo.dat", b"\xFF\xD8\xFF\xE0\x00\x10JFIF")));
+        // Image not recognized by content is still checked when it has proper extension
+        assert!(is_image_content(&entry("photo.png", b"not recognized")));
+        assert!(!is_image_content(&entry("notes.dat", b"not recognized")));
+        Ok(())
+    }
 }
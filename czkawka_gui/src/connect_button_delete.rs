@@ -5,8 +5,7 @@ use crate::notebook_enums::*;
 use gtk::prelude::*;
 use gtk::Align;
 use std::collections::BTreeMap;
-use std::fs;
-use std::fs::Metadata;
+use std::path::Path;
 
 // TODO add support for checking if really symlink doesn't point to correct directory/file
 
@@ -225,7 +224,8 @@ pub fn check_if_deleting_all_files_in_group(tree_view: &gtk::TreeView, column_co
 
 pub fn empty_folder_remover(tree_view: &gtk::TreeView, column_file_name: i32, column_path: i32, gui_data: &GuiData) {
     let text_view_errors = gui_data.text_view_errors.clone();
-    let use_trash = gui_data.settings.check_button_settings_use_trash.clone().get_active();
+    let delete_action = get_delete_action(gui_data);
+    let directories = get_directories(gui_data);
 
     let selection = tree_view.get_selection();
 
@@ -242,73 +242,12 @@ pub fn empty_folder_remover(tree_view: &gtk::TreeView, column_file_name: i32, co
         let name = tree_model.get_value(&tree_model.get_iter(tree_path).unwrap(), column_file_name).get::<String>().unwrap().unwrap();
         let path = tree_model.get_value(&tree_model.get_iter(tree_path).unwrap(), column_path).get::<String>().unwrap().unwrap();
 
-        // We must check if folder is really empty or contains only other empty folders
-        let mut error_happened = false;
-        let mut folders_to_check: Vec<String> = vec![format!("{}/{}", path, name)];
-        let mut current_folder: String;
-        let mut next_folder: String;
-        'dir: while !folders_to_check.is_empty() {
-            current_folder = folders_to_check.pop().unwrap();
-            let read_dir = match fs::read_dir(&current_folder) {
-                Ok(t) => t,
-                Err(_) => {
-                    error_happened = true;
-                    break 'dir;
-                }
-            };
-
-            for entry in read_dir {
-                let entry_data = match entry {
-                    Ok(t) => t,
-                    Err(_) => {
-                        error_happened = true;
-                        break 'dir;
-                    }
-                };
-                let metadata: Metadata = match entry_data.metadata() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        error_happened = true;
-                        break 'dir;
-                    }
-                };
-                if metadata.is_dir() {
-                    next_folder = "".to_owned()
-                        + &current_folder
-                        + "/"
-                        + match &entry_data.file_name().into_string() {
-                            Ok(t) => t,
-                            Err(_) => {
-                                error_happened = true;
-                                break 'dir;
-                            }
-                        };
-                    folders_to_check.push(next_folder.clone());
-                } else {
-                    error_happened = true;
-                }
-            }
-        }
-
-        if !error_happened {
-            if !use_trash {
-                match fs::remove_dir_all(format!("{}/{}", path, name)) {
-                    Ok(_) => {
-                        list_store.remove(&list_store.get_iter(tree_path).unwrap());
-                    }
-                    Err(_) => error_happened = true,
-                }
-            } else {
-                match trash::delete(format!("{}/{}", path, name)) {
-                    Ok(_) => {
-                        list_store.remove(&list_store.get_iter(tree_path).unwrap());
-                    }
-                    Err(_) => error_happened = true,
-                }
-            }
-        }
-        if error_happened {
-            messages += format!("Failed to remove folder {}/{} because folder doesn't exists, you don't have permissions or isn't empty.\n", path, name).as_str()
+        // Core checks if folder is really empty or contains only other empty folders
+        let result = delete_action.apply(Path::new(&format!("{}/{}", path, name)), &directories, false);
+        if result.is_failed() {
+            messages += get_failed_action_message(&result, format!("Failed to remove folder {}/{} because folder doesn't exists, you don't have permissions or isn't empty.\n", path, name)).as_str()
+        } else {
+            list_store.remove(&list_store.get_iter(tree_path).unwrap());
         }
     }
 
@@ -318,7 +257,8 @@ pub fn empty_folder_remover(tree_view: &gtk::TreeView, column_file_name: i32, co
 
 pub fn basic_remove(tree_view: &gtk::TreeView, column_file_name: i32, column_path: i32, gui_data: &GuiData) {
     let text_view_errors = gui_data.text_view_errors.clone();
-    let use_trash = gui_data.settings.check_button_settings_use_trash.clone().get_active();
+    let delete_action = get_delete_action(gui_data);
+    let directories = get_directories(gui_data);
 
     let selection = tree_view.get_selection();
 
@@ -335,20 +275,11 @@ pub fn basic_remove(tree_view: &gtk::TreeView, column_file_name: i32, column_pat
         let name = tree_model.get_value(&tree_model.get_iter(tree_path).unwrap(), column_file_name).get::<String>().unwrap().unwrap();
         let path = tree_model.get_value(&tree_model.get_iter(tree_path).unwrap(), column_path).get::<String>().unwrap().unwrap();
 
-        if !use_trash {
-            match fs::remove_file(format!("{}/{}", path, name)) {
-                Ok(_) => {
-                    list_store.remove(&list_store.get_iter(tree_path).unwrap());
-                }
-                Err(_) => messages += format!("Failed to remove file {}/{} because file doesn't exists or you don't have permissions.\n", path, name).as_str(),
-            }
+        let result = delete_action.apply(Path::new(&format!("{}/{}", path, name)), &directories, false);
+        if result.is_failed() {
+            messages += get_failed_action_message(&result, format!("Failed to remove file {}/{} because file doesn't exists or you don't have permissions.\n", path, name)).as_str()
         } else {
-            match trash::delete(format!("{}/{}", path, name)) {
-                Ok(_) => {
-                    list_store.remove(&list_store.get_iter(tree_path).unwrap());
-                }
-                Err(_) => messages += format!("Failed to remove file {}/{} because file doesn't exists or you don't have permissions.\n", path, name).as_str(),
-            }
+            list_store.remove(&list_store.get_iter(tree_path).unwrap());
         }
     }
 
@@ -359,7 +290,8 @@ pub fn basic_remove(tree_view: &gtk::TreeView, column_file_name: i32, column_pat
 // Remove all occurrences - remove every element which have same path and name as even non selected ones
 pub fn tree_remove(tree_view: &gtk::TreeView, column_file_name: i32, column_path: i32, column_color: i32, gui_data: &GuiData) {
     let text_view_errors = gui_data.text_view_errors.clone();
-    let use_trash = gui_data.settings.check_button_settings_use_trash.clone().get_active();
+    let delete_action = get_delete_action(gui_data);
+    let directories = get_directories(gui_data);
 
     let selection = tree_view.get_selection();
 
@@ -390,18 +322,14 @@ pub fn tree_remove(tree_view: &gtk::TreeView, column_file_name: i32, column_path
         vec_file_name.sort();
         vec_file_name.dedup();
         for file_name in vec_file_name {
-            if !use_trash {
-                if fs::remove_file(format!("{}/{}", path.clone(), file_name.clone())).is_err() {
-                    messages += format!(
+            let result = delete_action.apply(Path::new(&format!("{}/{}", path, file_name)), &directories, false);
+            if result.is_failed() {
+                messages += get_failed_action_message(
+                    &result,
+                    format!(
                         "Failed to remove file {}/{}. It is possible that you already deleted it, because similar images shows all possible file doesn't exists or you don't have permissions.\n",
                         path, file_name
-                    )
-                    .as_str()
-                }
-            } else if trash::delete(format!("{}/{}", path.clone(), file_name.clone())).is_err() {
-                messages += format!(
-                    "Failed to remove file {}/{}. It is possible that you already deleted it, because similar images shows all possible file doesn't exists or you don't have permissions.\n",
-                    path, file_name
+                    ),
                 )
                 .as_str()
             }
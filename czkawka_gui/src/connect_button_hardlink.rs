@@ -2,10 +2,10 @@ extern crate gtk;
 use crate::gui_data::GuiData;
 use crate::help_functions::*;
 use crate::notebook_enums::*;
-use czkawka_core::duplicate::make_hard_link;
+use czkawka_core::common_actions::FileAction;
 use gtk::prelude::*;
 use gtk::{TreeIter, TreePath};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn connect_button_hardlink(gui_data: &GuiData) {
     let gui_data = gui_data.clone();
@@ -115,14 +115,13 @@ fn hardlink(tree_view: gtk::TreeView, column_file_name: i32, column_path: i32, c
             break;
         }
     }
+    let directories = get_directories(gui_data);
     for hardlink_data in vec_hardlink_data {
+        let hardlink_action = FileAction::HardLink(PathBuf::from(&hardlink_data.original_data));
         for file_to_hardlink in hardlink_data.files_to_hardlink {
-            match make_hard_link(&PathBuf::from(&hardlink_data.original_data), &PathBuf::from(&file_to_hardlink)) {
-                Ok(_) => (),
-                Err(_) => {
-                    add_text_to_text_view(&text_view_errors, format!("Failed to hardlink {}.", file_to_hardlink).as_str());
-                    continue;
-                }
+            let result = hardlink_action.apply(Path::new(&file_to_hardlink), &directories, false);
+            if result.is_failed() {
+                add_text_to_text_view(&text_view_errors, get_failed_action_message(&result, format!("Failed to hardlink {}.", file_to_hardlink)).trim_end());
             }
         }
         println!();
@@ -18,62 +18,65 @@ pub fn connect_button_save(gui_data: &GuiData) {
     let shared_same_invalid_symlinks = gui_data.shared_same_invalid_symlinks.clone();
     let shared_broken_files_state = gui_data.shared_broken_files_state.clone();
     let notebook_main = gui_data.main_notebook.notebook_main.clone();
+    let combo_box_settings_save_format = gui_data.settings.combo_box_settings_save_format.clone();
     buttons_save.connect_clicked(move |_| {
         let file_name;
+        // Format of results is chosen by extension of file
+        let extension = combo_box_settings_save_format.get_active_id().map(|e| e.to_string()).unwrap_or_else(|| "txt".to_string());
 
         match to_notebook_main_enum(notebook_main.get_current_page().unwrap()) {
             NotebookMainEnum::Duplicate => {
-                file_name = "results_duplicates.txt";
+                file_name = format!("results_duplicates.{}", extension);
 
-                shared_duplication_state.borrow_mut().save_results_to_file(file_name);
+                shared_duplication_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::EmptyDirectories => {
-                file_name = "results_empty_folder.txt";
+                file_name = format!("results_empty_folder.{}", extension);
 
-                shared_empty_folders_state.borrow_mut().save_results_to_file(file_name);
+                shared_empty_folders_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::EmptyFiles => {
-                file_name = "results_empty_files.txt";
+                file_name = format!("results_empty_files.{}", extension);
 
-                shared_empty_files_state.borrow_mut().save_results_to_file(file_name);
+                shared_empty_files_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::Temporary => {
-                file_name = "results_temporary_files.txt";
+                file_name = format!("results_temporary_files.{}", extension);
 
-                shared_temporary_files_state.borrow_mut().save_results_to_file(file_name);
+                shared_temporary_files_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::BigFiles => {
-                file_name = "results_big_files.txt";
+                file_name = format!("results_big_files.{}", extension);
 
-                shared_big_files_state.borrow_mut().save_results_to_file(file_name);
+                shared_big_files_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::SimilarImages => {
-                file_name = "results_similar_images.txt";
+                file_name = format!("results_similar_images.{}", extension);
 
-                shared_similar_images_state.borrow_mut().save_results_to_file(file_name);
+                shared_similar_images_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::Zeroed => {
-                file_name = "results_zeroed_files.txt";
+                file_name = format!("results_zeroed_files.{}", extension);
 
-                shared_zeroed_files_state.borrow_mut().save_results_to_file(file_name);
+                shared_zeroed_files_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::SameMusic => {
-                file_name = "results_same_music.txt";
+                file_name = format!("results_same_music.{}", extension);
 
-                shared_same_music_state.borrow_mut().save_results_to_file(file_name);
+                shared_same_music_state.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::Symlinks => {
-                file_name = "results_invalid_symlinks.txt";
+                file_name = format!("results_invalid_symlinks.{}", extension);
 
-                shared_same_invalid_symlinks.borrow_mut().save_results_to_file(file_name);
+                shared_same_invalid_symlinks.borrow_mut().save_results_to_file(&file_name);
             }
             NotebookMainEnum::BrokenFiles => {
-                file_name = "results_broken_files.txt";
+                file_name = format!("results_broken_files.{}", extension);
 
-                shared_broken_files_state.borrow_mut().save_results_to_file(file_name);
+                shared_broken_files_state.borrow_mut().save_results_to_file(&file_name);
             }
         }
-        post_save_things(file_name, &to_notebook_main_enum(notebook_main.get_current_page().unwrap()), &gui_data);
+        post_save_things(&file_name, &to_notebook_main_enum(notebook_main.get_current_page().unwrap()), &gui_data);
     });
 }
 fn post_save_things(file_name: &str, type_of_tab: &NotebookMainEnum, gui_data: &GuiData) {
@@ -6,6 +6,12 @@ use crate::help_functions::*;
 use crate::notebook_enums::*;
 use czkawka_core::big_file::BigFile;
 use czkawka_core::broken_files::BrokenFiles;
+use czkawka_core::common_extensions::ExtensionMacros;
+use czkawka_core::common_file_type::FileType;
+use czkawka_core::common_messages::Messages;
+use czkawka_core::common_progress::ProgressData;
+use czkawka_core::common_scanner::ScanOptions;
+use czkawka_core::common_traits::Scanner;
 use czkawka_core::duplicate::{DuplicateFinder, HashType};
 use czkawka_core::empty_files::EmptyFiles;
 use czkawka_core::empty_folder::EmptyFolder;
@@ -17,27 +23,12 @@ use czkawka_core::zeroed::ZeroedFiles;
 use glib::Sender;
 use gtk::prelude::*;
 use gtk::WindowPosition;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::str::FromStr;
 use std::thread;
 
 use crate::taskbar_progress::tbp_flags::TBPF_NOPROGRESS;
 
-#[allow(clippy::too_many_arguments)]
-pub fn connect_button_search(
-    gui_data: &GuiData,
-    glib_stop_sender: Sender<Message>,
-    futures_sender_duplicate_files: futures::channel::mpsc::UnboundedSender<duplicate::ProgressData>,
-    futures_sender_empty_files: futures::channel::mpsc::UnboundedSender<empty_files::ProgressData>,
-    futures_sender_empty_folder: futures::channel::mpsc::UnboundedSender<empty_folder::ProgressData>,
-    futures_sender_big_file: futures::channel::mpsc::UnboundedSender<big_file::ProgressData>,
-    futures_sender_same_music: futures::channel::mpsc::UnboundedSender<same_music::ProgressData>,
-    futures_sender_similar_images: futures::channel::mpsc::UnboundedSender<similar_images::ProgressData>,
-    futures_sender_temporary: futures::channel::mpsc::UnboundedSender<temporary::ProgressData>,
-    futures_sender_zeroed: futures::channel::mpsc::UnboundedSender<zeroed::ProgressData>,
-    futures_sender_invalid_symlinks: futures::channel::mpsc::UnboundedSender<invalid_symlinks::ProgressData>,
-    futures_sender_broken_files: futures::channel::mpsc::UnboundedSender<broken_files::ProgressData>,
-) {
+pub fn connect_button_search(gui_data: &GuiData, glib_stop_sender: Sender<Message>, futures_sender: futures::channel::mpsc::UnboundedSender<ProgressData>) {
     let entry_info = gui_data.entry_info.clone();
     let notebook_main = gui_data.main_notebook.notebook_main.clone();
     let tree_view_included_directories = gui_data.upper_notebook.tree_view_included_directories.clone();
@@ -45,9 +36,19 @@ pub fn connect_button_search(
     let buttons_search_clone = gui_data.bottom_buttons.buttons_search.clone();
     let buttons_array = gui_data.bottom_buttons.buttons_array.clone();
     let check_button_recursive = gui_data.upper_notebook.check_button_recursive.clone();
+    let check_button_one_file_system = gui_data.upper_notebook.check_button_one_file_system.clone();
+    let check_button_skip_hidden = gui_data.upper_notebook.check_button_skip_hidden.clone();
+    let entry_max_depth = gui_data.upper_notebook.entry_max_depth.clone();
+    let entry_maximal_file_size = gui_data.upper_notebook.entry_maximal_file_size.clone();
+    let entry_modified_after = gui_data.upper_notebook.entry_modified_after.clone();
+    let entry_modified_before = gui_data.upper_notebook.entry_modified_before.clone();
     let entry_excluded_items = gui_data.upper_notebook.entry_excluded_items.clone();
+    let entry_reference_directories = gui_data.upper_notebook.entry_reference_directories.clone();
     let entry_same_music_minimal_size = gui_data.main_notebook.entry_same_music_minimal_size.clone();
     let entry_allowed_extensions = gui_data.upper_notebook.entry_allowed_extensions.clone();
+    let entry_excluded_extensions = gui_data.upper_notebook.entry_excluded_extensions.clone();
+    let entry_content_types = gui_data.upper_notebook.entry_content_types.clone();
+    let check_button_detect_by_content = gui_data.upper_notebook.check_button_detect_by_content.clone();
     let buttons_names = gui_data.bottom_buttons.buttons_names.clone();
     let radio_button_duplicates_name = gui_data.main_notebook.radio_button_duplicates_name.clone();
     let radio_button_duplicates_size = gui_data.main_notebook.radio_button_duplicates_size.clone();
@@ -60,7 +61,7 @@ pub fn connect_button_search(
     let radio_button_similar_images_high = gui_data.main_notebook.radio_button_similar_images_high.clone();
     let radio_button_similar_images_very_high = gui_data.main_notebook.radio_button_similar_images_very_high.clone();
     let entry_duplicate_minimal_size = gui_data.main_notebook.entry_duplicate_minimal_size.clone();
-    let stop_receiver = gui_data.stop_receiver.clone();
+    let stop_token = gui_data.stop_token.clone();
     let entry_big_files_number = gui_data.main_notebook.entry_big_files_number.clone();
     let entry_similar_images_minimal_size = gui_data.main_notebook.entry_similar_images_minimal_size.clone();
     let check_button_music_title: gtk::CheckButton = gui_data.main_notebook.check_button_music_title.clone();
@@ -82,6 +83,7 @@ pub fn connect_button_search(
     let text_view_errors = gui_data.text_view_errors.clone();
     let window_progress = gui_data.progress_window.window_progress.clone();
     let label_stage = gui_data.progress_window.label_stage.clone();
+    let label_current_path = gui_data.progress_window.label_current_path.clone();
     let grid_progress_stages = gui_data.progress_window.grid_progress_stages.clone();
     let progress_bar_current_stage = gui_data.progress_window.progress_bar_current_stage.clone();
     let progress_bar_all_stages = gui_data.progress_window.progress_bar_all_stages.clone();
@@ -93,18 +95,36 @@ pub fn connect_button_search(
     let check_button_settings_hide_hard_links = gui_data.settings.check_button_settings_hide_hard_links.clone();
     let check_button_settings_use_cache = gui_data.settings.check_button_settings_use_cache.clone();
     let entry_settings_cache_file_minimal_size = gui_data.settings.entry_settings_cache_file_minimal_size.clone();
+    let entry_settings_cache_dir = gui_data.settings.entry_settings_cache_dir.clone();
+    let check_button_settings_portable_cache = gui_data.settings.check_button_settings_portable_cache.clone();
 
     buttons_search_clone.connect_clicked(move |_| {
-        let included_directories = get_path_buf_from_vector_of_strings(get_string_from_list_store(&tree_view_included_directories));
-        let excluded_directories = get_path_buf_from_vector_of_strings(get_string_from_list_store(&tree_view_excluded_directories));
-        let recursive_search = check_button_recursive.get_active();
-        let excluded_items = entry_excluded_items.get_text().as_str().to_string().split(',').map(|e| e.to_string()).collect::<Vec<String>>();
-        let allowed_extensions = entry_allowed_extensions.get_text().as_str().to_string();
+        // Tools know only built-in macros, so macros defined by user are replaced here
+        let mut extension_macros_messages = Messages::new();
+        let extension_macros = ExtensionMacros::load(&mut extension_macros_messages);
+        let scan_options = ScanOptions {
+            included_directories: get_path_buf_from_vector_of_strings(get_string_from_list_store(&tree_view_included_directories)),
+            excluded_directories: get_path_buf_from_vector_of_strings(get_string_from_list_store(&tree_view_excluded_directories)),
+            reference_directories: get_reference_directories_from_entry(&entry_reference_directories),
+            recursive_search: check_button_recursive.get_active(),
+            one_file_system: check_button_one_file_system.get_active(),
+            skip_hidden: check_button_skip_hidden.get_active(),
+            max_depth: entry_max_depth.get_text().as_str().trim().parse::<usize>().ok(),
+            maximal_file_size: entry_maximal_file_size.get_text().as_str().trim().parse::<u64>().ok(),
+            modified_after: common::Common::parse_date(entry_modified_after.get_text().as_str()).ok(),
+            modified_before: common::Common::parse_date(entry_modified_before.get_text().as_str()).ok(),
+            excluded_items: entry_excluded_items.get_text().as_str().to_string().split(',').map(|e| e.to_string()).collect::<Vec<String>>(),
+            allowed_extensions: extension_macros.expand(entry_allowed_extensions.get_text().as_str()),
+            excluded_extensions: extension_macros.expand(entry_excluded_extensions.get_text().as_str()),
+            content_types: entry_content_types.get_text().as_str().split(',').filter_map(|e| FileType::from_str(e).ok()).collect(),
+            detect_by_content: check_button_detect_by_content.get_active(),
+            ..ScanOptions::new()
+        };
         let hide_hard_links = check_button_settings_hide_hard_links.get_active();
         let use_cache = check_button_settings_use_cache.get_active();
         let minimal_cache_file_size = entry_settings_cache_file_minimal_size.get_text().as_str().parse::<u64>().unwrap_or(2 * 1024 * 1024);
-
-        let show_dialog = Arc::new(AtomicBool::new(true));
+        let cache_dir = get_cache_dir_from_entry(&entry_settings_cache_dir);
+        let portable_cache = check_button_settings_portable_cache.get_active();
 
         hide_all_buttons(&buttons_array);
 
@@ -119,17 +139,22 @@ pub fn connect_button_search(
         // Resets progress bars
         progress_bar_all_stages.set_fraction(0 as f64);
         progress_bar_current_stage.set_fraction(0 as f64);
+        label_current_path.set_text("");
 
         reset_text_view(&text_view_errors);
+        for warning in &extension_macros_messages.warnings {
+            add_text_to_text_view(&text_view_errors, warning);
+        }
 
         let glib_stop_sender = glib_stop_sender.clone();
-        let stop_receiver = stop_receiver.clone();
+        // Stop from previous search must not end this one
+        stop_token.reset();
+        let stop_token = stop_token.clone();
 
-        match to_notebook_main_enum(notebook_main.get_current_page().unwrap()) {
+        // Only tool specific settings are set here, running tool and sending results back is same for all tabs
+        let scanner: Option<Box<dyn ScannerWithMessage>> = match to_notebook_main_enum(notebook_main.get_current_page().unwrap()) {
             NotebookMainEnum::Duplicate => {
-                label_stage.show();
                 grid_progress_stages.show_all();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_duplicate_finder).clear();
 
@@ -158,113 +183,53 @@ pub fn connect_button_search(
                     panic!("No radio button is pressed");
                 }
 
-                let futures_sender_duplicate_files = futures_sender_duplicate_files.clone();
-                // Find duplicates
-                thread::spawn(move || {
-                    let mut df = DuplicateFinder::new();
-                    df.set_included_directory(included_directories);
-                    df.set_excluded_directory(excluded_directories);
-                    df.set_recursive_search(recursive_search);
-                    df.set_excluded_items(excluded_items);
-                    df.set_allowed_extensions(allowed_extensions);
-                    df.set_minimal_file_size(minimal_file_size);
-                    df.set_minimal_cache_file_size(minimal_cache_file_size);
-                    df.set_check_method(check_method);
-                    df.set_hash_type(hash_type);
-                    df.set_ignore_hard_links(hide_hard_links);
-                    df.set_use_cache(use_cache);
-                    df.find_duplicates(Some(&stop_receiver), Some(&futures_sender_duplicate_files));
-                    let _ = glib_stop_sender.send(Message::Duplicates(df));
-                });
+                let mut df = DuplicateFinder::new();
+                df.set_minimal_file_size(minimal_file_size);
+                df.set_minimal_cache_file_size(minimal_cache_file_size);
+                df.set_check_method(check_method);
+                df.set_hash_type(hash_type);
+                df.set_ignore_hard_links(hide_hard_links);
+                df.set_use_cache(use_cache);
+                df.set_cache_dir(cache_dir);
+                df.set_portable_cache(portable_cache);
+                Some(Box::new(df))
             }
             NotebookMainEnum::EmptyFiles => {
-                label_stage.show();
                 grid_progress_stages.hide();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_empty_files_finder).clear();
 
-                let futures_sender_empty_files = futures_sender_empty_files.clone();
-                // Find empty files
-                thread::spawn(move || {
-                    let mut vf = EmptyFiles::new();
-
-                    vf.set_included_directory(included_directories);
-                    vf.set_excluded_directory(excluded_directories);
-                    vf.set_recursive_search(recursive_search);
-                    vf.set_excluded_items(excluded_items);
-                    vf.set_allowed_extensions(allowed_extensions);
-                    vf.find_empty_files(Some(&stop_receiver), Some(&futures_sender_empty_files));
-                    let _ = glib_stop_sender.send(Message::EmptyFiles(vf));
-                });
+                Some(Box::new(EmptyFiles::new()))
             }
             NotebookMainEnum::EmptyDirectories => {
-                label_stage.show();
                 grid_progress_stages.hide();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_empty_folder_finder).clear();
 
-                let futures_sender_empty_folder = futures_sender_empty_folder.clone();
-                // Find empty folders
-                thread::spawn(move || {
-                    let mut ef = EmptyFolder::new();
-                    ef.set_included_directory(included_directories);
-                    ef.set_excluded_directory(excluded_directories);
-                    ef.set_excluded_items(excluded_items);
-                    ef.find_empty_folders(Some(&stop_receiver), Some(&futures_sender_empty_folder));
-                    let _ = glib_stop_sender.send(Message::EmptyFolders(ef));
-                });
+                Some(Box::new(EmptyFolder::new()))
             }
             NotebookMainEnum::BigFiles => {
-                label_stage.show();
                 grid_progress_stages.hide();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_big_files_finder).clear();
 
                 let numbers_of_files_to_check = entry_big_files_number.get_text().as_str().parse::<usize>().unwrap_or(50);
 
-                let futures_sender_big_file = futures_sender_big_file.clone();
-                // Find big files
-                thread::spawn(move || {
-                    let mut bf = BigFile::new();
-
-                    bf.set_included_directory(included_directories);
-                    bf.set_excluded_directory(excluded_directories);
-                    bf.set_recursive_search(recursive_search);
-                    bf.set_excluded_items(excluded_items);
-                    bf.set_number_of_files_to_check(numbers_of_files_to_check);
-                    bf.find_big_files(Some(&stop_receiver), Some(&futures_sender_big_file));
-                    let _ = glib_stop_sender.send(Message::BigFiles(bf));
-                });
+                let mut bf = BigFile::new();
+                bf.set_number_of_files_to_check(numbers_of_files_to_check);
+                Some(Box::new(bf))
             }
             NotebookMainEnum::Temporary => {
-                label_stage.show();
                 grid_progress_stages.hide();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_temporary_files_finder).clear();
 
-                let futures_sender_temporary = futures_sender_temporary.clone();
-                // Find temporary files
-                thread::spawn(move || {
-                    let mut tf = Temporary::new();
-
-                    tf.set_included_directory(included_directories);
-                    tf.set_excluded_directory(excluded_directories);
-                    tf.set_recursive_search(recursive_search);
-                    tf.set_excluded_items(excluded_items);
-                    tf.find_temporary_files(Some(&stop_receiver), Some(&futures_sender_temporary));
-                    let _ = glib_stop_sender.send(Message::Temporary(tf));
-                });
+                Some(Box::new(Temporary::new()))
             }
             NotebookMainEnum::SimilarImages => {
                 image_preview_similar_images.hide();
 
-                label_stage.show();
                 grid_progress_stages.show_all();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_similar_images_finder).clear();
 
@@ -287,47 +252,23 @@ pub fn connect_button_search(
                     panic!("No radio button is pressed");
                 }
 
-                let futures_sender_similar_images = futures_sender_similar_images.clone();
-                // Find similar images
-                thread::spawn(move || {
-                    let mut sf = SimilarImages::new();
-
-                    sf.set_included_directory(included_directories);
-                    sf.set_excluded_directory(excluded_directories);
-                    sf.set_recursive_search(recursive_search);
-                    sf.set_excluded_items(excluded_items);
-                    sf.set_minimal_file_size(minimal_file_size);
-                    sf.set_similarity(similarity);
-                    sf.set_use_cache(use_cache);
-                    sf.find_similar_images(Some(&stop_receiver), Some(&futures_sender_similar_images));
-                    let _ = glib_stop_sender.send(Message::SimilarImages(sf));
-                });
+                let mut sf = SimilarImages::new();
+                sf.set_minimal_file_size(minimal_file_size);
+                sf.set_similarity(similarity);
+                sf.set_use_cache(use_cache);
+                sf.set_cache_dir(cache_dir);
+                sf.set_portable_cache(portable_cache);
+                Some(Box::new(sf))
             }
             NotebookMainEnum::Zeroed => {
-                label_stage.show();
                 grid_progress_stages.show_all();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_zeroed_files_finder).clear();
 
-                let futures_sender_zeroed = futures_sender_zeroed.clone();
-                // Find zeroed files
-                thread::spawn(move || {
-                    let mut zf = ZeroedFiles::new();
-
-                    zf.set_included_directory(included_directories);
-                    zf.set_excluded_directory(excluded_directories);
-                    zf.set_recursive_search(recursive_search);
-                    zf.set_excluded_items(excluded_items);
-                    zf.set_allowed_extensions(allowed_extensions);
-                    zf.find_zeroed_files(Some(&stop_receiver), Some(&futures_sender_zeroed));
-                    let _ = glib_stop_sender.send(Message::ZeroedFiles(zf));
-                });
+                Some(Box::new(ZeroedFiles::new()))
             }
             NotebookMainEnum::SameMusic => {
-                label_stage.show();
                 grid_progress_stages.show_all();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_same_music_finder).clear();
 
@@ -352,72 +293,49 @@ pub fn connect_button_search(
                 }
 
                 if music_similarity != MusicSimilarity::NONE {
-                    let futures_sender_same_music = futures_sender_same_music.clone();
-                    // Find Similar music
-                    thread::spawn(move || {
-                        let mut mf = SameMusic::new();
-
-                        mf.set_included_directory(included_directories);
-                        mf.set_excluded_directory(excluded_directories);
-                        mf.set_excluded_items(excluded_items);
-                        mf.set_minimal_file_size(minimal_file_size);
-                        mf.set_recursive_search(recursive_search);
-                        mf.set_music_similarity(music_similarity);
-                        mf.find_same_music(Some(&stop_receiver), Some(&futures_sender_same_music));
-                        let _ = glib_stop_sender.send(Message::SameMusic(mf));
-                    });
+                    let mut mf = SameMusic::new();
+                    mf.set_minimal_file_size(minimal_file_size);
+                    mf.set_music_similarity(music_similarity);
+                    Some(Box::new(mf))
                 } else {
                     notebook_main.set_sensitive(true);
                     set_buttons(&mut *shared_buttons.borrow_mut().get_mut(&NotebookMainEnum::SameMusic).unwrap(), &buttons_array, &buttons_names);
                     entry_info.set_text("ERROR: You must select at least one checkbox with music searching types.");
-                    show_dialog.store(false, Ordering::Relaxed);
+                    None
                 }
             }
             NotebookMainEnum::Symlinks => {
-                label_stage.show();
                 grid_progress_stages.hide();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_invalid_symlinks).clear();
 
-                let futures_sender_invalid_symlinks = futures_sender_invalid_symlinks.clone();
-
-                thread::spawn(move || {
-                    let mut isf = InvalidSymlinks::new();
-
-                    isf.set_included_directory(included_directories);
-                    isf.set_excluded_directory(excluded_directories);
-                    isf.set_recursive_search(recursive_search);
-                    isf.set_excluded_items(excluded_items);
-                    isf.find_invalid_links(Some(&stop_receiver), Some(&futures_sender_invalid_symlinks));
-                    let _ = glib_stop_sender.send(Message::InvalidSymlinks(isf));
-                });
+                Some(Box::new(InvalidSymlinks::new()))
             }
             NotebookMainEnum::BrokenFiles => {
-                label_stage.show();
                 grid_progress_stages.show();
-                window_progress.resize(1, 1);
 
                 get_list_store(&tree_view_broken_files).clear();
 
-                let futures_sender_broken_files = futures_sender_broken_files.clone();
+                let mut br = BrokenFiles::new();
+                br.set_use_cache(use_cache);
+                br.set_cache_dir(cache_dir);
+                br.set_portable_cache(portable_cache);
+                Some(Box::new(br))
+            }
+        };
 
-                thread::spawn(move || {
-                    let mut br = BrokenFiles::new();
+        if let Some(mut scanner) = scanner {
+            label_stage.show();
+            window_progress.resize(1, 1);
 
-                    br.set_included_directory(included_directories);
-                    br.set_excluded_directory(excluded_directories);
-                    br.set_recursive_search(recursive_search);
-                    br.set_excluded_items(excluded_items);
-                    br.set_use_cache(use_cache);
-                    br.find_broken_files(Some(&stop_receiver), Some(&futures_sender_broken_files));
-                    let _ = glib_stop_sender.send(Message::BrokenFiles(br));
-                });
-            }
-        }
+            let futures_sender = futures_sender.clone();
+            thread::spawn(move || {
+                scanner.set_scan_options(&scan_options);
+                scanner.scan(Some(&stop_token), Some(&futures_sender));
+                let _ = glib_stop_sender.send(scanner.into_message());
+            });
 
-        // Show progress dialog
-        if show_dialog.load(Ordering::Relaxed) {
+            // Show progress dialog
             window_progress.show();
             taskbar_state.borrow().show();
             taskbar_state.borrow().set_progress_state(TBPF_NOPROGRESS);
@@ -2,9 +2,11 @@ extern crate gtk;
 use crate::gui_data::GuiData;
 use crate::help_functions::*;
 use crate::notebook_enums::*;
+use czkawka_core::common_actions::FileAction;
+use czkawka_core::common_messages::MessageEntry;
 use gtk::prelude::*;
 use gtk::{TreeIter, TreePath};
-use std::fs;
+use std::path::{Path, PathBuf};
 
 pub fn connect_button_symlink(gui_data: &GuiData) {
     let gui_data = gui_data.clone();
@@ -52,9 +54,9 @@ fn symlink(tree_view: gtk::TreeView, column_file_name: i32, column_path: i32, co
 
     struct SymlinkData {
         original_data: String,
-        files_to_symlink: Vec<String>,
+        files_to_symlink: Vec<(String, TreePath)>,
     }
-    let mut vec_tree_path_to_remove: Vec<TreePath> = Vec::new(); // List of symlinked files without its root
+    let mut vec_tree_path_to_remove: Vec<TreePath> = Vec::new(); // List of symlinked files without its root, only successfully replaced files are removed
     let mut vec_symlink_data: Vec<SymlinkData> = Vec::new();
 
     let current_iter: TreeIter = tree_model.get_iter_first().unwrap(); // Symlink button should be only visible when more than 1 element is visible, otherwise it needs to be fixed
@@ -81,9 +83,8 @@ fn symlink(tree_view: gtk::TreeView, column_file_name: i32, column_path: i32, co
             let full_file_path = format!("{}/{}", path, file_name);
 
             if current_symlink_data.is_some() {
-                vec_tree_path_to_remove.push(tree_model.get_path(&current_iter).unwrap());
                 let mut temp_data = current_symlink_data.unwrap();
-                temp_data.files_to_symlink.push(full_file_path);
+                temp_data.files_to_symlink.push((full_file_path, tree_model.get_path(&current_iter).unwrap()));
                 current_symlink_data = Some(temp_data);
             } else {
                 current_symlink_data = Some(SymlinkData {
@@ -114,37 +115,25 @@ fn symlink(tree_view: gtk::TreeView, column_file_name: i32, column_path: i32, co
             break;
         }
     }
+    let directories = get_directories(gui_data);
+    let relative = gui_data.settings.check_button_settings_relative_symlinks.get_active();
     for symlink_data in vec_symlink_data {
-        for file_to_symlink in symlink_data.files_to_symlink {
-            match fs::remove_file(&file_to_symlink) {
-                Ok(_) => (),
-                Err(_) => {
-                    add_text_to_text_view(&text_view_errors, format!("Failed to remove file {} when creating symlink.", file_to_symlink).as_str());
-                    continue;
-                }
-            };
-
-            #[cfg(target_family = "unix")]
-            {
-                match std::os::unix::fs::symlink(&symlink_data.original_data, &file_to_symlink) {
-                    Ok(_) => (),
-                    Err(_) => {
-                        add_text_to_text_view(&text_view_errors, format!("Failed to remove file {} when creating symlink.", file_to_symlink).as_str());
-                        continue;
-                    }
-                };
+        // Original file is restored when symlink couldn't be created
+        let symlink_action = FileAction::SymLink {
+            original: PathBuf::from(&symlink_data.original_data),
+            relative,
+        };
+        for (file_to_symlink, tree_path) in symlink_data.files_to_symlink {
+            if !directories.is_in_same_included_directory(&file_to_symlink, &symlink_data.original_data) {
+                add_text_to_text_view(&text_view_errors, MessageEntry::SymlinkAcrossRoots { path: PathBuf::from(&file_to_symlink) }.to_string().as_str());
+                continue;
+            }
+            let result = symlink_action.apply(Path::new(&file_to_symlink), &directories, false);
+            if result.is_failed() {
+                add_text_to_text_view(&text_view_errors, get_failed_action_message(&result, format!("Failed to replace file {} with symlink.", file_to_symlink)).trim_end());
+            } else {
+                vec_tree_path_to_remove.push(tree_path);
             }
-            // TODO Add this, because for now it not working ()
-            // #[cfg(target_family = "windows")]
-            // {
-            //     match std::os::windows::fs::symlink(&symlink_data.original_data, &file_to_symlink) {
-            //         Ok(_) => (),
-            //         Err(_) => {
-            //             add_text_to_text_view(&text_view_errors, format!("Failed to remove file {} when creating symlink.", file_to_symlink).as_str());
-            //             continue;
-            //         }
-            //     };
-            // }
         }
         println!();
     }
@@ -5,7 +5,7 @@ use crate::gui_data::GuiData;
 use crate::help_functions::*;
 use crate::notebook_enums::*;
 use chrono::NaiveDateTime;
-use czkawka_core::duplicate::CheckingMethod;
+use czkawka_core::duplicate::{is_already_deduplicated, CheckingMethod};
 use czkawka_core::same_music::MusicSimilarity;
 use glib::Receiver;
 use gtk::prelude::*;
@@ -89,6 +89,18 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
                     }
 
                     entry_info.set_text(format!("Found {} duplicates files in {} groups which took {}.", duplicates_number, duplicates_group, duplicates_size.file_size(options::BINARY).unwrap()).as_str());
+                    if information.number_of_already_deduplicated_groups > 0 {
+                        entry_info.set_text(
+                            format!(
+                                "Found {} duplicates files in {} groups which took {}, {} groups already share their data.",
+                                duplicates_number,
+                                duplicates_group,
+                                duplicates_size.file_size(options::BINARY).unwrap(),
+                                information.number_of_already_deduplicated_groups
+                            )
+                            .as_str(),
+                        );
+                    }
 
                     // Create GUI
                     {
@@ -153,9 +165,15 @@ pub fn connect_compute_results(gui_data: &GuiData, glib_stop_receiver: Receiver<
                                             vector.clone()
                                         };
 
+                                        // Files which already share their data don't take additional space
+                                        let lost_space = if is_already_deduplicated(&vector) {
+                                            "already share data".to_string()
+                                        } else {
+                                            format!("{} ({} bytes) lost", ((vector.len() - 1) as u64 * *size as u64).file_size(options::BINARY).unwrap(), (vector.len() - 1) as u64 * *size as u64)
+                                        };
                                         let values: [&dyn ToValue; 6] = [
                                             &(format!("{} x {} ({} bytes)", vector.len(), size.file_size(options::BINARY).unwrap(), size)),
-                                            &(format!("{} ({} bytes) lost", ((vector.len() - 1) as u64 * *size as u64).file_size(options::BINARY).unwrap(), (vector.len() - 1) as u64 * *size as u64)),
+                                            &lost_space,
                                             &"".to_string(), // No text in 3 column
                                             &(0),            // Not used here
                                             &(HEADER_ROW_COLOR.to_string()),
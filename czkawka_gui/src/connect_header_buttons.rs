@@ -1,5 +1,8 @@
 extern crate gtk;
 use crate::gui_data::GuiData;
+use crate::help_functions::*;
+use czkawka_core::quarantine::UndoQuarantine;
+use czkawka_core::results_import::ResultsImport;
 use gtk::prelude::*;
 use gtk::{ResponseType, WindowPosition};
 
@@ -15,3 +18,125 @@ pub fn connect_button_about(gui_data: &GuiData) {
         }
     });
 }
+
+pub fn connect_button_open_results(gui_data: &GuiData) {
+    let gui_data = gui_data.clone();
+    let window_main = gui_data.window_main.clone();
+    let entry_info = gui_data.entry_info.clone();
+    let text_view_errors = gui_data.text_view_errors.clone();
+    let button_open_results = gui_data.header.button_open_results.clone();
+    button_open_results.connect_clicked(move |_| {
+        let chooser = gtk::FileChooserDialog::with_buttons(Some("Open saved results"), Some(&window_main), gtk::FileChooserAction::Open, &[("Ok", gtk::ResponseType::Ok), ("Close", gtk::ResponseType::Cancel)]);
+        // Only JSON results contain size and modification date needed to check files before removing them
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("JSON results (*.json, *.jsonl)"));
+        filter.add_pattern("*.json");
+        filter.add_pattern("*.jsonl");
+        chooser.add_filter(&filter);
+        chooser.show_all();
+        let response_type = chooser.run();
+        let file_name = chooser.get_filename();
+        chooser.close();
+        let file_name = match (response_type, file_name) {
+            (gtk::ResponseType::Ok, Some(file_name)) => file_name,
+            _ => return,
+        };
+
+        let mut ri = ResultsImport::new();
+        if !ri.load_results(&file_name.to_string_lossy()) {
+            print_text_messages_to_text_view(ri.get_text_messages(), &text_view_errors);
+            entry_info.set_text(format!("Failed to load results from {}", file_name.display()).as_str());
+            return;
+        }
+
+        let information = ri.get_information();
+        if information.number_of_entries_to_delete == 0 {
+            print_text_messages_to_text_view(ri.get_text_messages(), &text_view_errors);
+            entry_info.set_text(format!("Loaded {} files, none of them are marked to delete.", information.number_of_entries).as_str());
+            return;
+        }
+
+        // Results file may be edited by hand, so user always needs to confirm deleting
+        let confirmation_dialog = gtk::Dialog::with_buttons(
+            Some("Delete confirmation"),
+            Some(&window_main),
+            gtk::DialogFlags::DESTROY_WITH_PARENT,
+            &[("Ok", gtk::ResponseType::Ok), ("Close", gtk::ResponseType::Cancel)],
+        );
+        let label: gtk::Label = gtk::Label::new(Some(
+            format!(
+                "Loaded {} files, {} of them are marked to delete.\nAre you sure that you want to delete them?",
+                information.number_of_entries, information.number_of_entries_to_delete
+            )
+            .as_str(),
+        ));
+        get_dialog_box_child(&confirmation_dialog).add(&label);
+        confirmation_dialog.show_all();
+        let response_type = confirmation_dialog.run();
+        confirmation_dialog.close();
+        if response_type != gtk::ResponseType::Ok {
+            return;
+        }
+
+        ri.set_delete_action(get_delete_action(&gui_data));
+        ri.set_reference_directory(get_reference_directories_from_entry(&gui_data.upper_notebook.entry_reference_directories));
+        ri.apply_actions();
+
+        let information = ri.get_information();
+        print_text_messages_to_text_view(ri.get_text_messages(), &text_view_errors);
+        entry_info.set_text(
+            format!(
+                "Removed {} files, failed to remove {} files, skipped {} files.",
+                information.number_of_removed_files, information.number_of_failed_to_remove_files, information.number_of_skipped_files
+            )
+            .as_str(),
+        );
+    });
+}
+
+pub fn connect_button_undo(gui_data: &GuiData) {
+    let window_main = gui_data.window_main.clone();
+    let entry_info = gui_data.entry_info.clone();
+    let text_view_errors = gui_data.text_view_errors.clone();
+    let entry_settings_quarantine_dir = gui_data.settings.entry_settings_quarantine_dir.clone();
+    let button_undo = gui_data.header.button_undo.clone();
+    button_undo.connect_clicked(move |_| {
+        let chooser = gtk::FileChooserDialog::with_buttons(
+            Some("Open journal of quarantine"),
+            Some(&window_main),
+            gtk::FileChooserAction::Open,
+            &[("Ok", gtk::ResponseType::Ok), ("Close", gtk::ResponseType::Cancel)],
+        );
+        // Journals are saved inside quarantine folder
+        let quarantine_dir = entry_settings_quarantine_dir.get_text().as_str().trim().to_string();
+        if !quarantine_dir.is_empty() {
+            chooser.set_current_folder(&quarantine_dir);
+        }
+        chooser.show_all();
+        let response_type = chooser.run();
+        let file_name = chooser.get_filename();
+        chooser.close();
+        let file_name = match (response_type, file_name) {
+            (gtk::ResponseType::Ok, Some(file_name)) => file_name,
+            _ => return,
+        };
+
+        let mut uq = UndoQuarantine::new();
+        if !uq.load_journal(&file_name.to_string_lossy()) {
+            print_text_messages_to_text_view(uq.get_text_messages(), &text_view_errors);
+            entry_info.set_text(format!("Failed to load journal from {}", file_name.display()).as_str());
+            return;
+        }
+        uq.restore_files();
+
+        let information = uq.get_information();
+        print_text_messages_to_text_view(uq.get_text_messages(), &text_view_errors);
+        entry_info.set_text(
+            format!(
+                "Restored {} files, failed to restore {} files, skipped {} files.",
+                information.number_of_restored_files, information.number_of_failed_to_restore_files, information.number_of_skipped_files
+            )
+            .as_str(),
+        );
+    });
+}
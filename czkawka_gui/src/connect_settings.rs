@@ -1,6 +1,9 @@
 extern crate gtk;
 use crate::gui_data::GuiData;
+use crate::help_functions::get_cache_dir_from_entry;
 use crate::saving_loading::{load_configuration, reset_configuration, save_configuration};
+use czkawka_core::common_cache::{clean_cache, clear_cache, get_cache_dir, get_cache_files_info};
+use czkawka_core::common_messages::Messages;
 use gtk::prelude::*;
 use gtk::WindowPosition;
 
@@ -10,8 +13,12 @@ pub fn connect_settings(gui_data: &GuiData) {
         let button_settings = gui_data.header.button_settings.clone();
         let window_main = gui_data.window_main.clone();
         let window_settings = gui_data.settings.window_settings.clone();
+        let label_settings_cache_stats = gui_data.settings.label_settings_cache_stats.clone();
+        let entry_settings_cache_dir = gui_data.settings.entry_settings_cache_dir.clone();
         button_settings.connect_clicked(move |_| {
             window_main.set_sensitive(false);
+            // Cache may change after every search, so stats are read again each time
+            label_settings_cache_stats.set_text(&get_cache_stats_text(&entry_settings_cache_dir));
             window_settings.show();
             window_settings.set_position(WindowPosition::Center);
         });
@@ -52,4 +59,62 @@ pub fn connect_settings(gui_data: &GuiData) {
             reset_configuration(&gui_data, true);
         });
     }
+    // Connect cache buttons
+    {
+        let label_settings_cache_stats = gui_data.settings.label_settings_cache_stats.clone();
+        let button_settings_cache_clean = gui_data.settings.button_settings_cache_clean.clone();
+        let entry_settings_cache_dir = gui_data.settings.entry_settings_cache_dir.clone();
+        button_settings_cache_clean.connect_clicked(move |_| {
+            let cache_dir = match get_cache_dir_from_entry(&entry_settings_cache_dir).or_else(get_cache_dir) {
+                Some(t) => t,
+                None => return,
+            };
+            let mut messages = Messages::new();
+            let clean_results = clean_cache(&cache_dir, None, &mut messages);
+            let result_text = format!("Removed {} entries, {} entries left, freed {} bytes", clean_results.removed_entries, clean_results.remaining_entries, clean_results.freed_bytes);
+            label_settings_cache_stats.set_text(&format!("{}\n\n{}{}", result_text, get_messages_text(&messages), get_cache_stats_text(&entry_settings_cache_dir)));
+        });
+    }
+    {
+        let label_settings_cache_stats = gui_data.settings.label_settings_cache_stats.clone();
+        let button_settings_cache_clear = gui_data.settings.button_settings_cache_clear.clone();
+        let entry_settings_cache_dir = gui_data.settings.entry_settings_cache_dir.clone();
+        button_settings_cache_clear.connect_clicked(move |_| {
+            let cache_dir = match get_cache_dir_from_entry(&entry_settings_cache_dir).or_else(get_cache_dir) {
+                Some(t) => t,
+                None => return,
+            };
+            let mut messages = Messages::new();
+            let removed_files = clear_cache(&cache_dir, &mut messages);
+            let result_text = format!("Removed {} cache files", removed_files);
+            label_settings_cache_stats.set_text(&format!("{}\n\n{}{}", result_text, get_messages_text(&messages), get_cache_stats_text(&entry_settings_cache_dir)));
+        });
+    }
+}
+
+fn get_cache_stats_text(entry_settings_cache_dir: &gtk::Entry) -> String {
+    let cache_dir = match get_cache_dir_from_entry(entry_settings_cache_dir).or_else(get_cache_dir) {
+        Some(t) => t,
+        None => return "Cannot find cache folder".to_string(),
+    };
+    let mut text = format!("Cache folder - {}\n", cache_dir.display());
+    let cache_files_info = get_cache_files_info(&cache_dir);
+    if cache_files_info.is_empty() {
+        text.push_str("There are no cache files");
+    }
+    for cache_file_info in cache_files_info {
+        text.push_str(&format!("\n{}", cache_file_info));
+    }
+    text
+}
+
+fn get_messages_text(messages: &Messages) -> String {
+    let mut text = String::new();
+    for message in messages.warnings.iter().chain(messages.errors.iter()) {
+        text.push_str(&format!("{}\n", message));
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
 }
@@ -9,9 +9,9 @@ use crate::gui_settings::GuiSettings;
 use crate::gui_upper_notepad::GuiUpperNotebook;
 use crate::notebook_enums::*;
 use crate::taskbar_progress::TaskbarProgress;
-use crossbeam_channel::unbounded;
 use czkawka_core::big_file::BigFile;
 use czkawka_core::broken_files::BrokenFiles;
+use czkawka_core::common_stop::StopToken;
 use czkawka_core::duplicate::DuplicateFinder;
 use czkawka_core::empty_files::EmptyFiles;
 use czkawka_core::empty_folder::EmptyFolder;
@@ -72,9 +72,8 @@ pub struct GuiData {
     pub text_view_errors: gtk::TextView,
     pub scrolled_window_errors: gtk::ScrolledWindow,
 
-    // Used for sending stop signal to thread
-    pub stop_sender: crossbeam_channel::Sender<()>,
-    pub stop_receiver: crossbeam_channel::Receiver<()>,
+    // Used for stopping search from GUI thread
+    pub stop_token: StopToken,
 }
 
 impl GuiData {
@@ -153,9 +152,6 @@ impl GuiData {
         let scrolled_window_errors: gtk::ScrolledWindow = builder.get_object("scrolled_window_errors").unwrap();
         scrolled_window_errors.show_all(); // Not sure why needed, but without it text view errors sometimes hide itself
 
-        // Used for sending stop signal to thread
-        let (stop_sender, stop_receiver): (crossbeam_channel::Sender<()>, crossbeam_channel::Receiver<()>) = unbounded();
-
         Self {
             glade_src,
             builder,
@@ -184,8 +180,7 @@ impl GuiData {
             entry_info,
             text_view_errors,
             scrolled_window_errors,
-            stop_sender,
-            stop_receiver,
+            stop_token: StopToken::new(),
         }
     }
 }
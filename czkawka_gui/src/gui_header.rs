@@ -4,12 +4,21 @@ use gtk::prelude::*;
 pub struct GuiHeader {
     pub button_settings: gtk::Button,
     pub button_app_info: gtk::Button,
+    pub button_open_results: gtk::Button,
+    pub button_undo: gtk::Button,
 }
 
 impl GuiHeader {
     pub fn create_from_builder(builder: &gtk::Builder) -> Self {
         let button_settings: gtk::Button = builder.get_object("button_settings").unwrap();
         let button_app_info: gtk::Button = builder.get_object("button_app_info").unwrap();
-        Self { button_settings, button_app_info }
+        let button_open_results: gtk::Button = builder.get_object("button_open_results").unwrap();
+        let button_undo: gtk::Button = builder.get_object("button_undo").unwrap();
+        Self {
+            button_settings,
+            button_app_info,
+            button_open_results,
+            button_undo,
+        }
     }
 }
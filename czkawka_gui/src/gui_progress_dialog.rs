@@ -8,6 +8,7 @@ pub struct GuiProgressDialog {
     pub progress_bar_all_stages: gtk::ProgressBar,
 
     pub label_stage: gtk::Label,
+    pub label_current_path: gtk::Label,
 
     pub grid_progress_stages: gtk::Grid,
 
@@ -22,6 +23,7 @@ impl GuiProgressDialog {
         let progress_bar_all_stages: gtk::ProgressBar = builder.get_object("progress_bar_all_stages").unwrap();
 
         let label_stage: gtk::Label = builder.get_object("label_stage").unwrap();
+        let label_current_path: gtk::Label = builder.get_object("label_current_path").unwrap();
 
         let grid_progress_stages: gtk::Grid = builder.get_object("grid_progress_stages").unwrap();
 
@@ -32,6 +34,7 @@ impl GuiProgressDialog {
             progress_bar_current_stage,
             progress_bar_all_stages,
             label_stage,
+            label_current_path,
             grid_progress_stages,
             button_stop_in_dialog,
         }
@@ -13,14 +13,24 @@ pub struct GuiSettings {
     pub check_button_settings_show_text_view: gtk::CheckButton,
     pub check_button_settings_use_cache: gtk::CheckButton,
     pub check_button_settings_use_trash: gtk::CheckButton,
+    pub combo_box_settings_save_format: gtk::ComboBoxText,
+    pub entry_settings_quarantine_dir: gtk::Entry,
 
     // Duplicates
     pub check_button_settings_hide_hard_links: gtk::CheckButton,
+    pub check_button_settings_relative_symlinks: gtk::CheckButton,
     pub entry_settings_cache_file_minimal_size: gtk::Entry,
 
     // Similar Images
     pub check_button_settings_show_preview_similar_images: gtk::CheckButton,
 
+    // Cache
+    pub entry_settings_cache_dir: gtk::Entry,
+    pub check_button_settings_portable_cache: gtk::CheckButton,
+    pub label_settings_cache_stats: gtk::Label,
+    pub button_settings_cache_clean: gtk::Button,
+    pub button_settings_cache_clear: gtk::Button,
+
     // Buttons
     pub button_settings_save_configuration: gtk::Button,
     pub button_settings_load_configuration: gtk::Button,
@@ -40,14 +50,24 @@ impl GuiSettings {
         let check_button_settings_show_text_view: gtk::CheckButton = builder.get_object("check_button_settings_show_text_view").unwrap();
         let check_button_settings_use_cache: gtk::CheckButton = builder.get_object("check_button_settings_use_cache").unwrap();
         let check_button_settings_use_trash: gtk::CheckButton = builder.get_object("check_button_settings_use_trash").unwrap();
+        let combo_box_settings_save_format: gtk::ComboBoxText = builder.get_object("combo_box_settings_save_format").unwrap();
+        let entry_settings_quarantine_dir: gtk::Entry = builder.get_object("entry_settings_quarantine_dir").unwrap();
 
         // Duplicates
         let check_button_settings_hide_hard_links: gtk::CheckButton = builder.get_object("check_button_settings_hide_hard_links").unwrap();
+        let check_button_settings_relative_symlinks: gtk::CheckButton = builder.get_object("check_button_settings_relative_symlinks").unwrap();
         let entry_settings_cache_file_minimal_size: gtk::Entry = builder.get_object("entry_settings_cache_file_minimal_size").unwrap();
 
         // Similar Images
         let check_button_settings_show_preview_similar_images: gtk::CheckButton = builder.get_object("check_button_settings_show_preview_similar_images").unwrap();
 
+        // Cache
+        let entry_settings_cache_dir: gtk::Entry = builder.get_object("entry_settings_cache_dir").unwrap();
+        let check_button_settings_portable_cache: gtk::CheckButton = builder.get_object("check_button_settings_portable_cache").unwrap();
+        let label_settings_cache_stats: gtk::Label = builder.get_object("label_settings_cache_stats").unwrap();
+        let button_settings_cache_clean: gtk::Button = builder.get_object("button_settings_cache_clean").unwrap();
+        let button_settings_cache_clear: gtk::Button = builder.get_object("button_settings_cache_clear").unwrap();
+
         // Saving/Loading/Resetting configuration
         let button_settings_save_configuration: gtk::Button = builder.get_object("button_settings_save_configuration").unwrap();
         let button_settings_load_configuration: gtk::Button = builder.get_object("button_settings_load_configuration").unwrap();
@@ -62,9 +82,17 @@ impl GuiSettings {
             check_button_settings_show_text_view,
             check_button_settings_use_cache,
             check_button_settings_use_trash,
+            combo_box_settings_save_format,
+            entry_settings_quarantine_dir,
             check_button_settings_hide_hard_links,
+            check_button_settings_relative_symlinks,
             entry_settings_cache_file_minimal_size,
             check_button_settings_show_preview_similar_images,
+            entry_settings_cache_dir,
+            check_button_settings_portable_cache,
+            label_settings_cache_stats,
+            button_settings_cache_clean,
+            button_settings_cache_clear,
             button_settings_save_configuration,
             button_settings_load_configuration,
             button_settings_reset_configuration,
@@ -11,10 +11,20 @@ pub struct GuiUpperNotebook {
     pub tree_view_included_directories: gtk::TreeView,
     pub tree_view_excluded_directories: gtk::TreeView,
 
+    pub entry_reference_directories: gtk::Entry,
     pub entry_excluded_items: gtk::Entry,
     pub entry_allowed_extensions: gtk::Entry,
+    pub entry_excluded_extensions: gtk::Entry,
+    pub entry_content_types: gtk::Entry,
+    pub check_button_detect_by_content: gtk::CheckButton,
 
     pub check_button_recursive: gtk::CheckButton,
+    pub check_button_one_file_system: gtk::CheckButton,
+    pub check_button_skip_hidden: gtk::CheckButton,
+    pub entry_max_depth: gtk::Entry,
+    pub entry_maximal_file_size: gtk::Entry,
+    pub entry_modified_after: gtk::Entry,
+    pub entry_modified_before: gtk::Entry,
 
     pub buttons_manual_add_directory: gtk::Button,
     pub buttons_add_included_directory: gtk::Button,
@@ -35,9 +45,19 @@ impl GuiUpperNotebook {
         let tree_view_excluded_directories: gtk::TreeView = TreeView::new();
 
         let entry_allowed_extensions: gtk::Entry = builder.get_object("entry_allowed_extensions").unwrap();
+        let entry_excluded_extensions: gtk::Entry = builder.get_object("entry_excluded_extensions").unwrap();
+        let entry_content_types: gtk::Entry = builder.get_object("entry_content_types").unwrap();
+        let check_button_detect_by_content: gtk::CheckButton = builder.get_object("check_button_detect_by_content").unwrap();
         let entry_excluded_items: gtk::Entry = builder.get_object("entry_excluded_items").unwrap();
+        let entry_reference_directories: gtk::Entry = builder.get_object("entry_reference_directories").unwrap();
 
         let check_button_recursive: gtk::CheckButton = builder.get_object("check_button_recursive").unwrap();
+        let check_button_one_file_system: gtk::CheckButton = builder.get_object("check_button_one_file_system").unwrap();
+        let check_button_skip_hidden: gtk::CheckButton = builder.get_object("check_button_skip_hidden").unwrap();
+        let entry_max_depth: gtk::Entry = builder.get_object("entry_max_depth").unwrap();
+        let entry_maximal_file_size: gtk::Entry = builder.get_object("entry_maximal_file_size").unwrap();
+        let entry_modified_after: gtk::Entry = builder.get_object("entry_modified_after").unwrap();
+        let entry_modified_before: gtk::Entry = builder.get_object("entry_modified_before").unwrap();
 
         let buttons_manual_add_directory: gtk::Button = builder.get_object("buttons_manual_add_directory").unwrap();
         let buttons_add_included_directory: gtk::Button = builder.get_object("buttons_add_included_directory").unwrap();
@@ -52,9 +72,19 @@ impl GuiUpperNotebook {
             scrolled_window_excluded_directories,
             tree_view_included_directories,
             tree_view_excluded_directories,
+            entry_reference_directories,
             entry_excluded_items,
             entry_allowed_extensions,
+            entry_excluded_extensions,
+            entry_content_types,
+            check_button_detect_by_content,
             check_button_recursive,
+            check_button_one_file_system,
+            check_button_skip_hidden,
+            entry_max_depth,
+            entry_maximal_file_size,
+            entry_modified_after,
+            entry_modified_before,
             buttons_manual_add_directory,
             buttons_add_included_directory,
             buttons_remove_included_directory,
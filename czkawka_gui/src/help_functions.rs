@@ -1,11 +1,16 @@
+use crate::gui_data::GuiData;
 use czkawka_core::big_file::BigFile;
 use czkawka_core::broken_files::BrokenFiles;
-use czkawka_core::common_messages::Messages;
+use czkawka_core::common_actions::{ActionOutcome, ActionResult, FileAction};
+use czkawka_core::common_directory::Directories;
+use czkawka_core::common_messages::{MessageEntry, Messages};
+use czkawka_core::common_traits::Scanner;
 use czkawka_core::duplicate::DuplicateFinder;
 use czkawka_core::empty_files::EmptyFiles;
 use czkawka_core::empty_folder::EmptyFolder;
 use czkawka_core::invalid_symlinks;
 use czkawka_core::invalid_symlinks::InvalidSymlinks;
+use czkawka_core::quarantine::Quarantine;
 use czkawka_core::same_music::SameMusic;
 use czkawka_core::similar_images::{SimilarImages, Similarity};
 use czkawka_core::temporary::Temporary;
@@ -28,6 +33,61 @@ pub enum Message {
     BrokenFiles(BrokenFiles),
 }
 
+/// Tool which can be run from GUI in same way as others and then send back to main thread with its results
+pub trait ScannerWithMessage: Scanner + Send {
+    fn into_message(self: Box<Self>) -> Message;
+}
+impl ScannerWithMessage for DuplicateFinder {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::Duplicates(*self)
+    }
+}
+impl ScannerWithMessage for EmptyFolder {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::EmptyFolders(*self)
+    }
+}
+impl ScannerWithMessage for EmptyFiles {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::EmptyFiles(*self)
+    }
+}
+impl ScannerWithMessage for BigFile {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::BigFiles(*self)
+    }
+}
+impl ScannerWithMessage for Temporary {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::Temporary(*self)
+    }
+}
+impl ScannerWithMessage for SimilarImages {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::SimilarImages(*self)
+    }
+}
+impl ScannerWithMessage for ZeroedFiles {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::ZeroedFiles(*self)
+    }
+}
+impl ScannerWithMessage for SameMusic {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::SameMusic(*self)
+    }
+}
+impl ScannerWithMessage for InvalidSymlinks {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::InvalidSymlinks(*self)
+    }
+}
+impl ScannerWithMessage for BrokenFiles {
+    fn into_message(self: Box<Self>) -> Message {
+        Message::BrokenFiles(*self)
+    }
+}
+
 pub enum ColumnsDuplicates {
     // Columns for duplicate treeview
     Name = 0,
@@ -141,6 +201,53 @@ pub fn get_path_buf_from_vector_of_strings(vec_string: Vec<String>) -> Vec<PathB
     vec_string.iter().map(PathBuf::from).collect()
 }
 
+/// Empty entry means default cache folder
+pub fn get_cache_dir_from_entry(entry: &gtk::Entry) -> Option<PathBuf> {
+    let cache_dir = entry.get_text().as_str().trim().to_string();
+    if cache_dir.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(cache_dir))
+    }
+}
+
+pub fn get_reference_directories_from_entry(entry: &gtk::Entry) -> Vec<PathBuf> {
+    entry.get_text().as_str().split(',').map(str::trim).filter(|e| !e.is_empty()).map(PathBuf::from).collect()
+}
+
+/// Directories currently set in GUI, used to check which files may be changed by buttons
+pub fn get_directories(gui_data: &GuiData) -> Directories {
+    let upper_notebook = &gui_data.upper_notebook;
+    let mut text_messages = Messages::new();
+    let mut directories = Directories::new();
+    directories.set_included_directory(get_path_buf_from_vector_of_strings(get_string_from_list_store(&upper_notebook.tree_view_included_directories)), &mut text_messages);
+    directories.set_excluded_directory(get_path_buf_from_vector_of_strings(get_string_from_list_store(&upper_notebook.tree_view_excluded_directories)), &mut text_messages);
+    directories.set_reference_directory(get_reference_directories_from_entry(&upper_notebook.entry_reference_directories), &mut text_messages);
+    directories.optimize_directories(upper_notebook.check_button_recursive.get_active(), &mut text_messages);
+    directories
+}
+
+/// Files inside reference directories are skipped on purpose, so they get their own message instead of generic one
+pub fn get_failed_action_message(result: &ActionResult, message: String) -> String {
+    if result.outcome == ActionOutcome::InReferenceDirectory {
+        format!("{}\n", MessageEntry::InReferenceDirectory { path: result.path.clone() })
+    } else {
+        message
+    }
+}
+
+/// Quarantine is used instead of trash when its folder is set, each call creates new journal
+pub fn get_delete_action(gui_data: &GuiData) -> FileAction {
+    let quarantine_dir = gui_data.settings.entry_settings_quarantine_dir.get_text().as_str().trim().to_string();
+    if !quarantine_dir.is_empty() {
+        FileAction::MoveToQuarantine(Quarantine::new(PathBuf::from(quarantine_dir)))
+    } else if gui_data.settings.check_button_settings_use_trash.get_active() {
+        FileAction::MoveToTrash
+    } else {
+        FileAction::Delete
+    }
+}
+
 pub fn split_path(path: &Path) -> (String, String) {
     match (path.parent(), path.file_name()) {
         (Some(dir), Some(file)) => (dir.display().to_string(), file.to_string_lossy().into_owned()),
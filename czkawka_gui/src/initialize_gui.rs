@@ -502,12 +502,12 @@ pub fn initialize_gui(gui_data: &mut GuiData) {
     //// Window progress
     {
         let window_progress = gui_data.progress_window.window_progress.clone();
-        let stop_sender = gui_data.stop_sender.clone();
+        let stop_token = gui_data.stop_token.clone();
 
         window_progress.hide_on_delete();
 
         window_progress.connect_delete_event(move |_e, _y| {
-            stop_sender.send(()).unwrap();
+            stop_token.stop();
             gtk::Inhibit(true)
         });
     }
@@ -38,6 +38,7 @@ mod taskbar_progress_dummy;
 #[cfg(target_os = "windows")]
 mod taskbar_progress_win;
 
+use czkawka_core::common_progress::ProgressData;
 use czkawka_core::*;
 
 extern crate gtk;
@@ -88,18 +89,7 @@ fn main() {
     let (glib_stop_sender, glib_stop_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
 
     // Futures progress report
-    let (futures_sender_duplicate_files, futures_receiver_duplicate_files): (futures::channel::mpsc::UnboundedSender<duplicate::ProgressData>, futures::channel::mpsc::UnboundedReceiver<duplicate::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_empty_files, futures_receiver_empty_files): (futures::channel::mpsc::UnboundedSender<empty_files::ProgressData>, futures::channel::mpsc::UnboundedReceiver<empty_files::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_empty_folder, futures_receiver_empty_folder): (futures::channel::mpsc::UnboundedSender<empty_folder::ProgressData>, futures::channel::mpsc::UnboundedReceiver<empty_folder::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_big_file, futures_receiver_big_file): (futures::channel::mpsc::UnboundedSender<big_file::ProgressData>, futures::channel::mpsc::UnboundedReceiver<big_file::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_same_music, futures_receiver_same_music): (futures::channel::mpsc::UnboundedSender<same_music::ProgressData>, futures::channel::mpsc::UnboundedReceiver<same_music::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_similar_images, futures_receiver_similar_images): (futures::channel::mpsc::UnboundedSender<similar_images::ProgressData>, futures::channel::mpsc::UnboundedReceiver<similar_images::ProgressData>) =
-        futures::channel::mpsc::unbounded();
-    let (futures_sender_temporary, futures_receiver_temporary): (futures::channel::mpsc::UnboundedSender<temporary::ProgressData>, futures::channel::mpsc::UnboundedReceiver<temporary::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_zeroed, futures_receiver_zeroed): (futures::channel::mpsc::UnboundedSender<zeroed::ProgressData>, futures::channel::mpsc::UnboundedReceiver<zeroed::ProgressData>) = futures::channel::mpsc::unbounded();
-    let (futures_sender_invalid_symlinks, futures_receiver_invalid_symlinks): (futures::channel::mpsc::UnboundedSender<invalid_symlinks::ProgressData>, futures::channel::mpsc::UnboundedReceiver<invalid_symlinks::ProgressData>) =
-        futures::channel::mpsc::unbounded();
-    let (futures_sender_broken_files, futures_receiver_broken_files): (futures::channel::mpsc::UnboundedSender<broken_files::ProgressData>, futures::channel::mpsc::UnboundedReceiver<broken_files::ProgressData>) = futures::channel::mpsc::unbounded();
+    let (futures_sender, futures_receiver): (futures::channel::mpsc::UnboundedSender<ProgressData>, futures::channel::mpsc::UnboundedReceiver<ProgressData>) = futures::channel::mpsc::unbounded();
 
     initialize_gui(&mut gui_data);
     reset_configuration(&gui_data, false); // Fallback for invalid loading setting project
@@ -107,20 +97,7 @@ fn main() {
 
     connect_button_delete(&gui_data);
     connect_button_save(&gui_data);
-    connect_button_search(
-        &gui_data,
-        glib_stop_sender,
-        futures_sender_duplicate_files,
-        futures_sender_empty_files,
-        futures_sender_empty_folder,
-        futures_sender_big_file,
-        futures_sender_same_music,
-        futures_sender_similar_images,
-        futures_sender_temporary,
-        futures_sender_zeroed,
-        futures_sender_invalid_symlinks,
-        futures_sender_broken_files,
-    );
+    connect_button_search(&gui_data, glib_stop_sender, futures_sender);
     connect_button_select(&gui_data);
     connect_button_stop(&gui_data);
     connect_button_symlink(&gui_data);
@@ -129,22 +106,12 @@ fn main() {
     connect_selection_of_directories(&gui_data);
     connect_popovers(&gui_data);
     connect_compute_results(&gui_data, glib_stop_receiver);
-    connect_progress_window(
-        &gui_data,
-        futures_receiver_duplicate_files,
-        futures_receiver_empty_files,
-        futures_receiver_empty_folder,
-        futures_receiver_big_file,
-        futures_receiver_same_music,
-        futures_receiver_similar_images,
-        futures_receiver_temporary,
-        futures_receiver_zeroed,
-        futures_receiver_invalid_symlinks,
-        futures_receiver_broken_files,
-    );
+    connect_progress_window(&gui_data, futures_receiver);
     connect_hide_text_view_errors(&gui_data);
     connect_settings(&gui_data);
     connect_button_about(&gui_data);
+    connect_button_open_results(&gui_data);
+    connect_button_undo(&gui_data);
     connect_about_buttons(&gui_data);
 
     // Quit the program when X in main window was clicked
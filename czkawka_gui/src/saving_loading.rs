@@ -65,6 +65,16 @@ pub fn save_configuration(gui_data: &GuiData, manual_execution: bool) {
             }
         }
 
+        //// Reference Directories
+        data_to_save.push("--reference_directories:".to_string());
+        let entry_reference_directories = gui_data.upper_notebook.entry_reference_directories.clone();
+        for directory in entry_reference_directories.get_text().split(',') {
+            if directory.trim().is_empty() {
+                continue;
+            }
+            data_to_save.push(directory.trim().to_string());
+        }
+
         {
             //// Excluded Items
             data_to_save.push("--excluded_items:".to_string());
@@ -86,6 +96,16 @@ pub fn save_configuration(gui_data: &GuiData, manual_execution: bool) {
                 data_to_save.push(extension.to_string());
             }
 
+            //// Excluded extensions
+            data_to_save.push("--excluded_extensions:".to_string());
+            let entry_excluded_extensions = gui_data.upper_notebook.entry_excluded_extensions.clone();
+            for extension in entry_excluded_extensions.get_text().split(',') {
+                if extension.trim().is_empty() {
+                    continue;
+                }
+                data_to_save.push(extension.to_string());
+            }
+
             //// Save at exit
             data_to_save.push("--save_at_exit:".to_string());
             let check_button_settings_save_at_exit = gui_data.settings.check_button_settings_save_at_exit.clone();
@@ -121,6 +141,11 @@ pub fn save_configuration(gui_data: &GuiData, manual_execution: bool) {
             let check_button_settings_hide_hard_links = gui_data.settings.check_button_settings_hide_hard_links.clone();
             data_to_save.push(check_button_settings_hide_hard_links.get_active().to_string());
 
+            //// Create symlinks with path relative to link
+            data_to_save.push("--relative_symlinks:".to_string());
+            let check_button_settings_relative_symlinks = gui_data.settings.check_button_settings_relative_symlinks.clone();
+            data_to_save.push(check_button_settings_relative_symlinks.get_active().to_string());
+
             //// Use cache system
             data_to_save.push("--use_cache:".to_string());
             let check_button_settings_use_cache = gui_data.settings.check_button_settings_use_cache.clone();
@@ -131,10 +156,30 @@ pub fn save_configuration(gui_data: &GuiData, manual_execution: bool) {
             let check_button_settings_use_trash = gui_data.settings.check_button_settings_use_trash.clone();
             data_to_save.push(check_button_settings_use_trash.get_active().to_string());
 
+            //// Format of saved results
+            data_to_save.push("--save_format:".to_string());
+            let combo_box_settings_save_format = gui_data.settings.combo_box_settings_save_format.clone();
+            data_to_save.push(combo_box_settings_save_format.get_active_id().map(|e| e.to_string()).unwrap_or_else(|| "txt".to_string()));
+
+            //// Quarantine folder
+            data_to_save.push("--quarantine_dir:".to_string());
+            let entry_settings_quarantine_dir = gui_data.settings.entry_settings_quarantine_dir.clone();
+            data_to_save.push(entry_settings_quarantine_dir.get_text().as_str().trim().to_string());
+
             //// minimal cache file size
             data_to_save.push("--cache_minimal_file_size:".to_string());
             let entry_settings_cache_file_minimal_size = gui_data.settings.entry_settings_cache_file_minimal_size.clone();
             data_to_save.push(entry_settings_cache_file_minimal_size.get_text().as_str().parse::<u64>().unwrap_or(2 * 1024 * 1024).to_string());
+
+            //// Custom cache folder
+            data_to_save.push("--cache_dir:".to_string());
+            let entry_settings_cache_dir = gui_data.settings.entry_settings_cache_dir.clone();
+            data_to_save.push(entry_settings_cache_dir.get_text().as_str().trim().to_string());
+
+            //// Save cache on scanned volumes
+            data_to_save.push("--portable_cache:".to_string());
+            let check_button_settings_portable_cache = gui_data.settings.check_button_settings_portable_cache.clone();
+            data_to_save.push(check_button_settings_portable_cache.get_active().to_string());
         }
 
         // Creating/Opening config file
@@ -175,8 +220,10 @@ enum TypeOfLoadedData {
     None,
     IncludedDirectories,
     ExcludedDirectories,
+    ReferenceDirectories,
     ExcludedItems,
     AllowedExtensions,
+    ExcludedExtensions,
     LoadingAtStart,
     SavingAtExit,
     ConfirmDeletion,
@@ -184,9 +231,14 @@ enum TypeOfLoadedData {
     ShowPreviews,
     BottomTextPanel,
     HideHardLinks,
+    RelativeSymlinks,
     UseCache,
     UseTrash,
+    SaveFormat,
     CacheMinimalSize,
+    CacheDir,
+    PortableCache,
+    QuarantineDir,
 }
 
 pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
@@ -222,8 +274,10 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
 
         let mut included_directories: Vec<String> = Vec::new();
         let mut excluded_directories: Vec<String> = Vec::new();
+        let mut reference_directories: Vec<String> = Vec::new();
         let mut excluded_items: Vec<String> = Vec::new();
         let mut allowed_extensions: Vec<String> = Vec::new();
+        let mut excluded_extensions: Vec<String> = Vec::new();
         let mut loading_at_start: bool = true;
         let mut saving_at_exit: bool = true;
         let mut confirm_deletion: bool = true;
@@ -231,9 +285,14 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
         let mut show_previews: bool = true;
         let mut bottom_text_panel: bool = true;
         let mut hide_hard_links: bool = true;
+        let mut relative_symlinks: bool = false;
         let mut use_cache: bool = true;
         let mut use_trash: bool = false;
+        let mut save_format: String = "txt".to_string();
         let mut cache_minimal_size: u64 = 2 * 1024 * 1024;
+        let mut cache_dir: String = "".to_string();
+        let mut portable_cache: bool = false;
+        let mut quarantine_dir: String = "".to_string();
 
         let mut current_type = TypeOfLoadedData::None;
         for (line_number, line) in loaded_data.replace("\r\n", "\n").split('\n').enumerate() {
@@ -245,10 +304,14 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                 current_type = TypeOfLoadedData::IncludedDirectories;
             } else if line.starts_with("--excluded_directories") {
                 current_type = TypeOfLoadedData::ExcludedDirectories;
+            } else if line.starts_with("--reference_directories") {
+                current_type = TypeOfLoadedData::ReferenceDirectories;
             } else if line.starts_with("--excluded_items") {
                 current_type = TypeOfLoadedData::ExcludedItems;
             } else if line.starts_with("--allowed_extensions") {
                 current_type = TypeOfLoadedData::AllowedExtensions;
+            } else if line.starts_with("--excluded_extensions") {
+                current_type = TypeOfLoadedData::ExcludedExtensions;
             } else if line.starts_with("--load_at_start") {
                 current_type = TypeOfLoadedData::LoadingAtStart;
             } else if line.starts_with("--save_at_exit") {
@@ -263,12 +326,22 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                 current_type = TypeOfLoadedData::BottomTextPanel;
             } else if line.starts_with("--hide_hard_links") {
                 current_type = TypeOfLoadedData::HideHardLinks;
+            } else if line.starts_with("--relative_symlinks") {
+                current_type = TypeOfLoadedData::RelativeSymlinks;
             } else if line.starts_with("--use_cache") {
                 current_type = TypeOfLoadedData::UseCache;
             } else if line.starts_with("--use_trash") {
                 current_type = TypeOfLoadedData::UseTrash;
+            } else if line.starts_with("--save_format") {
+                current_type = TypeOfLoadedData::SaveFormat;
             } else if line.starts_with("--cache_minimal_file_size") {
                 current_type = TypeOfLoadedData::CacheMinimalSize;
+            } else if line.starts_with("--cache_dir") {
+                current_type = TypeOfLoadedData::CacheDir;
+            } else if line.starts_with("--portable_cache") {
+                current_type = TypeOfLoadedData::PortableCache;
+            } else if line.starts_with("--quarantine_dir") {
+                current_type = TypeOfLoadedData::QuarantineDir;
             } else if line.starts_with("--") {
                 current_type = TypeOfLoadedData::None;
                 add_text_to_text_view(
@@ -289,12 +362,18 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                     TypeOfLoadedData::ExcludedDirectories => {
                         excluded_directories.push(line);
                     }
+                    TypeOfLoadedData::ReferenceDirectories => {
+                        reference_directories.push(line);
+                    }
                     TypeOfLoadedData::ExcludedItems => {
                         excluded_items.push(line);
                     }
                     TypeOfLoadedData::AllowedExtensions => {
                         allowed_extensions.push(line);
                     }
+                    TypeOfLoadedData::ExcludedExtensions => {
+                        excluded_extensions.push(line);
+                    }
                     TypeOfLoadedData::LoadingAtStart => {
                         let line = line.to_lowercase();
                         if line == "1" || line == "true" {
@@ -386,6 +465,19 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                             );
                         }
                     }
+                    TypeOfLoadedData::RelativeSymlinks => {
+                        let line = line.to_lowercase();
+                        if line == "1" || line == "true" {
+                            relative_symlinks = true;
+                        } else if line == "0" || line == "false" {
+                            relative_symlinks = false;
+                        } else {
+                            add_text_to_text_view(
+                                &text_view_errors,
+                                format!("Found invalid data in line {} \"{}\" isn't proper value(0/1/true/false) when loading file {:?}", line_number, line, config_file).as_str(),
+                            );
+                        }
+                    }
                     TypeOfLoadedData::UseCache => {
                         let line = line.to_lowercase();
                         if line == "1" || line == "true" {
@@ -412,6 +504,17 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                             );
                         }
                     }
+                    TypeOfLoadedData::SaveFormat => {
+                        let line = line.to_lowercase();
+                        if line == "txt" || line == "json" || line == "jsonl" || line == "csv" {
+                            save_format = line;
+                        } else {
+                            add_text_to_text_view(
+                                &text_view_errors,
+                                format!("Found invalid data in line {} \"{}\" isn't proper value(txt/json/jsonl/csv) when loading file {:?}", line_number, line, config_file).as_str(),
+                            );
+                        }
+                    }
                     TypeOfLoadedData::CacheMinimalSize => {
                         if let Ok(number) = line.parse::<u64>() {
                             cache_minimal_size = number;
@@ -422,6 +525,25 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                             );
                         }
                     }
+                    TypeOfLoadedData::CacheDir => {
+                        cache_dir = line;
+                    }
+                    TypeOfLoadedData::QuarantineDir => {
+                        quarantine_dir = line;
+                    }
+                    TypeOfLoadedData::PortableCache => {
+                        let line = line.to_lowercase();
+                        if line == "1" || line == "true" {
+                            portable_cache = true;
+                        } else if line == "0" || line == "false" {
+                            portable_cache = false;
+                        } else {
+                            add_text_to_text_view(
+                                &text_view_errors,
+                                format!("Found invalid data in line {} \"{}\" isn't proper value(0/1/true/false) when loading file {:?}", line_number, line, config_file).as_str(),
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -452,6 +574,10 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                 list_store.set(&list_store.append(), &col_indices, &values);
             }
 
+            //// Reference Directories
+            let entry_reference_directories = gui_data.upper_notebook.entry_reference_directories.clone();
+            entry_reference_directories.set_text(reference_directories.join(",").as_str());
+
             //// Excluded Items
             let entry_excluded_items = gui_data.upper_notebook.entry_excluded_items.clone();
             entry_excluded_items.set_text(excluded_items.iter().map(|e| e.to_string() + ",").collect::<String>().as_str());
@@ -460,6 +586,10 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
             let entry_allowed_extensions = gui_data.upper_notebook.entry_allowed_extensions.clone();
             entry_allowed_extensions.set_text(allowed_extensions.iter().map(|e| e.to_string() + ",").collect::<String>().as_str());
 
+            //// Excluded extensions
+            let entry_excluded_extensions = gui_data.upper_notebook.entry_excluded_extensions.clone();
+            entry_excluded_extensions.set_text(excluded_extensions.iter().map(|e| e.to_string() + ",").collect::<String>().as_str());
+
             //// Buttons
             gui_data.settings.check_button_settings_load_at_start.set_active(loading_at_start);
             gui_data.settings.check_button_settings_save_at_exit.set_active(saving_at_exit);
@@ -474,9 +604,14 @@ pub fn load_configuration(gui_data: &GuiData, manual_execution: bool) {
                 gui_data.scrolled_window_errors.show();
             }
             gui_data.settings.check_button_settings_hide_hard_links.set_active(hide_hard_links);
+            gui_data.settings.check_button_settings_relative_symlinks.set_active(relative_symlinks);
             gui_data.settings.check_button_settings_use_cache.set_active(use_cache);
             gui_data.settings.check_button_settings_use_trash.set_active(use_trash);
+            gui_data.settings.combo_box_settings_save_format.set_active_id(Some(save_format.as_str()));
+            gui_data.settings.entry_settings_quarantine_dir.set_text(quarantine_dir.as_str());
             gui_data.settings.entry_settings_cache_file_minimal_size.set_text(cache_minimal_size.to_string().as_str());
+            gui_data.settings.entry_settings_cache_dir.set_text(cache_dir.as_str());
+            gui_data.settings.check_button_settings_portable_cache.set_active(portable_cache);
         } else {
             gui_data.settings.check_button_settings_load_at_start.set_active(false);
         }
@@ -543,11 +678,21 @@ pub fn reset_configuration(gui_data: &GuiData, manual_clearing: bool) {
             entry_excluded_items.set_text("*\\.git\\*,*\\node_modules\\*,*\\lost+found\\*,*:\\windows\\*");
         }
     }
+    // Resetting reference directories
+    {
+        let entry_reference_directories = gui_data.upper_notebook.entry_reference_directories.clone();
+        entry_reference_directories.set_text("");
+    }
     // Resetting allowed extensions
     {
         let entry_allowed_extensions = gui_data.upper_notebook.entry_allowed_extensions.clone();
         entry_allowed_extensions.set_text("");
     }
+    // Resetting excluded extensions
+    {
+        let entry_excluded_extensions = gui_data.upper_notebook.entry_excluded_extensions.clone();
+        entry_excluded_extensions.set_text("");
+    }
 
     // Set settings
     {
@@ -558,9 +703,14 @@ pub fn reset_configuration(gui_data: &GuiData, manual_clearing: bool) {
         gui_data.settings.check_button_settings_show_preview_similar_images.set_active(true);
         gui_data.settings.check_button_settings_show_text_view.set_active(true);
         gui_data.settings.check_button_settings_hide_hard_links.set_active(true);
+        gui_data.settings.check_button_settings_relative_symlinks.set_active(false);
         gui_data.settings.check_button_settings_use_cache.set_active(true);
         gui_data.settings.check_button_settings_use_trash.set_active(false);
+        gui_data.settings.combo_box_settings_save_format.set_active_id(Some("txt"));
+        gui_data.settings.entry_settings_quarantine_dir.set_text("");
         gui_data.settings.entry_settings_cache_file_minimal_size.set_text("2097152");
+        gui_data.settings.entry_settings_cache_dir.set_text("");
+        gui_data.settings.check_button_settings_portable_cache.set_active(false);
     }
     if manual_clearing {
         add_text_to_text_view(&text_view_errors, "Current configuration was cleared.");